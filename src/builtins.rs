@@ -1,9 +1,11 @@
+use std::collections::HashSet;
+use std::path::Path;
+
 /// Known development artifact directory names that should be excluded from Time Machine backups.
 ///
 /// Some names are generic and may match non-artifact directories. These are
-/// annotated with "generic" below. Veiled only matches top-level directory
-/// names inside search paths, which limits false positives to projects that
-/// use these names for committed source code.
+/// annotated with "generic" below and listed in `GENERIC_DIRS`, and only
+/// match next to a project manifest (see `PROJECT_MARKERS`).
 const BUILTIN_DIRS: &[&str] = &[
     // JavaScript / TypeScript
     "node_modules",
@@ -40,8 +42,120 @@ const BUILTIN_DIRS: &[&str] = &[
     ".tmp",
 ];
 
-pub fn is_builtin(name: &str) -> bool {
-    BUILTIN_DIRS.contains(&name)
+/// The generic names from `BUILTIN_DIRS`, matched only in a directory that
+/// also holds one of the `PROJECT_MARKERS`.
+const GENERIC_DIRS: &[&str] = &["dist", "build", "out", "target", "vendor", "tmp"];
+
+/// Files marking a project root, next to which generic names are artifacts.
+const PROJECT_MARKERS: &[&str] = &[
+    "package.json",
+    "Cargo.toml",
+    "go.mod",
+    "pyproject.toml",
+    "setup.py",
+    "requirements.txt",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+    "composer.json",
+    "Gemfile",
+    "Package.swift",
+    "Podfile",
+    "CMakeLists.txt",
+    "Makefile",
+];
+
+/// Whether `dir` contains one of the `PROJECT_MARKERS`.
+pub fn has_project_marker(dir: &Path) -> bool {
+    PROJECT_MARKERS
+        .iter()
+        .any(|marker| dir.join(marker).is_file())
+}
+
+/// The directory names a scan matches: `(BUILTIN_DIRS ∪ custom ∪ globs) \
+/// ignored`, built from the `custom_builtins`, `builtin_globs` and
+/// `ignore_names` config keys.
+#[derive(Debug, Default)]
+pub struct BuiltinNames {
+    custom: HashSet<String>,
+    globs: Vec<String>,
+    ignored: HashSet<String>,
+}
+
+impl BuiltinNames {
+    pub fn new(custom: &[String], ignored: &[String]) -> Self {
+        Self {
+            custom: custom.iter().cloned().collect(),
+            globs: vec![],
+            ignored: ignored.iter().cloned().collect(),
+        }
+    }
+
+    /// Also matches names fitting any of `globs` (`*` for any run of
+    /// characters, `?` for exactly one).
+    pub fn with_globs(mut self, globs: &[String]) -> Self {
+        self.globs = globs.to_vec();
+        self
+    }
+
+    /// Returns true if `name` is a built-in artifact directory, one of the
+    /// custom names or matches a glob, and is not one of the ignored names.
+    pub fn contains(&self, name: &str) -> bool {
+        !self.ignored.contains(name)
+            && (BUILTIN_DIRS.contains(&name)
+                || self.custom.contains(name)
+                || self.globs.iter().any(|glob| glob_matches(glob, name)))
+    }
+
+    /// Whether `name` is one of the generic builtins that only count next to
+    /// a project manifest. Names listed in `custom_builtins` always match.
+    pub fn requires_project_marker(&self, name: &str) -> bool {
+        GENERIC_DIRS.contains(&name) && !self.custom.contains(name)
+    }
+
+    /// Whether the directory at `path` should be excluded by name: it is in
+    /// the set, and a generic name sits beside a project manifest.
+    pub fn matches_dir(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            return false;
+        };
+        self.contains(name)
+            && (!self.requires_project_marker(name)
+                || path.parent().is_some_and(has_project_marker))
+    }
+}
+
+/// Whether the whole of `name` matches `pattern`, where `*` matches any run
+/// of characters (including none) and `?` exactly one. Everything else,
+/// including `/`, matches literally.
+pub fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position after the last `*` seen, and the name index it resumed at.
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                backtrack = Some((p, n));
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_n)) => {
+                    p = star_p;
+                    n = star_n + 1;
+                    backtrack = Some((star_p, n));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
 }
 
 #[cfg(test)]
@@ -50,22 +164,102 @@ mod tests {
 
     #[test]
     fn matches_known_directory() {
-        assert!(is_builtin("node_modules"));
-        assert!(is_builtin("target"));
-        assert!(is_builtin(".venv"));
-        assert!(is_builtin("Pods"));
+        let builtins = BuiltinNames::default();
+        assert!(builtins.contains("node_modules"));
+        assert!(builtins.contains("target"));
+        assert!(builtins.contains(".venv"));
+        assert!(builtins.contains("Pods"));
     }
 
     #[test]
     fn rejects_unknown_directory() {
-        assert!(!is_builtin("src"));
-        assert!(!is_builtin("README.md"));
-        assert!(!is_builtin(""));
+        let builtins = BuiltinNames::default();
+        assert!(!builtins.contains("src"));
+        assert!(!builtins.contains("README.md"));
+        assert!(!builtins.contains(""));
     }
 
     #[test]
     fn match_is_case_sensitive() {
-        assert!(!is_builtin("Node_Modules"));
-        assert!(!is_builtin("TARGET"));
+        let builtins = BuiltinNames::new(&[".bazel-out".to_string()], &[]);
+        assert!(!builtins.contains("Node_Modules"));
+        assert!(!builtins.contains("TARGET"));
+        assert!(!builtins.contains(".Bazel-Out"));
+    }
+
+    #[test]
+    fn matches_custom_directory() {
+        let builtins =
+            BuiltinNames::new(&[".bazel-out".to_string(), ".cargo-cache".to_string()], &[]);
+        assert!(builtins.contains(".bazel-out"));
+        assert!(builtins.contains(".cargo-cache"));
+        assert!(builtins.contains("node_modules"));
+        assert!(!builtins.contains("src"));
+    }
+
+    #[test]
+    fn ignored_name_is_not_matched_even_if_builtin() {
+        let builtins = BuiltinNames::new(&[], &["vendor".to_string()]);
+        assert!(!builtins.contains("vendor"));
+        assert!(builtins.contains("node_modules"));
+    }
+
+    #[test]
+    fn matches_glob_patterns_on_whole_name() {
+        let builtins = BuiltinNames::new(&[], &[])
+            .with_globs(&["cmake-build-*".to_string(), "*.xcarchive".to_string()]);
+        assert!(builtins.contains("cmake-build-debug"));
+        assert!(builtins.contains("cmake-build-"));
+        assert!(builtins.contains("App 2024.xcarchive"));
+        assert!(!builtins.contains("cmake-build"));
+        assert!(!builtins.contains("my-cmake-build-debug"));
+        assert!(!builtins.contains("App.xcarchive.zip"));
+    }
+
+    #[test]
+    fn glob_matching_handles_question_marks_and_backtracking() {
+        assert!(glob_matches("out-?", "out-1"));
+        assert!(!glob_matches("out-?", "out-12"));
+        assert!(glob_matches("*-build-*-x", "a-build-b-build-c-x"));
+        assert!(glob_matches("**", ""));
+        assert!(!glob_matches("a*b", "acbd"));
+    }
+
+    #[test]
+    fn ignored_name_overrides_glob() {
+        let builtins = BuiltinNames::new(&[], &["cmake-build-keep".to_string()])
+            .with_globs(&["cmake-build-*".to_string()]);
+        assert!(!builtins.contains("cmake-build-keep"));
+        assert!(builtins.contains("cmake-build-release"));
+    }
+
+    #[test]
+    fn generic_names_require_a_project_marker() {
+        let builtins = BuiltinNames::default();
+        assert!(builtins.requires_project_marker("build"));
+        assert!(builtins.requires_project_marker("vendor"));
+        assert!(!builtins.requires_project_marker("node_modules"));
+        assert!(!BuiltinNames::new(&["build".to_string()], &[]).requires_project_marker("build"));
+    }
+
+    #[test]
+    fn matches_dir_checks_for_sibling_manifest() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let app = dir.path().join("app");
+        let notes = dir.path().join("notes");
+        std::fs::create_dir_all(app.join("build")).unwrap();
+        std::fs::create_dir_all(notes.join("build")).unwrap();
+        std::fs::write(app.join("package.json"), "{}").unwrap();
+
+        let builtins = BuiltinNames::default();
+        assert!(builtins.matches_dir(&app.join("build")));
+        assert!(!builtins.matches_dir(&notes.join("build")));
+        assert!(builtins.matches_dir(&notes.join("node_modules")));
+    }
+
+    #[test]
+    fn ignored_name_overrides_custom_name() {
+        let builtins = BuiltinNames::new(&[".bazel-out".to_string()], &[".bazel-out".to_string()]);
+        assert!(!builtins.contains(".bazel-out"));
     }
 }
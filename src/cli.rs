@@ -1,11 +1,49 @@
-use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
 
 #[derive(Debug, Parser)]
 #[command(name = "veiled", version, about)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Cli {
     #[arg(long, global = true)]
     pub verbose: bool,
 
+    /// Print nothing but errors and warnings
+    #[arg(long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Print machine-readable JSON instead of styled output
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Answer yes to confirmation prompts; required to confirm when stdin
+    /// is not a terminal
+    #[arg(long, global = true)]
+    pub assume_yes: bool,
+
+    /// Check for updates during run even when the config turns them off
+    #[arg(long, global = true, conflicts_with = "no_auto_update")]
+    pub auto_update: bool,
+
+    /// Skip the update check during run even when the config turns it on
+    #[arg(long, global = true)]
+    pub no_auto_update: bool,
+
+    /// How list, scan and status --top print paths
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub path_style: PathStyle,
+
+    /// Keep config, registry and logs in DIR instead of ~/.config/veiled
+    #[arg(long, global = true, value_name = "DIR")]
+    pub config_dir: Option<PathBuf>,
+
+    /// Read and write the config at FILE; the registry and logs stay put
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -13,35 +51,319 @@ pub struct Cli {
 #[derive(Debug, Subcommand)]
 pub enum Commands {
     /// Install binary and activate daemon
-    Start,
+    Start {
+        /// Print the launch agent plist that would be installed, without
+        /// writing it or touching launchd
+        #[arg(long, visible_alias = "dry-run")]
+        print: bool,
+    },
     /// Deactivate daemon and remove plist
     Stop,
     /// Run a scan manually
-    Run,
+    Run {
+        /// Only descend into directories modified within this window
+        /// (e.g. 30m, 24h, 7d)
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+        since: Option<Duration>,
+        /// Summary format (--json implies json)
+        #[arg(long, value_enum)]
+        format: Option<OutputFormat>,
+        /// Also descend into hidden directories that are not builtins
+        #[arg(long)]
+        include_hidden: bool,
+        /// Re-check every managed exclusion, even ones verified recently
+        #[arg(long)]
+        force_verify: bool,
+        /// Exclude new directories even when the batch is larger than the
+        /// configured warning thresholds, without asking, and ignore
+        /// --skip-if-recent
+        #[arg(long)]
+        force: bool,
+        /// Exit without scanning when the last full run finished within this
+        /// window (e.g. 30m, 12h)
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+        skip_if_recent: Option<Duration>,
+        /// Only discover directories under this path, which must be inside a
+        /// configured search path (repeatable)
+        #[arg(long, value_name = "PATH")]
+        only: Vec<String>,
+        /// Also scan this directory for this run only, without adding it to
+        /// `search_paths` (repeatable)
+        #[arg(long, value_name = "DIR")]
+        include_path: Vec<String>,
+        /// Report directories visited and skipped, git repos scanned and
+        /// scan time
+        #[arg(long)]
+        stats: bool,
+        /// Ask before excluding each newly discovered directory
+        #[arg(long, short)]
+        interactive: bool,
+        /// Replace per-path failure warnings with one count at the end
+        /// (they still show with --verbose)
+        #[arg(long)]
+        summary_only: bool,
+    },
+    /// List the directories a scan would find, with sizes, without excluding
+    /// anything
+    Scan {
+        /// Also scan this directory, without adding it to `search_paths`
+        /// (repeatable)
+        #[arg(long, value_name = "DIR")]
+        include_path: Vec<String>,
+    },
     /// List all paths excluded by veiled
-    List,
+    List {
+        /// Order by path (default) or by size, largest first
+        #[arg(long, value_enum)]
+        sort: Option<SortKey>,
+        /// Check each path's live exclusion state (ok, drift or missing)
+        #[arg(long)]
+        verify: bool,
+    },
     /// Remove all exclusions managed by veiled
     Reset {
         /// Skip confirmation prompt
         #[arg(long)]
         yes: bool,
+        /// List what would be removed without changing anything
+        #[arg(long, conflicts_with = "yes")]
+        dry_run: bool,
+    },
+    /// Remove all exclusions, the daemon, and veiled's config, registry and
+    /// logs
+    Uninstall {
+        /// Skip confirmation prompts
+        #[arg(long)]
+        yes: bool,
     },
-    /// Add a directory to the custom exclusion list
+    /// Add directories to the custom exclusion list
     Add {
-        /// Path to exclude
-        path: String,
+        /// Paths to exclude
+        #[arg(required = true, num_args = 1..)]
+        paths: Vec<String>,
+        /// Accept paths that don't exist yet; they are excluded by the next
+        /// `run` after they are created
+        #[arg(long)]
+        force: bool,
     },
-    /// Remove a directory from the exclusion list
+    /// Remove directories from the exclusion list
     Remove {
-        /// Path to restore
-        path: String,
+        /// Paths to restore
+        #[arg(required_unless_present_any = ["all_extra", "name"], num_args = 1..)]
+        paths: Vec<String>,
+        /// Remove every custom exclusion added with `add`
+        #[arg(long, conflicts_with = "paths")]
+        all_extra: bool,
+        /// Only lift the Time Machine exclusion; keep the path managed so a
+        /// later run re-applies it once the suspension ends
+        #[arg(long)]
+        keep_registry: bool,
+        /// Remove every managed exclusion whose directory has this name
+        /// (e.g. target)
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["paths", "all_extra", "keep_registry"])]
+        name: Option<String>,
+        /// With --name, skip the confirmation prompt
+        #[arg(long, requires = "name")]
+        yes: bool,
     },
+    /// Remove registry entries whose paths no longer exist
+    Prune,
     /// Show daemon state and exclusion stats
     Status {
         /// Recalculate saved space
         #[arg(long)]
         refresh: bool,
+        /// With --refresh, only re-measure directories whose mtime changed
+        #[arg(long, requires = "refresh")]
+        stale_only: bool,
+        /// Also list the N largest managed exclusions
+        #[arg(long, value_name = "N")]
+        top: Option<usize>,
+        /// Also break the total down by directory name
+        #[arg(long)]
+        by_type: bool,
+        /// Check every managed path against Time Machine and report drift
+        #[arg(long)]
+        verify: bool,
     },
     /// Update binary to the latest version
-    Update,
+    Update {
+        /// Only report whether an update is available (exits 10 if so)
+        #[arg(long)]
+        check: bool,
+    },
+    /// Check that every managed path is still excluded
+    Verify,
+    /// Restore exclusions that were lost, without scanning
+    Reapply,
+    /// Back up a managed directory again until it is resumed
+    Suspend {
+        /// Managed directory to stop excluding
+        path: String,
+    },
+    /// Exclude a suspended directory again
+    Resume {
+        /// Suspended directory to exclude
+        path: String,
+    },
+    /// Check Full Disk Access, the daemon, config and registry; exits
+    /// non-zero when any check fails
+    Doctor,
+    /// Exclude new builtin directories as soon as they are created
+    Watch,
+    /// Show the daemon's recent output
+    Logs {
+        /// Number of lines to show from each log (default 20)
+        #[arg(short = 'n', long)]
+        lines: Option<usize>,
+        /// Keep printing new lines as they are written
+        #[arg(short, long)]
+        follow: bool,
+    },
+    /// Export managed exclusions and scan settings
+    Export {
+        /// File to write (defaults to stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Merge settings from an export file into the current config
+    Import {
+        /// Export file to read
+        input: PathBuf,
+        /// Also exclude the exported paths that exist on this machine
+        #[arg(long)]
+        apply: bool,
+    },
+    /// Print a shell completion script to stdout
+    Completions {
+        /// Shell to generate completions for
+        shell: Shell,
+    },
+    /// Manage the directories scanned for projects
+    SearchPaths {
+        #[command(subcommand)]
+        action: SearchPathsAction,
+    },
+    /// Manage the paths skipped during scans
+    Ignore {
+        #[command(subcommand)]
+        action: IgnoreAction,
+    },
+    /// Inspect the configuration file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigAction {
+    /// Check the config file for errors without running a scan
+    Validate,
+    /// Print the location of the config file
+    Path,
+    /// Open the config file in $VISUAL or $EDITOR, then check it
+    Edit,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SearchPathsAction {
+    /// Add a directory to scan
+    Add {
+        /// Directory to add
+        path: String,
+    },
+    /// Stop scanning a directory
+    Remove {
+        /// Directory to remove
+        path: String,
+    },
+    /// List the configured search paths
+    List,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum IgnoreAction {
+    /// Skip a path during scans
+    Add {
+        /// Path to ignore
+        path: String,
+    },
+    /// Scan a previously ignored path again
+    Remove {
+        /// Path to stop ignoring
+        path: String,
+    },
+    /// List the ignored paths
+    List,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum PathStyle {
+    /// Full paths with the parent directory dimmed
+    #[default]
+    Pretty,
+    /// Raw absolute paths, one per line
+    Plain,
+    /// Paths relative to the search path containing them
+    Relative,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    #[default]
+    Path,
+    Size,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Parses a duration such as `90s`, `30m`, `24h` or `7d`.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let split = value.len() - value.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("invalid duration {value:?}: expected e.g. 30m, 24h, 7d"))?;
+    let secs_per_unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        _ => {
+            return Err(format!(
+                "invalid duration unit in {value:?}: use s, m, h or d"
+            ));
+        }
+    };
+    amount
+        .checked_mul(secs_per_unit)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("duration {value:?} is too large"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_accepts_each_unit() {
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30m"), Ok(Duration::from_mins(30)));
+        assert_eq!(parse_duration("24h"), Ok(Duration::from_hours(24)));
+        assert_eq!(parse_duration("7d"), Ok(Duration::from_hours(168)));
+    }
+
+    #[test]
+    fn parse_duration_rejects_missing_or_unknown_parts() {
+        assert!(parse_duration("24").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("3w").is_err());
+        assert!(parse_duration("-1h").is_err());
+    }
 }
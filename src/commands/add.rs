@@ -1,13 +1,65 @@
 use std::fs;
+use std::path::PathBuf;
 
 use console::style;
 
-use crate::{config, registry, tmutil};
+use super::remove::{clean_path, normalize_expanded};
+use crate::error::Error;
+use crate::{config, quiet, registry, tmutil};
 
-pub fn execute(path: &str) -> Result<(), Box<dyn std::error::Error>> {
+pub fn execute(paths: &[String], force: bool) -> Result<(), Error> {
+    let mut cfg_guard = config::Config::locked()?;
+    let mut cfg = cfg_guard.load()?;
+    let mut guard = registry::Registry::locked()?;
+    let mut reg = guard.load()?;
+
+    let mut added: Vec<PathBuf> = Vec::new();
+    let mut failed = 0usize;
+
+    for path in paths {
+        match add_one(path, force, &mut cfg, &mut reg) {
+            Ok(canonical) => added.push(canonical),
+            Err(e) => {
+                eprintln!("{} {e}", style("error:").red().bold());
+                failed += 1;
+            }
+        }
+    }
+
+    if !added.is_empty() {
+        cfg_guard.save(&cfg)?;
+        guard.save(&reg)?;
+    }
+
+    for path in &added {
+        if !quiet() {
+            println!("{} {}", style("Added:").bold(), path.display());
+        }
+    }
+
+    if added.is_empty() {
+        return Err(format!(
+            "failed to add {failed} {}",
+            if failed == 1 { "path" } else { "paths" }
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+fn add_one(
+    path: &str,
+    force: bool,
+    cfg: &mut config::Config,
+    reg: &mut registry::Registry,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
     let expanded = config::expand_tilde(path);
-    let canonical = fs::canonicalize(&expanded)
-        .map_err(|_| format!("{}: no such directory", expanded.display()))?;
+    let canonical = match fs::canonicalize(&expanded) {
+        Ok(canonical) => canonical,
+        Err(_) if force => return add_pending(&expanded, cfg),
+        Err(_) => return Err(format!("{}: no such directory", expanded.display()).into()),
+    };
 
     if !canonical.is_dir() {
         return Err(format!("{}: not a directory", canonical.display()).into());
@@ -15,13 +67,8 @@ pub fn execute(path: &str) -> Result<(), Box<dyn std::error::Error>> {
 
     let canonical_str = canonical.to_string_lossy().into_owned();
 
-    let mut cfg_guard = config::Config::locked()?;
-    let mut cfg = cfg_guard.load()?;
-    let mut guard = registry::Registry::locked()?;
-    let mut reg = guard.load()?;
-
     for entry in reg.list() {
-        if canonical_str != *entry && canonical_str.starts_with(&format!("{entry}/")) {
+        if canonical_str != entry && canonical_str.starts_with(&format!("{entry}/")) {
             eprintln!(
                 "{} {} is already covered by {}",
                 style("warning:").yellow().bold(),
@@ -32,17 +79,43 @@ pub fn execute(path: &str) -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    tmutil::add_exclusion(&canonical)?;
+    if !reg.contains(&canonical_str) && tmutil::is_excluded(&canonical) {
+        eprintln!(
+            "{} {} was already excluded from Time Machine before veiled",
+            style("note:").dim(),
+            canonical.display()
+        );
+        reg.add_pre_existing(&canonical_str);
+    } else {
+        tmutil::add_exclusion(&canonical, cfg.exclusion_mode)?;
+        reg.add(&canonical_str);
+    }
 
     if !cfg.extra_exclusions.contains(&canonical_str) {
         cfg.extra_exclusions.push(canonical_str.clone());
-        cfg_guard.save(&cfg)?;
     }
 
-    reg.add(&canonical_str);
-    guard.save(&reg)?;
+    Ok(canonical)
+}
+
+/// Records a directory that doesn't exist yet in `extra_exclusions` only;
+/// the scan in `run` excludes it and adds it to the registry once it exists.
+fn add_pending(
+    expanded: &PathBuf,
+    cfg: &mut config::Config,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let path = clean_path(&normalize_expanded(expanded)?);
+    let path_str = path.to_string_lossy().into_owned();
+
+    if !cfg.extra_exclusions.contains(&path_str) {
+        cfg.extra_exclusions.push(path_str);
+    }
 
-    println!("{} {}", style("Added:").bold(), canonical.display());
+    eprintln!(
+        "{} {} does not exist yet; it will be excluded by the next run after it is created",
+        style("note:").dim(),
+        path.display()
+    );
 
-    Ok(())
+    Ok(path)
 }
@@ -0,0 +1,18 @@
+use std::io::{self, Write};
+
+use clap::CommandFactory;
+use clap_complete::Shell;
+
+use crate::cli::Cli;
+use crate::error::Error;
+
+pub fn execute(shell: Shell) -> Result<(), Error> {
+    let mut cmd = Cli::command();
+    let name = cmd.get_name().to_string();
+
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut cmd, name, &mut script);
+    io::stdout().write_all(&script)?;
+
+    Ok(())
+}
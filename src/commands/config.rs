@@ -0,0 +1,242 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use console::style;
+
+use crate::cli::ConfigAction;
+use crate::config::{self, Config};
+use crate::error::Error;
+use crate::{daemon, quiet};
+
+/// A problem found while validating. Errors make the config unusable;
+/// warnings are worth fixing but do not stop a scan.
+#[derive(Debug, PartialEq, Eq)]
+enum Issue {
+    Error(String),
+    Warning(String),
+}
+
+pub fn execute(action: &ConfigAction) -> Result<(), Error> {
+    match action {
+        ConfigAction::Validate => validate(),
+        ConfigAction::Path => {
+            println!("{}", config::config_path()?.display());
+            Ok(())
+        }
+        ConfigAction::Edit => edit(),
+    }
+}
+
+fn validate() -> Result<(), Error> {
+    let path = config::config_path()?;
+    if !path.exists() {
+        if !quiet() {
+            println!(
+                "{} no config file at {}, defaults apply",
+                style("Valid:").green().bold(),
+                path.display()
+            );
+        }
+        return Ok(());
+    }
+
+    let issues = inspect(&path)?;
+    let errors = print_issues(&issues);
+    let warnings = issues.len() - errors;
+
+    if errors > 0 {
+        return Err(Error::Config(format!(
+            "{} is invalid: {errors} {}, {warnings} {}",
+            path.display(),
+            plural(errors, "error"),
+            plural(warnings, "warning")
+        )));
+    }
+
+    if !quiet() {
+        println!(
+            "{} {} ({warnings} {})",
+            style("Valid:").green().bold(),
+            path.display(),
+            plural(warnings, "warning")
+        );
+    }
+    Ok(())
+}
+
+/// Opens the config in `$VISUAL` or `$EDITOR` (falling back to `vi`),
+/// creating it with defaults first, and re-checks it once the editor exits.
+fn edit() -> Result<(), Error> {
+    let path = config::config_path()?;
+    config::load_from(&path)?;
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = Command::new(program)
+        .args(parts)
+        .arg(&path)
+        .status()
+        .map_err(|e| format!("failed to launch {program}: {e}"))?;
+    if !status.success() {
+        return Err(format!("{program} exited with {status}").into());
+    }
+
+    let errors = print_issues(&inspect(&path)?);
+    if errors > 0 {
+        eprintln!(
+            "{} {} still has {errors} {}; veiled falls back to defaults where it cannot parse it",
+            style("warning:").yellow().bold(),
+            path.display(),
+            plural(errors, "error")
+        );
+    }
+    Ok(())
+}
+
+fn inspect(path: &Path) -> Result<Vec<Issue>, Error> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| Error::Config(format!("failed to read {}: {e}", path.display())))?;
+    Ok(match config::parse(&content) {
+        Ok(config) => check(&config),
+        Err(e) => vec![Issue::Error(format!("failed to parse: {e}"))],
+    })
+}
+
+/// Prints each issue on stderr and returns how many are errors.
+fn print_issues(issues: &[Issue]) -> usize {
+    let mut errors = 0usize;
+    for issue in issues {
+        match issue {
+            Issue::Error(message) => {
+                errors += 1;
+                eprintln!("{} {message}", style("error:").red().bold());
+            }
+            Issue::Warning(message) => {
+                eprintln!("{} {message}", style("warning:").yellow().bold());
+            }
+        }
+    }
+    errors
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        noun.to_string()
+    } else {
+        format!("{noun}s")
+    }
+}
+
+/// Checks an already parsed (and tilde-expanded) config.
+fn check(config: &Config) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    for path in &config.search_paths {
+        if !Path::new(path).is_absolute() {
+            issues.push(Issue::Error(format!(
+                "search path {path} is not an absolute path"
+            )));
+        } else if !Path::new(path).is_dir() {
+            issues.push(Issue::Warning(format!("search path {path} does not exist")));
+        }
+    }
+
+    for path in &config.ignore_paths {
+        if path.trim().is_empty() {
+            issues.push(Issue::Error("ignore_paths contains an empty path".into()));
+        } else if !Path::new(path).is_absolute() {
+            issues.push(Issue::Error(format!(
+                "ignore path {path} is not an absolute path"
+            )));
+        }
+    }
+
+    for (search, ignore) in config.ignored_search_paths() {
+        issues.push(Issue::Warning(format!(
+            "search path {search} is inside ignore path {ignore} and will be skipped"
+        )));
+    }
+
+    if let Err(e) = daemon::check_schedule(config) {
+        issues.push(Issue::Error(e.to_string()));
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn config_with(search: &str, ignore: &str) -> Config {
+        Config {
+            search_paths: vec![search.to_string()],
+            ignore_paths: vec![ignore.to_string()],
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn valid_config_has_no_issues() {
+        let dir = TempDir::new().unwrap();
+        let config = config_with(&dir.path().to_string_lossy(), "/nonexistent/ignored");
+
+        assert!(check(&config).is_empty());
+    }
+
+    #[test]
+    fn relative_search_path_is_an_error() {
+        let config = config_with("Projects", "/nonexistent/ignored");
+
+        assert_eq!(
+            check(&config),
+            vec![Issue::Error(
+                "search path Projects is not an absolute path".into()
+            )]
+        );
+    }
+
+    #[test]
+    fn missing_search_path_is_a_warning() {
+        let config = config_with("/nonexistent/projects", "/nonexistent/ignored");
+
+        assert_eq!(
+            check(&config),
+            vec![Issue::Warning(
+                "search path /nonexistent/projects does not exist".into()
+            )]
+        );
+    }
+
+    #[test]
+    fn empty_ignore_path_is_an_error() {
+        let dir = TempDir::new().unwrap();
+        let config = config_with(&dir.path().to_string_lossy(), " ");
+
+        assert_eq!(
+            check(&config),
+            vec![Issue::Error("ignore_paths contains an empty path".into())]
+        );
+    }
+
+    #[test]
+    fn out_of_range_schedule_is_an_error() {
+        let dir = TempDir::new().unwrap();
+        let config = Config {
+            schedule_minute: 75,
+            ..config_with(&dir.path().to_string_lossy(), "/nonexistent/ignored")
+        };
+
+        assert_eq!(
+            check(&config),
+            vec![Issue::Error(
+                "invalid schedule_minute 75: expected 0-59".into()
+            )]
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use std::fs;
+
+use console::style;
+use serde::Serialize;
+
+use crate::error::Error;
+use crate::{config, daemon, quiet, registry, tmutil};
+
+/// The outcome of each health check, as printed by `doctor --json`.
+#[derive(Debug, Serialize)]
+#[allow(clippy::struct_excessive_bools)]
+struct Report {
+    fda: bool,
+    daemon_loaded: bool,
+    config_ok: bool,
+    registry_ok: bool,
+    binary_path_matches: bool,
+    healthy: bool,
+}
+
+impl Report {
+    fn checks(&self) -> [(&'static str, bool); 5] {
+        [
+            ("Full Disk Access", self.fda),
+            ("Daemon loaded", self.daemon_loaded),
+            ("Config parses", self.config_ok),
+            ("Registry parses", self.registry_ok),
+            ("Daemon runs this binary", self.binary_path_matches),
+        ]
+    }
+}
+
+pub fn execute(json: bool) -> Result<(), Error> {
+    let mut report = Report {
+        fda: tmutil::check_access().is_ok(),
+        daemon_loaded: daemon::is_installed()?,
+        config_ok: config_ok()?,
+        registry_ok: registry_ok()?,
+        binary_path_matches: daemon::stale_binary_path()?.is_none(),
+        healthy: false,
+    };
+    report.healthy = report.checks().iter().all(|(_, ok)| *ok);
+
+    if json {
+        println!("{}", serde_json::to_string(&report)?);
+    } else if !quiet() {
+        for (name, ok) in report.checks() {
+            let status = if ok {
+                style("ok").green()
+            } else {
+                style("fail").red().bold()
+            };
+            println!("{name:<24} {status}");
+        }
+    }
+
+    if report.healthy {
+        Ok(())
+    } else {
+        let failed = report.checks().iter().filter(|(_, ok)| !ok).count();
+        Err(format!(
+            "{failed} health {} failed",
+            if failed == 1 { "check" } else { "checks" }
+        )
+        .into())
+    }
+}
+
+/// A missing config is fine (defaults apply); an unparsable one is not.
+fn config_ok() -> Result<bool, Box<dyn std::error::Error>> {
+    Ok(match fs::read_to_string(config::config_path()?) {
+        Ok(content) => config::parse(&content).is_ok(),
+        Err(_) => true,
+    })
+}
+
+/// Parses the registry file directly, since loading it falls back to an
+/// empty registry on errors.
+fn registry_ok() -> Result<bool, Box<dyn std::error::Error>> {
+    Ok(match fs::read_to_string(registry::registry_path()?) {
+        Ok(content) if !content.trim().is_empty() => {
+            serde_json::from_str::<registry::Registry>(&content).is_ok()
+        }
+        _ => true,
+    })
+}
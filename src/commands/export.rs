@@ -0,0 +1,67 @@
+use std::fs;
+use std::path::PathBuf;
+
+use console::style;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::{config, registry};
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Portable snapshot of a veiled setup. Paths are stored with `~` in place
+/// of the home directory so the file can be shared across users.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportDocument {
+    pub schema_version: u32,
+    #[serde(default)]
+    pub search_paths: Vec<String>,
+    #[serde(default)]
+    pub ignore_paths: Vec<String>,
+    #[serde(default)]
+    pub extra_exclusions: Vec<String>,
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+fn collapse_all(paths: &[String]) -> Vec<String> {
+    paths.iter().map(|p| config::collapse_tilde(p)).collect()
+}
+
+pub fn execute(output: Option<&PathBuf>) -> Result<(), Error> {
+    let cfg = config::load()?;
+    let mut guard = registry::Registry::locked()?;
+    let reg = guard.load()?;
+    drop(guard);
+
+    let document = ExportDocument {
+        schema_version: SCHEMA_VERSION,
+        search_paths: collapse_all(&cfg.search_paths),
+        ignore_paths: collapse_all(&cfg.ignore_paths),
+        extra_exclusions: collapse_all(&cfg.extra_exclusions),
+        paths: collapse_all(&reg.list()),
+    };
+
+    let content = toml::to_string_pretty(&document)?;
+
+    match output {
+        Some(path) => {
+            fs::write(path, content)
+                .map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+            eprintln!(
+                "{} {} {} to {}",
+                style("Exported:").bold(),
+                document.paths.len(),
+                if document.paths.len() == 1 {
+                    "exclusion"
+                } else {
+                    "exclusions"
+                },
+                path.display()
+            );
+        }
+        None => print!("{content}"),
+    }
+
+    Ok(())
+}
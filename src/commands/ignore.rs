@@ -0,0 +1,153 @@
+use std::path::{Path, PathBuf};
+
+use console::style;
+
+use crate::cli::IgnoreAction;
+use crate::error::Error;
+use crate::{config, quiet};
+
+pub fn execute(action: &IgnoreAction) -> Result<(), Error> {
+    match action {
+        IgnoreAction::Add { path } => add(path),
+        IgnoreAction::Remove { path } => remove(path),
+        IgnoreAction::List => list(),
+    }
+    .map_err(Error::from)
+}
+
+fn resolve(path: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let expanded = config::expand_tilde(path);
+    if expanded.is_absolute() {
+        return Ok(expanded);
+    }
+    Ok(std::env::current_dir()?.join(expanded))
+}
+
+/// Whether `path` is inside a search path or contains one, i.e. whether
+/// ignoring it can affect a scan at all.
+fn overlaps_search_paths(path: &Path, search_paths: &[String]) -> bool {
+    search_paths.iter().any(|root| {
+        let root = Path::new(root);
+        path.starts_with(root) || root.starts_with(path)
+    })
+}
+
+fn add(path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let resolved = resolve(path)?;
+    let resolved_str = resolved.to_string_lossy().into_owned();
+    let display = config::collapse_tilde(&resolved_str);
+
+    let mut guard = config::Config::locked()?;
+    let mut cfg = guard.load()?;
+
+    if is_ignored(&cfg, &resolved) {
+        if !quiet() {
+            println!("{display} is already ignored");
+        }
+        return Ok(());
+    }
+
+    if !overlaps_search_paths(&resolved, &cfg.search_paths) {
+        eprintln!(
+            "{} {display} is outside every search path, so ignoring it has no effect",
+            style("warning:").yellow().bold()
+        );
+    }
+
+    cfg.ignore_paths.push(resolved_str);
+    guard.save(&cfg)?;
+
+    if !quiet() {
+        println!("{} {display}", style("Ignored:").bold());
+    }
+    Ok(())
+}
+
+fn is_ignored(cfg: &config::Config, path: &Path) -> bool {
+    cfg.ignore_paths.iter().any(|p| Path::new(p) == path)
+}
+
+/// Appends `paths` to `ignore_paths`, skipping ones already listed. Used by
+/// `run --interactive` for candidates declined for good.
+pub(super) fn persist(paths: &[PathBuf]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut guard = config::Config::locked()?;
+    let mut cfg = guard.load()?;
+    for path in paths {
+        if !is_ignored(&cfg, path) {
+            cfg.ignore_paths.push(path.to_string_lossy().into_owned());
+        }
+    }
+    guard.save(&cfg)
+}
+
+fn remove(path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let resolved = resolve(path)?;
+
+    let mut guard = config::Config::locked()?;
+    let mut cfg = guard.load()?;
+
+    let before = cfg.ignore_paths.len();
+    cfg.ignore_paths.retain(|p| Path::new(p) != resolved);
+
+    if cfg.ignore_paths.len() == before {
+        return Err(format!("{path} is not an ignored path").into());
+    }
+
+    guard.save(&cfg)?;
+
+    if !quiet() {
+        println!(
+            "{} {}",
+            style("Unignored:").bold(),
+            config::collapse_tilde(&resolved.to_string_lossy())
+        );
+    }
+    Ok(())
+}
+
+fn list() -> Result<(), Box<dyn std::error::Error>> {
+    let cfg = config::load()?;
+
+    if cfg.ignore_paths.is_empty() {
+        if !quiet() {
+            println!("{}", style("No ignored paths.").dim());
+        }
+        return Ok(());
+    }
+
+    for path in &cfg.ignore_paths {
+        println!("{}", config::collapse_tilde(path));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlaps_when_inside_search_path() {
+        let roots = vec!["/Users/dev/Projects".to_string()];
+        assert!(overlaps_search_paths(
+            Path::new("/Users/dev/Projects/vendor"),
+            &roots
+        ));
+    }
+
+    #[test]
+    fn overlaps_when_containing_search_path() {
+        let roots = vec!["/Users/dev/Projects".to_string()];
+        assert!(overlaps_search_paths(Path::new("/Users/dev"), &roots));
+    }
+
+    #[test]
+    fn does_not_overlap_unrelated_path_or_sibling_prefix() {
+        let roots = vec!["/Users/dev/Projects".to_string()];
+        assert!(!overlaps_search_paths(Path::new("/opt/cache"), &roots));
+        assert!(!overlaps_search_paths(
+            Path::new("/Users/dev/Projects-old"),
+            &roots
+        ));
+    }
+}
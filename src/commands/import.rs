@@ -0,0 +1,124 @@
+use std::fs;
+use std::path::Path;
+
+use console::style;
+
+use super::export::{ExportDocument, SCHEMA_VERSION};
+use crate::error::Error;
+use crate::{config, quiet, registry, tmutil};
+
+fn merge(target: &mut Vec<String>, imported: &[String]) -> usize {
+    let mut added = 0;
+    for path in imported {
+        let expanded = config::expand_tilde(path).to_string_lossy().into_owned();
+        if !target.contains(&expanded) {
+            target.push(expanded);
+            added += 1;
+        }
+    }
+    added
+}
+
+pub fn execute(input: &Path, apply: bool) -> Result<(), Error> {
+    let content = fs::read_to_string(input)
+        .map_err(|e| format!("failed to read {}: {e}", input.display()))?;
+    let document: ExportDocument = toml::from_str(&content)
+        .map_err(|e| format!("failed to parse {}: {e}", input.display()))?;
+
+    if document.schema_version > SCHEMA_VERSION {
+        return Err(format!(
+            "unsupported export schema version {} (this veiled supports up to {SCHEMA_VERSION})",
+            document.schema_version
+        )
+        .into());
+    }
+
+    let mut cfg_guard = config::Config::locked()?;
+    let mut cfg = cfg_guard.load()?;
+
+    let merged = merge(&mut cfg.search_paths, &document.search_paths)
+        + merge(&mut cfg.ignore_paths, &document.ignore_paths)
+        + merge(&mut cfg.extra_exclusions, &document.extra_exclusions);
+
+    if merged > 0 {
+        cfg_guard.save(&cfg)?;
+    }
+
+    if !quiet() {
+        println!(
+            "{} {} new config {}",
+            style("Imported:").bold(),
+            merged,
+            if merged == 1 { "entry" } else { "entries" }
+        );
+    }
+
+    if !apply {
+        return Ok(());
+    }
+
+    let mut guard = registry::Registry::locked()?;
+    let mut reg = guard.load()?;
+
+    let mut applied = 0usize;
+    for path in &document.paths {
+        let expanded = config::expand_tilde(path);
+        if !expanded.is_dir() {
+            eprintln!(
+                "{} {} does not exist, skipping",
+                style("note:").dim(),
+                expanded.display()
+            );
+            continue;
+        }
+
+        if let Err(e) = tmutil::add_exclusion(&expanded, cfg.exclusion_mode) {
+            eprintln!("{} {e}", style("warning:").yellow().bold());
+            continue;
+        }
+
+        reg.add(&expanded.to_string_lossy());
+        applied += 1;
+    }
+
+    if applied > 0 {
+        guard.save(&reg)?;
+    }
+
+    if !quiet() {
+        println!(
+            "{} {} {}",
+            style("Applied:").bold(),
+            applied,
+            if applied == 1 {
+                "exclusion"
+            } else {
+                "exclusions"
+            }
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_expands_tilde_and_deduplicates() {
+        let home = dirs::home_dir().unwrap().to_string_lossy().into_owned();
+        let mut target = vec![format!("{home}/Projects")];
+
+        let added = merge(
+            &mut target,
+            &["~/Projects".to_string(), "~/Work".to_string()],
+        );
+
+        assert_eq!(added, 1);
+        assert_eq!(
+            target,
+            vec![format!("{home}/Projects"), format!("{home}/Work")]
+        );
+    }
+}
@@ -1,27 +1,134 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
 use console::style;
+use serde::Serialize;
+
+use super::paths::PathPrinter;
+use crate::cli::SortKey;
+use crate::error::Error;
+use crate::{config, disksize, quiet, registry, tmutil};
+
+#[derive(Serialize)]
+struct Entry<'a> {
+    path: &'a str,
+    size_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<LiveState>,
+}
+
+/// What `tmutil` reports for a managed path right now. Suspended entries
+/// are meant to be backed up, so they are not checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum LiveState {
+    Ok,
+    Drift,
+    Missing,
+    Suspended,
+}
+
+impl LiveState {
+    fn label(self) -> String {
+        match self {
+            Self::Ok => style("ok").green().to_string(),
+            Self::Drift => style("drift").red().bold().to_string(),
+            Self::Missing => style("missing").dim().to_string(),
+            Self::Suspended => style("suspended").yellow().to_string(),
+        }
+    }
+}
 
-use crate::registry;
+/// Checks every path that is not suspended in `reg` against the live
+/// exclusion state, without changing it.
+fn live_states(
+    reg: &registry::Registry,
+    paths: &[String],
+) -> Result<Vec<LiveState>, Box<dyn std::error::Error>> {
+    let mode = config::load()?.exclusion_mode;
+    let now = registry::now_epoch();
+    let suspended: HashSet<&str> = reg
+        .entries()
+        .iter()
+        .filter(|e| e.is_suspended(now))
+        .map(|e| e.path.as_str())
+        .collect();
+    let targets: Vec<PathBuf> = paths
+        .iter()
+        .filter(|p| !suspended.contains(p.as_str()))
+        .map(PathBuf::from)
+        .collect();
+    let mut excluded = targets.iter().zip(tmutil::are_excluded(&targets, mode));
+    Ok(paths
+        .iter()
+        .map(|path| {
+            if suspended.contains(path.as_str()) {
+                return LiveState::Suspended;
+            }
+            match excluded.next() {
+                Some((_, true)) => LiveState::Ok,
+                Some((path, false)) if Path::new(path).exists() => LiveState::Drift,
+                _ => LiveState::Missing,
+            }
+        })
+        .collect())
+}
 
-pub fn execute() -> Result<(), Box<dyn std::error::Error>> {
+pub fn execute(sort: SortKey, verify: bool, json: bool) -> Result<(), Error> {
     let mut guard = registry::Registry::locked()?;
     let reg = guard.load()?;
-    let paths = reg.list();
 
-    if paths.is_empty() {
-        println!("{}", style("No exclusions managed by veiled.").dim());
+    let rows: Vec<(String, Option<u64>)> = match sort {
+        SortKey::Path => {
+            let mut paths = reg.list();
+            paths.sort();
+            paths.into_iter().map(|p| (p, None)).collect()
+        }
+        SortKey::Size => reg
+            .largest(config::load()?.size_mode)
+            .into_iter()
+            .map(|(p, size)| (p, Some(size)))
+            .collect(),
+    };
+    let states: Vec<Option<LiveState>> = if verify {
+        let paths: Vec<String> = rows.iter().map(|(p, _)| p.clone()).collect();
+        live_states(&reg, &paths)?.into_iter().map(Some).collect()
+    } else {
+        vec![None; rows.len()]
+    };
+
+    if json {
+        let paths: Vec<String> = rows.iter().map(|(p, _)| p.clone()).collect();
+        let sizes = match sort {
+            SortKey::Path => disksize::dir_sizes(&paths, config::load()?.size_mode),
+            SortKey::Size => rows.iter().map(|(_, s)| s.unwrap_or(0)).collect(),
+        };
+        let entries: Vec<Entry> = paths
+            .iter()
+            .zip(sizes)
+            .zip(states)
+            .map(|((path, size_bytes), state)| Entry {
+                path,
+                size_bytes,
+                state,
+            })
+            .collect();
+        println!("{}", serde_json::to_string(&entries)?);
         return Ok(());
     }
 
-    for path in paths {
-        let p = std::path::Path::new(&path);
-        match (
-            p.parent().and_then(|p| p.to_str()),
-            p.file_name().and_then(|n| n.to_str()),
-        ) {
-            (Some(parent), Some(name)) => {
-                println!("{}{name}", style(format!("{parent}/")).dim());
-            }
-            _ => println!("{path}"),
+    if rows.is_empty() {
+        if !quiet() {
+            println!("{}", style("No exclusions managed by veiled.").dim());
+        }
+        return Ok(());
+    }
+
+    let printer = PathPrinter::new(&config::load()?);
+    for ((path, size), state) in rows.into_iter().zip(states) {
+        match state {
+            Some(state) => println!("{}  {}", printer.row(&path, size), state.label()),
+            None => printer.print_row(&path, size),
         }
     }
 
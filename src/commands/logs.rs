@@ -0,0 +1,123 @@
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use console::style;
+
+use crate::daemon;
+use crate::error::Error;
+
+const DEFAULT_LINES: usize = 20;
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The last `n` lines of `content`, oldest first.
+fn tail(content: &str, n: usize) -> Vec<&str> {
+    let lines: Vec<&str> = content.lines().collect();
+    lines[lines.len().saturating_sub(n)..].to_vec()
+}
+
+fn print_header(path: &Path) {
+    println!("{}", style(format!("==> {} <==", path.display())).bold());
+}
+
+pub fn execute(lines: Option<usize>, follow: bool) -> Result<(), Error> {
+    let dir = daemon::log_dir()?;
+    let files: Vec<PathBuf> = [daemon::STDOUT_LOG, daemon::STDERR_LOG]
+        .iter()
+        .map(|name| dir.join(name))
+        .collect();
+
+    if !files.iter().any(|f| f.exists()) && !follow {
+        println!(
+            "{}",
+            style(format!(
+                "No daemon logs in {} yet. They appear after the first scheduled run.",
+                dir.display()
+            ))
+            .dim()
+        );
+        return Ok(());
+    }
+
+    let mut offsets = Vec::with_capacity(files.len());
+    let mut printed_any = false;
+    for file in &files {
+        let content = fs::read_to_string(file).unwrap_or_default();
+        offsets.push(content.len() as u64);
+        if content.is_empty() {
+            continue;
+        }
+        if printed_any {
+            println!();
+        }
+        printed_any = true;
+        print_header(file);
+        for line in tail(&content, lines.unwrap_or(DEFAULT_LINES)) {
+            println!("{line}");
+        }
+    }
+
+    if follow {
+        follow_files(&files, &mut offsets)?;
+    }
+
+    Ok(())
+}
+
+/// Polls `files` like `tail -f`, printing appended text under a header
+/// whenever the output switches files. A file that shrank was rotated, so
+/// it is read again from the start.
+fn follow_files(files: &[PathBuf], offsets: &mut [u64]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut last_printed: Option<usize> = None;
+
+    loop {
+        for (i, path) in files.iter().enumerate() {
+            let Ok(mut file) = fs::File::open(path) else {
+                offsets[i] = 0;
+                continue;
+            };
+            let len = file.metadata()?.len();
+            if len < offsets[i] {
+                offsets[i] = 0;
+            }
+            if len == offsets[i] {
+                continue;
+            }
+
+            file.seek(SeekFrom::Start(offsets[i]))?;
+            let mut appended = String::new();
+            file.read_to_string(&mut appended)?;
+            offsets[i] = len;
+
+            if last_printed != Some(i) {
+                print_header(path);
+                last_printed = Some(i);
+            }
+            print!("{appended}");
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tail_returns_last_lines_in_order() {
+        assert_eq!(tail("a\nb\nc\nd\n", 2), vec!["c", "d"]);
+    }
+
+    #[test]
+    fn tail_returns_everything_when_short() {
+        assert_eq!(tail("a\nb", 20), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn tail_handles_empty_and_zero() {
+        assert!(tail("", 5).is_empty());
+        assert!(tail("a\nb\n", 0).is_empty());
+    }
+}
@@ -1,9 +1,26 @@
 pub mod add;
+pub mod completions;
+pub mod config;
+pub mod doctor;
+pub mod export;
+pub mod ignore;
+pub mod import;
 pub mod list;
+pub mod logs;
+mod paths;
+pub mod prune;
+pub mod reapply;
 pub mod remove;
 pub mod reset;
+pub mod resume;
 pub mod run;
+pub mod scan;
+pub mod search_paths;
 pub mod start;
 pub mod status;
 pub mod stop;
+pub mod suspend;
+pub mod uninstall;
 pub mod update;
+pub mod verify;
+pub mod watch;
@@ -0,0 +1,131 @@
+use std::path::{Path, PathBuf};
+
+use console::style;
+
+use crate::cli::PathStyle;
+use crate::config::Config;
+use crate::disksize::{self, SizeUnits};
+use crate::path_style;
+
+/// Renders paths in the style chosen with `--path-style`.
+pub(super) struct PathPrinter {
+    style: PathStyle,
+    search_paths: Vec<PathBuf>,
+    units: SizeUnits,
+}
+
+impl PathPrinter {
+    /// A printer for the global `--path-style`, taking the search paths and
+    /// `size_units` from `config`.
+    pub(super) fn new(config: &Config) -> Self {
+        let style = path_style();
+        let search_paths = if style == PathStyle::Relative {
+            config.search_paths.iter().map(PathBuf::from).collect()
+        } else {
+            vec![]
+        };
+        Self {
+            style,
+            search_paths,
+            units: config.size_units,
+        }
+    }
+
+    pub(super) fn render(&self, path: &str) -> String {
+        match self.style {
+            PathStyle::Pretty => {
+                let p = Path::new(path);
+                match (
+                    p.parent().and_then(|p| p.to_str()),
+                    p.file_name().and_then(|n| n.to_str()),
+                ) {
+                    (Some(parent), Some(name)) => {
+                        format!("{}{name}", style(format!("{parent}/")).dim())
+                    }
+                    _ => path.to_string(),
+                }
+            }
+            PathStyle::Plain => path.to_string(),
+            PathStyle::Relative => relative_to(Path::new(path), &self.search_paths)
+                .map_or_else(|| path.to_string(), |rel| rel.display().to_string()),
+        }
+    }
+
+    /// Prints `path` after a right-aligned size column when a size is given.
+    pub(super) fn print_row(&self, path: &str, size: Option<u64>) {
+        println!("{}", self.row(path, size));
+    }
+
+    pub(super) fn row(&self, path: &str, size: Option<u64>) -> String {
+        let size = size
+            .map(|b| format!("{:>10}  ", disksize::format_size(b, self.units)))
+            .unwrap_or_default();
+        format!("{size}{}", self.render(path))
+    }
+}
+
+/// `path` relative to the deepest search path containing it, or `None`
+/// when it is outside (or is itself) every search path.
+fn relative_to(path: &Path, search_paths: &[PathBuf]) -> Option<PathBuf> {
+    search_paths
+        .iter()
+        .filter_map(|root| path.strip_prefix(root).ok())
+        .filter(|rel| !rel.as_os_str().is_empty())
+        .min_by_key(|rel| rel.components().count())
+        .map(Path::to_path_buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn printer(style: PathStyle, search_paths: &[&str]) -> PathPrinter {
+        PathPrinter {
+            style,
+            search_paths: search_paths.iter().map(PathBuf::from).collect(),
+            units: SizeUnits::Binary,
+        }
+    }
+
+    #[test]
+    fn pretty_keeps_the_full_path() {
+        let rendered = printer(PathStyle::Pretty, &[]).render("/Users/dev/app/node_modules");
+        assert_eq!(
+            console::strip_ansi_codes(&rendered),
+            "/Users/dev/app/node_modules"
+        );
+    }
+
+    #[test]
+    fn plain_prints_the_raw_path() {
+        assert_eq!(
+            printer(PathStyle::Plain, &[]).render("/Users/dev/app/node_modules"),
+            "/Users/dev/app/node_modules"
+        );
+    }
+
+    #[test]
+    fn relative_strips_the_nearest_search_path() {
+        let printer = printer(
+            PathStyle::Relative,
+            &["/Users/dev", "/Users/dev/work", "/Volumes/src"],
+        );
+
+        assert_eq!(printer.render("/Users/dev/work/api/target"), "api/target");
+        assert_eq!(
+            printer.render("/Users/dev/app/node_modules"),
+            "app/node_modules"
+        );
+    }
+
+    #[test]
+    fn relative_falls_back_to_absolute_outside_search_paths() {
+        let printer = printer(PathStyle::Relative, &["/Users/dev/work"]);
+
+        assert_eq!(
+            printer.render("/Users/dev/workshop/dist"),
+            "/Users/dev/workshop/dist"
+        );
+        assert_eq!(printer.render("/Users/dev/work"), "/Users/dev/work");
+    }
+}
@@ -0,0 +1,43 @@
+use console::style;
+
+use crate::error::Error;
+use crate::{config, quiet, registry, verbose};
+
+pub fn execute() -> Result<(), Error> {
+    let mut guard = registry::Registry::locked()?;
+    let mut reg = guard.load()?;
+
+    let pruned = reg.prune_missing();
+
+    if pruned.is_empty() {
+        if !quiet() {
+            println!("{}", style("No stale exclusions.").dim());
+        }
+        return Ok(());
+    }
+
+    if verbose() {
+        for entry in &pruned {
+            eprintln!("{} pruning stale entry: {entry}", style("verbose:").dim());
+        }
+    }
+
+    let total = reg.refresh_sizes(config::load()?.size_mode).total;
+    reg.saved_bytes = if total > 0 { Some(total) } else { None };
+    guard.save(&reg)?;
+
+    if !quiet() {
+        println!(
+            "{} {} stale {}",
+            style("Pruned:").bold(),
+            pruned.len(),
+            if pruned.len() == 1 {
+                "exclusion"
+            } else {
+                "exclusions"
+            }
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use std::path::{Path, PathBuf};
+
+use console::style;
+
+use super::run::PathWarnings;
+use crate::error::Error;
+use crate::tmutil::ExclusionMode;
+use crate::{config, quiet, registry, tmutil, verbose};
+
+/// Checks every managed path now, ignoring the verification cooldown `run`
+/// uses, and restores the exclusions that were lost.
+pub fn execute() -> Result<(), Error> {
+    let mode = config::load()?.exclusion_mode;
+    let mut guard = registry::Registry::locked()?;
+    let mut reg = guard.load()?;
+    if reg.entries().is_empty() {
+        if !quiet() {
+            println!("{}", style("No exclusions managed by veiled.").dim());
+        }
+        return Ok(());
+    }
+
+    let verification = reapply_lost(&mut reg, mode, 0, &PathWarnings::new(false));
+    guard.save(&reg)?;
+
+    if verification.re_applied < verification.lost {
+        return Err(Error::Tmutil(format!(
+            "could not restore {} lost {}",
+            verification.lost,
+            if verification.lost == 1 {
+                "exclusion"
+            } else {
+                "exclusions"
+            }
+        )));
+    }
+
+    if !quiet() {
+        if verification.re_applied == 0 {
+            println!(
+                "{} {} {} still excluded",
+                style("Checked:").bold(),
+                verification.checked,
+                if verification.checked == 1 {
+                    "path"
+                } else {
+                    "paths"
+                }
+            );
+        } else {
+            println!(
+                "{} {} lost {}",
+                style("Restored:").bold(),
+                verification.re_applied,
+                if verification.re_applied == 1 {
+                    "exclusion"
+                } else {
+                    "exclusions"
+                }
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// How many managed paths `reapply_lost` queried, how many of those had
+/// lost their exclusion, and how many of those got it back.
+#[derive(Debug, Default)]
+pub(super) struct Verification {
+    pub(super) checked: usize,
+    pub(super) lost: usize,
+    pub(super) re_applied: usize,
+}
+
+/// Re-excludes managed paths that lost their exclusion, skipping the ones
+/// confirmed within the last `cooldown_secs` and the ones no longer on disk.
+pub(super) fn reapply_lost(
+    reg: &mut registry::Registry,
+    mode: ExclusionMode,
+    cooldown_secs: i64,
+    warnings: &PathWarnings,
+) -> Verification {
+    let now = registry::now_epoch();
+    let entries: Vec<String> = reg
+        .unverified(now, cooldown_secs)
+        .into_iter()
+        .filter(|path| Path::new(path).exists())
+        .collect();
+    if verbose() {
+        let skipped = reg.entries().len().saturating_sub(entries.len());
+        if skipped > 0 {
+            eprintln!(
+                "{} skipping {skipped} recently verified {}",
+                style("verbose:").dim(),
+                if skipped == 1 { "path" } else { "paths" }
+            );
+        }
+    }
+    if entries.is_empty() {
+        return Verification::default();
+    }
+
+    let paths: Vec<PathBuf> = entries.iter().map(PathBuf::from).collect();
+    let status = tmutil::are_excluded(&paths, mode);
+
+    let (kept, lost): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .zip(status)
+        .partition(|(_, excluded)| *excluded);
+    let kept: Vec<String> = kept.into_iter().map(|(path, _)| path).collect();
+    let lost: Vec<String> = lost.into_iter().map(|(path, _)| path).collect();
+    reg.mark_verified(&kept, now);
+
+    let mut verification = Verification {
+        checked: paths.len(),
+        lost: lost.len(),
+        re_applied: 0,
+    };
+    if lost.is_empty() {
+        return verification;
+    }
+
+    let lost_paths: Vec<PathBuf> = lost.iter().map(PathBuf::from).collect();
+    if let Err(e) = tmutil::add_exclusions(&lost_paths, mode) {
+        warnings.warn(&format!("batch re-apply failed: {e}"), lost.len());
+        return verification;
+    }
+    reg.mark_verified(&lost, now);
+    verification.re_applied = lost.len();
+    verification
+}
@@ -2,29 +2,224 @@ use std::path::{Component, Path, PathBuf};
 
 use console::style;
 
-use crate::{config, disksize, registry, tmutil, verbose};
+use super::suspend;
+use crate::error::Error;
+use crate::{config, disksize, prompt, quiet, registry, tmutil, verbose};
 
-pub fn execute(path: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let expanded = config::expand_tilde(path);
+/// With `keep_registry`, each path's exclusion is lifted but its entry stays,
+/// suspended for `suspend_hours` so it can be backed up before `run`
+/// re-applies it.
+pub fn execute(paths: &[String], all_extra: bool, keep_registry: bool) -> Result<(), Error> {
+    let mut cfg_guard = config::Config::locked()?;
+    let mut cfg = cfg_guard.load()?;
+    let mut guard = registry::Registry::locked()?;
+    let mut reg = guard.load()?;
 
-    let (lookup_path, exists) = match expanded.canonicalize() {
-        Ok(canonical) => (canonical, true),
-        Err(_) => (clean_path(&normalize_expanded(&expanded)?), false),
+    let targets: Vec<String> = if all_extra {
+        cfg.extra_exclusions.clone()
+    } else {
+        paths.to_vec()
     };
 
-    let lookup_str = lookup_path.to_string_lossy().into_owned();
+    if targets.is_empty() {
+        if !quiet() {
+            println!("{}", style("No custom exclusions to remove.").dim());
+        }
+        return Ok(());
+    }
+
+    let mut removed: Vec<PathBuf> = Vec::new();
+    let mut failed = 0usize;
+
+    for path in &targets {
+        let result = if keep_registry {
+            let until = registry::now_epoch() + i64::from(cfg.suspend_hours) * 3600;
+            suspend::suspend_path(path, &cfg, &mut reg, Some(until))
+        } else {
+            remove_one(path, &mut cfg, &mut reg)
+        };
+        match result {
+            Ok(lookup_path) => removed.push(lookup_path),
+            Err(e) => {
+                eprintln!("{} {e}", style("error:").red().bold());
+                failed += 1;
+            }
+        }
+    }
+
+    if !removed.is_empty() {
+        cfg_guard.save(&cfg)?;
+        guard.save(&reg)?;
+    }
+
+    for path in &removed {
+        if quiet() {
+            break;
+        }
+        if keep_registry {
+            println!(
+                "{} {} (excluded again by the first run after {}h)",
+                style("Suspended:").bold(),
+                path.display(),
+                cfg.suspend_hours
+            );
+        } else {
+            println!("{} {}", style("Removed:").bold(), path.display());
+        }
+    }
 
+    if removed.is_empty() {
+        return Err(format!(
+            "failed to remove {failed} {}",
+            if failed == 1 { "path" } else { "paths" }
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Managed paths whose last component is exactly `name`.
+fn named(reg: &registry::Registry, name: &str) -> Vec<String> {
+    reg.list()
+        .into_iter()
+        .filter(|p| Path::new(p).file_name().is_some_and(|n| n == name))
+        .collect()
+}
+
+/// Removes every managed exclusion whose directory is called `name`, such
+/// as all `target` dirs, after a confirmation unless `yes`. Existing paths
+/// are cleared in one tmutil batch, falling back to one call per path.
+pub fn execute_by_name(name: &str, yes: bool) -> Result<(), Error> {
+    let cfg = config::load()?;
+    // Config before registry, in the order every other command locks them.
     let mut cfg_guard = config::Config::locked()?;
-    let mut cfg = cfg_guard.load()?;
+    let mut user_cfg = cfg_guard.load()?;
     let mut guard = registry::Registry::locked()?;
     let mut reg = guard.load()?;
 
-    if !reg.contains(&lookup_str) {
+    let matching = named(&reg, name);
+    if matching.is_empty() {
+        if !quiet() {
+            println!(
+                "{}",
+                style(format!("No managed exclusions named {name}.")).dim()
+            );
+        }
+        return Ok(());
+    }
+
+    let noun = if matching.len() == 1 {
+        "exclusion"
+    } else {
+        "exclusions"
+    };
+    if !yes && !prompt::confirm(&format!("Remove {} {name} {noun}?", matching.len()))? {
+        if !quiet() {
+            println!("{}", style("Aborted.").dim());
+        }
+        return Ok(());
+    }
+
+    let existing: Vec<PathBuf> = matching
+        .iter()
+        .map(PathBuf::from)
+        .filter(|p| p.exists())
+        .collect();
+    let mut failed: Vec<String> = Vec::new();
+    if let Err(e) = tmutil::remove_exclusions(&existing, cfg.exclusion_mode) {
+        eprintln!(
+            "{} batch removal failed, retrying individually: {e}",
+            style("warning:").yellow().bold()
+        );
+        for path in &existing {
+            if let Err(e) = tmutil::remove_exclusion(path, cfg.exclusion_mode) {
+                eprintln!(
+                    "{} {}: {e}",
+                    style("warning:").yellow().bold(),
+                    path.display()
+                );
+                failed.push(path.to_string_lossy().into_owned());
+            }
+        }
+    }
+
+    let mut freed = 0u64;
+    for path in matching.iter().filter(|p| !failed.contains(p)) {
+        freed = freed.saturating_add(disksize::dir_size(Path::new(path), cfg.size_mode));
+        reg.remove(path);
+    }
+    if freed > 0 {
+        reg.saved_bytes = reg.saved_bytes.map(|b| b.saturating_sub(freed));
+    }
+    guard.save(&reg)?;
+
+    let before = user_cfg.extra_exclusions.len();
+    user_cfg
+        .extra_exclusions
+        .retain(|p| failed.contains(p) || !matching.contains(p));
+    if user_cfg.extra_exclusions.len() < before {
+        cfg_guard.save(&user_cfg)?;
+    }
+
+    let removed = matching.len() - failed.len();
+    if removed == 0 {
+        return Err(Error::Tmutil(format!(
+            "failed to remove {} {name} {noun}",
+            failed.len()
+        )));
+    }
+    if !quiet() {
+        println!(
+            "{} {removed} {name} {}{}",
+            style("Removed:").bold(),
+            if removed == 1 {
+                "exclusion"
+            } else {
+                "exclusions"
+            },
+            if failed.is_empty() {
+                String::new()
+            } else {
+                format!(", {} failed", failed.len())
+            }
+        );
+    }
+    Ok(())
+}
+
+/// Resolves `path` to the form the registry stores, and whether it exists.
+pub(super) fn lookup(path: &str) -> Result<(PathBuf, bool), Box<dyn std::error::Error>> {
+    let expanded = config::expand_tilde(path);
+    Ok(match expanded.canonicalize() {
+        Ok(canonical) => (canonical, true),
+        Err(_) => (clean_path(&normalize_expanded(&expanded)?), false),
+    })
+}
+
+fn remove_one(
+    path: &str,
+    cfg: &mut config::Config,
+    reg: &mut registry::Registry,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let (lookup_path, exists) = lookup(path)?;
+
+    let lookup_str = lookup_path.to_string_lossy().into_owned();
+
+    if !reg.contains(&lookup_str) && !cfg.extra_exclusions.contains(&lookup_str) {
         return Err(format!("{}: not managed by veiled", lookup_path.display()).into());
     }
 
+    if reg.is_pre_existing(&lookup_str) {
+        eprintln!(
+            "{} {} was excluded before veiled managed it; it will be backed up again",
+            style("warning:").yellow().bold(),
+            lookup_path.display()
+        );
+    }
+
     if exists {
-        if let Err(e) = tmutil::remove_exclusion(&lookup_path) {
+        if let Err(e) = tmutil::remove_exclusion(&lookup_path, cfg.exclusion_mode) {
             eprintln!(
                 "{} {}: {e}",
                 style("warning:").yellow().bold(),
@@ -39,25 +234,19 @@ pub fn execute(path: &str) -> Result<(), Box<dyn std::error::Error>> {
         );
     }
 
-    if let Some(pos) = cfg.extra_exclusions.iter().position(|p| p == &lookup_str) {
-        cfg.extra_exclusions.remove(pos);
-        cfg_guard.save(&cfg)?;
-    }
+    cfg.extra_exclusions.retain(|p| p != &lookup_str);
 
-    let removed_size = disksize::dir_size(&lookup_path);
+    let removed_size = disksize::dir_size(&lookup_path, cfg.size_mode);
     if removed_size > 0 {
         reg.saved_bytes = Some(reg.saved_bytes.unwrap_or(0).saturating_sub(removed_size));
     }
 
     reg.remove(&lookup_str);
-    guard.save(&reg)?;
 
-    println!("{} {}", style("Removed:").bold(), lookup_path.display());
-
-    Ok(())
+    Ok(lookup_path)
 }
 
-fn normalize_expanded(path: &PathBuf) -> Result<PathBuf, Box<dyn std::error::Error>> {
+pub(super) fn normalize_expanded(path: &PathBuf) -> Result<PathBuf, Box<dyn std::error::Error>> {
     if path.is_absolute() {
         Ok(path.clone())
     } else {
@@ -67,7 +256,7 @@ fn normalize_expanded(path: &PathBuf) -> Result<PathBuf, Box<dyn std::error::Err
     }
 }
 
-fn clean_path(path: &Path) -> PathBuf {
+pub(super) fn clean_path(path: &Path) -> PathBuf {
     let mut result = PathBuf::new();
     for component in path.components() {
         match component {
@@ -85,6 +274,21 @@ fn clean_path(path: &Path) -> PathBuf {
 mod tests {
     use super::*;
 
+    #[test]
+    fn named_matches_leaf_name_exactly() {
+        let mut reg = registry::Registry::default();
+        reg.add("/p/app/target");
+        reg.add("/p/api/target");
+        reg.add("/p/app/node_modules");
+        reg.add("/p/target-old");
+        reg.add("/p/target/cache");
+
+        let mut found = named(&reg, "target");
+        found.sort();
+
+        assert_eq!(found, vec!["/p/api/target", "/p/app/target"]);
+    }
+
     #[test]
     fn clean_path_resolves_parent_dir() {
         assert_eq!(clean_path(Path::new("/a/b/../c")), Path::new("/a/c"));
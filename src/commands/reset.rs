@@ -1,41 +1,98 @@
-use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 
 use console::style;
 
-use crate::{config, registry, tmutil};
+use crate::disksize::SizeUnits;
+use crate::error::Error;
+use crate::{config, disksize, prompt, quiet, registry, tmutil};
 
-pub fn execute(yes: bool) -> Result<(), Box<dyn std::error::Error>> {
-    let snapshot = {
+/// How many of the largest paths the preview lists before summarizing.
+const PREVIEW_PATHS: usize = 5;
+
+pub fn execute(yes: bool, dry_run: bool) -> Result<(), Error> {
+    if dry_run {
+        return preview_all();
+    }
+    remove_all(yes).map(drop)
+}
+
+/// Lists every exclusion `reset` would remove and the space it would give
+/// back to Time Machine, without touching tmutil, the registry or config.
+fn preview_all() -> Result<(), Error> {
+    let config = config::load()?;
+    let sized = {
         let mut guard = registry::Registry::locked()?;
         let reg = guard.load()?;
-        reg.list().to_vec()
+        reg.largest(config.size_mode)
     };
 
-    if snapshot.is_empty() {
-        println!("{}", style("No exclusions to remove.").dim());
+    if sized.is_empty() {
+        if !quiet() {
+            println!("{}", style("No exclusions to remove.").dim());
+        }
         return Ok(());
     }
 
-    if !yes {
-        print!(
-            "Remove {} {}? [y/N] ",
-            snapshot.len(),
-            if snapshot.len() == 1 {
-                "exclusion"
-            } else {
-                "exclusions"
-            }
+    let (existing, missing): (Vec<_>, Vec<_>) =
+        sized.iter().partition(|(path, _)| Path::new(path).exists());
+    let total = existing
+        .iter()
+        .map(|(_, size)| *size)
+        .fold(0u64, u64::saturating_add);
+
+    println!(
+        "{} {} {} ({} reclaimed)",
+        style("Would remove:").bold(),
+        sized.len(),
+        if sized.len() == 1 {
+            "exclusion"
+        } else {
+            "exclusions"
+        },
+        disksize::format_size(total, config.size_units)
+    );
+    for (path, size) in &existing {
+        println!(
+            "{:>12}  {path}",
+            disksize::format_size(*size, config.size_units)
         );
-        io::stdout().flush()?;
+    }
+    for (path, _) in &missing {
+        println!("{:>12}  {path}", style("missing").dim());
+    }
+    if !quiet() {
+        println!("{}", style("Dry run: nothing was changed.").dim());
+    }
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
+    Ok(())
+}
 
-        if !input.trim().eq_ignore_ascii_case("y") {
+/// Removes every managed exclusion after a preview and confirmation.
+/// Returns `false` when the user declined, so callers can stop there.
+pub(super) fn remove_all(yes: bool) -> Result<bool, Error> {
+    let config = config::load()?;
+    let (snapshot, sized) = {
+        let mut guard = registry::Registry::locked()?;
+        let reg = guard.load()?;
+        (reg.list(), reg.largest(config.size_mode))
+    };
+
+    if snapshot.is_empty() {
+        if !quiet() {
+            println!("{}", style("No exclusions to remove.").dim());
+        }
+        return Ok(true);
+    }
+
+    if !quiet() {
+        print_preview(&sized, config.size_units);
+    }
+
+    if !yes && !confirm(snapshot.len())? {
+        if !quiet() {
             println!("{}", style("Aborted.").dim());
-            return Ok(());
         }
+        return Ok(false);
     }
 
     let mut cfg_guard = config::Config::locked()?;
@@ -52,13 +109,13 @@ pub fn execute(yes: bool) -> Result<(), Box<dyn std::error::Error>> {
     let mut removed = missing.len();
     let mut failed: Vec<String> = Vec::new();
 
-    if let Err(e) = tmutil::remove_exclusions(&existing_paths) {
+    if let Err(e) = tmutil::remove_exclusions(&existing_paths, cfg.exclusion_mode) {
         eprintln!(
             "{} batch removal failed, retrying individually: {e}",
             style("warning:").yellow().bold()
         );
         for path in &existing {
-            if let Err(e) = tmutil::remove_exclusion(path.as_ref()) {
+            if let Err(e) = tmutil::remove_exclusion(path.as_ref(), cfg.exclusion_mode) {
                 eprintln!("{} {path}: {e}", style("warning:").yellow().bold());
                 failed.push((*path).clone());
             } else {
@@ -85,30 +142,73 @@ pub fn execute(yes: bool) -> Result<(), Box<dyn std::error::Error>> {
     reg.saved_bytes = None;
     guard.save(&reg)?;
 
-    if failed.is_empty() {
-        println!(
-            "{} {} {}",
-            style("Removed:").bold(),
-            removed,
-            if removed == 1 {
-                "exclusion"
-            } else {
-                "exclusions"
-            }
-        );
-    } else {
+    if !quiet() {
+        if failed.is_empty() {
+            println!(
+                "{} {} {}",
+                style("Removed:").bold(),
+                removed,
+                if removed == 1 {
+                    "exclusion"
+                } else {
+                    "exclusions"
+                }
+            );
+        } else {
+            println!(
+                "{} {} {}, {} failed",
+                style("Removed:").bold(),
+                removed,
+                if removed == 1 {
+                    "exclusion"
+                } else {
+                    "exclusions"
+                },
+                failed.len()
+            );
+        }
+    }
+
+    Ok(true)
+}
+
+fn confirm(count: usize) -> Result<bool, Box<dyn std::error::Error>> {
+    prompt::confirm(&format!(
+        "Remove {count} {}?",
+        if count == 1 {
+            "exclusion"
+        } else {
+            "exclusions"
+        }
+    ))
+}
+
+/// Lists the total size of what `reset` is about to undo and its largest
+/// paths, so the prompt can be answered with some context.
+fn print_preview(sized: &[(String, u64)], units: SizeUnits) {
+    let total = sized
+        .iter()
+        .map(|(_, s)| *s)
+        .fold(0u64, u64::saturating_add);
+
+    println!(
+        "{} {} {} ({})",
+        style("Resetting:").bold(),
+        sized.len(),
+        if sized.len() == 1 {
+            "exclusion"
+        } else {
+            "exclusions"
+        },
+        disksize::format_size(total, units)
+    );
+    for (path, size) in sized.iter().take(PREVIEW_PATHS) {
+        println!("{:>12}  {path}", disksize::format_size(*size, units));
+    }
+    if sized.len() > PREVIEW_PATHS {
         println!(
-            "{} {} {}, {} failed",
-            style("Removed:").bold(),
-            removed,
-            if removed == 1 {
-                "exclusion"
-            } else {
-                "exclusions"
-            },
-            failed.len()
+            "{}",
+            style(format!("... and {} more", sized.len() - PREVIEW_PATHS)).dim()
         );
     }
-
-    Ok(())
 }
@@ -0,0 +1,33 @@
+use console::style;
+
+use super::remove::lookup;
+use crate::error::Error;
+use crate::{config, quiet, registry, tmutil};
+
+/// Excludes a suspended path again and clears its suspension.
+pub fn execute(path: &str) -> Result<(), Error> {
+    let cfg = config::load()?;
+    let mut guard = registry::Registry::locked()?;
+    let mut reg = guard.load()?;
+
+    let (lookup_path, exists) = lookup(path)?;
+    let lookup_str = lookup_path.to_string_lossy().into_owned();
+
+    let suspended = reg
+        .entries()
+        .iter()
+        .any(|e| e.path == lookup_str && e.suspended);
+    if !suspended {
+        return Err(format!("{}: not suspended", lookup_path.display()).into());
+    }
+    if exists {
+        tmutil::add_exclusion(&lookup_path, cfg.exclusion_mode)?;
+    }
+    reg.resume(&lookup_str);
+    guard.save(&reg)?;
+
+    if !quiet() {
+        println!("{} {}", style("Resumed:").bold(), lookup_path.display());
+    }
+    Ok(())
+}
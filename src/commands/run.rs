@@ -1,147 +1,761 @@
+use std::cell::Cell;
+use std::fs;
+use std::io::{self, IsTerminal};
 use std::path::{Path, PathBuf};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant, SystemTime};
 
 use console::style;
 use indicatif::ProgressBar;
+use serde::Serialize;
 
-use crate::{config, daemon, disksize, registry, scanner, tmutil, updater, verbose};
+use super::{ignore, reapply};
+use crate::cli::OutputFormat;
+use crate::disksize::SizeUnits;
+use crate::error::Error;
+use crate::{
+    assume_yes, config, daemon, disksize, interrupt, log, notify, prompt, quiet, registry, scanner,
+    tmutil, updater, verbose,
+};
 
 const UPDATE_COOLDOWN_SECS: i64 = 86_400; // 24 hours
+/// How long a confirmed exclusion is trusted before `run` asks tmutil
+/// about it again.
+const VERIFY_COOLDOWN_SECS: i64 = 21_600; // 6 hours
 
-pub fn execute() -> Result<(), Box<dyn std::error::Error>> {
-    let config = config::load()?;
+#[derive(Serialize)]
+struct Summary {
+    timestamp: String,
+    re_applied: usize,
+    added: usize,
+    total_managed: usize,
+    saved_bytes: Option<u64>,
+    added_paths: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    failed_paths: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<scanner::ScanStats>,
+}
+
+/// Per-invocation overrides for `run`.
+#[derive(Debug, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Options {
+    /// Only descend into directories modified within this window
+    pub since: Option<Duration>,
+    pub format: OutputFormat,
+    /// Also descend into hidden non-builtin directories
+    pub include_hidden: bool,
+    /// Replaces the config's `auto_update` for this run when set
+    pub auto_update: Option<bool>,
+    /// Ignore the verification cooldown
+    pub force_verify: bool,
+    /// Skip the large-batch guardrail and the `skip_if_recent` window
+    pub force: bool,
+    /// Exit early when the last full run is more recent than this window
+    pub skip_if_recent: Option<Duration>,
+    /// Restrict discovery to these paths inside the configured search paths
+    pub only: Vec<String>,
+    /// Extra scan roots for this run, never saved to the config
+    pub include_paths: Vec<String>,
+    /// Report scan metrics with the summary
+    pub stats: bool,
+    /// Ask about each new directory before excluding it
+    pub interactive: bool,
+    /// Replace per-path failure warnings with one count at the end
+    pub summary_only: bool,
+}
+
+/// Per-path failure warnings from one run. They are printed as they happen,
+/// unless `--summary-only` holds them back (still shown with `--verbose`)
+/// in favour of a single count once the run is done.
+pub(super) struct PathWarnings {
+    summary_only: bool,
+    failures: Cell<usize>,
+}
 
-    if config.auto_update {
-        auto_update()?;
+impl PathWarnings {
+    pub(super) fn new(summary_only: bool) -> Self {
+        Self {
+            summary_only,
+            failures: Cell::new(0),
+        }
+    }
+
+    /// Reports `message` about `failures` paths that could not be excluded.
+    pub(super) fn warn(&self, message: &str, failures: usize) {
+        self.failures.set(self.failures.get() + failures);
+        if !self.summary_only || verbose() {
+            log::warn(message);
+        }
+    }
+
+    /// The line `--summary-only` prints in place of the held-back warnings.
+    fn aggregate(&self) -> Option<String> {
+        let failures = self.failures.get();
+        (self.summary_only && failures > 0 && !verbose()).then(|| {
+            format!(
+                "{failures} {} failed; run with --verbose for details",
+                if failures == 1 {
+                    "exclusion"
+                } else {
+                    "exclusions"
+                }
+            )
+        })
+    }
+}
+
+pub fn execute(options: &Options) -> Result<(), Error> {
+    let json = options.format == OutputFormat::Json || daemon::is_daemon_context();
+    if options.interactive && !can_prompt() {
+        return Err("--interactive needs a terminal; rerun without it or with --assume-yes".into());
+    }
+    interrupt::install();
+    let mut config = config::load()?;
+    config.include_hidden |= options.include_hidden;
+    include_search_paths(&mut config, &options.include_paths)?;
+    if !options.only.is_empty() {
+        scope_search_paths(&mut config, &options.only)?;
+    }
+
+    if skips_as_recent(options, json)? {
+        return Ok(());
+    }
+    if options.auto_update.unwrap_or(config.auto_update) {
+        auto_update(&updater::UpdateSource::from_config(&config))?;
     }
 
     let mut guard = registry::Registry::locked()?;
     let mut reg = guard.load()?;
 
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_message("Scanning...");
-    spinner.enable_steady_tick(Duration::from_millis(80));
+    let now = registry::now_epoch();
+    // Scoped runs don't cover every search path, so they don't count as full.
+    let full_run = options.only.is_empty() && options.since.is_none();
+
+    let spinner = spinner(quiet() || json);
+    let warnings = PathWarnings::new(options.summary_only);
+    // A stop request abandons the run before the scan or before
+    // reconciling, leaving the registry file untouched.
+    let interrupted = |_: &Error| spinner.finish_and_clear();
 
-    let stale_count = prune_stale(&mut reg);
-    let re_applied = reapply_lost(&reg);
+    let started = Instant::now();
+    let stale_count = timed("prune", || prune_stale(&mut reg));
+    let nested_count = timed("dedup", || drop_nested(&mut reg, config.exclusion_mode));
+    let verify_cooldown = if options.force_verify {
+        0
+    } else {
+        VERIFY_COOLDOWN_SECS
+    };
+    let verification = timed("reapply", || {
+        reapply::reapply_lost(&mut reg, config.exclusion_mode, verify_cooldown, &warnings)
+    });
+    let re_applied = verification.re_applied;
+    interrupt::stop().check().inspect_err(interrupted)?;
 
-    let candidates = scanner::scan(&config, &|_| {});
-    let added_paths = reconcile(&mut reg, candidates);
+    let modified_since = options
+        .since
+        .and_then(|window| SystemTime::now().checked_sub(window));
+    let (candidates, scan_stats) = timed("scan", || {
+        scanner::scan_with_stats(&config, modified_since, &|found| {
+            spinner.set_message(format!("Scanning... {found} found"));
+        })
+    });
+    interrupt::stop().check().inspect_err(interrupted)?;
+    let (mut guard, reconciled, ignored) = reconcile_candidates(
+        guard, &mut reg, candidates, &config, &warnings, &spinner, options,
+    )?;
+    let Reconciled {
+        added: added_paths,
+        failed: failed_paths,
+    } = reconciled;
 
-    if stale_count > 0 || !added_paths.is_empty() {
-        let total = disksize::calculate_total_size(reg.list());
+    if stale_count > 0 || nested_count > 0 || !added_paths.is_empty() {
+        let total = timed("sizes", || reg.refresh_sizes(config.size_mode).total);
         reg.saved_bytes = if total > 0 { Some(total) } else { None };
     }
-    if stale_count > 0 || re_applied > 0 || !added_paths.is_empty() {
+    if full_run {
+        reg.last_full_run = Some(now);
+    }
+    if full_run
+        || stale_count > 0
+        || nested_count > 0
+        || verification.checked > 0
+        || !added_paths.is_empty()
+    {
         guard.save(&reg)?;
     }
+    if verbose() {
+        eprintln!(
+            "{} total took {} ms",
+            style("verbose:").dim(),
+            started.elapsed().as_millis()
+        );
+    }
 
     spinner.finish_and_clear();
-    print_summary(
+
+    let summary = Summary {
+        timestamp: log::timestamp(),
         re_applied,
-        added_paths.len(),
-        reg.list().len(),
-        reg.saved_bytes,
-    );
+        added: added_paths.len(),
+        total_managed: reg.entries().len(),
+        saved_bytes: reg.saved_bytes,
+        added_paths,
+        failed_paths,
+        stats: options.stats.then_some(scan_stats),
+    };
+    drop(guard);
+    persist_ignored(&ignored);
+    report(&summary, &config, json, &warnings)
+}
 
-    Ok(())
+fn spinner(hidden: bool) -> ProgressBar {
+    if hidden {
+        return ProgressBar::hidden();
+    }
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_message("Scanning...");
+    spinner.enable_steady_tick(Duration::from_millis(80));
+    spinner
 }
 
-fn prune_stale(reg: &mut registry::Registry) -> usize {
-    let mut count = 0usize;
-    for entry in reg.list().to_vec() {
-        if !Path::new(&entry).exists() {
-            if verbose() {
-                eprintln!("{} pruning stale entry: {entry}", style("verbose:").dim());
-            }
-            reg.remove(&entry);
-            count += 1;
+/// Prints the summary, then runs the post-run side effects: notification,
+/// `post_run_command` and daemon log rotation. None of those can fail the run.
+fn report(
+    summary: &Summary,
+    config: &config::Config,
+    json: bool,
+    warnings: &PathWarnings,
+) -> Result<(), Error> {
+    if json {
+        println!("{}", serde_json::to_string(summary)?);
+    } else if !quiet() {
+        print_summary(summary, config.size_units);
+        if let Some(stats) = &summary.stats {
+            print_stats(stats);
         }
     }
-    count
+    if let Some(aggregate) = warnings.aggregate() {
+        log::warn(&aggregate);
+    }
+
+    if config.notify_on_run
+        && summary.added > 0
+        && !quiet()
+        && let Err(e) = notify::notify("veiled", &notification_body(summary, config.size_units))
+    {
+        log::warn(&format!("notification failed: {e}"));
+    }
+
+    if let Some(command) = &config.post_run_command
+        && let Err(e) = run_hook(command, summary)
+    {
+        log::warn(&format!("post_run_command failed: {e}"));
+    }
+
+    if daemon::is_daemon_context()
+        && let Err(e) = log::rotate_daemon_logs()
+    {
+        log::warn(&format!("log rotation failed: {e}"));
+    }
+
+    Ok(())
 }
 
-fn reapply_lost(reg: &registry::Registry) -> usize {
-    let entries: Vec<String> = reg.list().to_vec();
-    if entries.is_empty() {
-        return 0;
+/// Appends `paths` to the search paths of the loaded config, leaving the
+/// file on disk alone. Each must be an existing directory.
+pub(super) fn include_search_paths(
+    config: &mut config::Config,
+    paths: &[String],
+) -> Result<(), Error> {
+    for path in paths {
+        let expanded = config::expand_tilde(path);
+        let canonical = fs::canonicalize(&expanded)
+            .map_err(|_| format!("{}: no such directory", expanded.display()))?;
+        if !canonical.is_dir() {
+            return Err(format!("{}: not a directory", canonical.display()).into());
+        }
+        let root = canonical.to_string_lossy().into_owned();
+        if !config.search_paths.contains(&root) {
+            config.search_paths.push(root);
+        }
     }
+    Ok(())
+}
+
+/// Narrows discovery to `only`. Each path must exist and sit inside one of
+/// the configured search paths; `extra_exclusions` outside them are dropped
+/// for this run. Pruning and re-applying still cover the whole registry.
+fn scope_search_paths(config: &mut config::Config, only: &[String]) -> Result<(), Error> {
+    let roots: Vec<PathBuf> = config
+        .search_paths
+        .iter()
+        .map(|root| fs::canonicalize(root).unwrap_or_else(|_| PathBuf::from(root)))
+        .collect();
 
-    let paths: Vec<PathBuf> = entries.iter().map(PathBuf::from).collect();
-    let status = tmutil::are_excluded(&paths);
+    let mut scoped = Vec::with_capacity(only.len());
+    for path in only {
+        let expanded = config::expand_tilde(path);
+        let canonical = fs::canonicalize(&expanded)
+            .map_err(|_| format!("{}: no such directory", expanded.display()))?;
+        if !canonical.is_dir() {
+            return Err(format!("{}: not a directory", canonical.display()).into());
+        }
+        if !roots.iter().any(|root| canonical.starts_with(root)) {
+            return Err(Error::Config(format!(
+                "{} is not inside any configured search path",
+                canonical.display()
+            )));
+        }
+        scoped.push(canonical);
+    }
 
-    let lost: Vec<PathBuf> = paths
+    config
+        .extra_exclusions
+        .retain(|extra| scoped.iter().any(|path| Path::new(extra).starts_with(path)));
+    config.search_paths = scoped
         .into_iter()
-        .zip(status.iter())
-        .filter(|(_, excluded)| !**excluded)
-        .map(|(path, _)| path)
+        .map(|path| path.to_string_lossy().into_owned())
         .collect();
+    Ok(())
+}
 
-    if lost.is_empty() {
-        return 0;
+fn notification_body(summary: &Summary, units: SizeUnits) -> String {
+    let saved = summary
+        .saved_bytes
+        .map(|b| format!(" ({} saved)", disksize::format_size(b, units)))
+        .unwrap_or_default();
+    format!(
+        "Excluded {} new {}{saved}",
+        summary.added,
+        if summary.added == 1 { "path" } else { "paths" }
+    )
+}
+
+/// Runs `command` through `sh -c` with the summary in `VEILED_*`
+/// environment variables. Its output goes to stderr so a JSON summary on
+/// stdout stays parseable.
+fn run_hook(command: &str, summary: &Summary) -> Result<(), Box<dyn std::error::Error>> {
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("VEILED_TIMESTAMP", &summary.timestamp)
+        .env("VEILED_ADDED", summary.added.to_string())
+        .env("VEILED_RE_APPLIED", summary.re_applied.to_string())
+        .env("VEILED_TOTAL_MANAGED", summary.total_managed.to_string())
+        .env(
+            "VEILED_SAVED_BYTES",
+            summary.saved_bytes.unwrap_or_default().to_string(),
+        )
+        .env("VEILED_ADDED_PATHS", summary.added_paths.join("\n"))
+        .stdin(Stdio::null())
+        .stdout(io::stderr())
+        .status()?;
+    if !status.success() {
+        return Err(format!("exited with {status}").into());
     }
+    Ok(())
+}
 
-    let count = lost.len();
-    if let Err(e) = tmutil::add_exclusions(&lost) {
+/// Runs one phase of the scan, reporting its duration with `--verbose`.
+fn timed<T>(phase: &str, f: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = f();
+    if verbose() {
         eprintln!(
-            "{} batch re-apply failed: {e}",
-            style("warning:").yellow().bold()
+            "{} {phase} took {} ms",
+            style("verbose:").dim(),
+            started.elapsed().as_millis()
         );
-        return 0;
     }
-    count
+    result
 }
 
-fn reconcile(reg: &mut registry::Registry, candidates: Vec<PathBuf>) -> Vec<String> {
-    let new_candidates: Vec<PathBuf> = candidates
+fn prune_stale(reg: &mut registry::Registry) -> usize {
+    let pruned = reg.prune_missing();
+    if verbose() {
+        for entry in &pruned {
+            eprintln!("{} pruning stale entry: {entry}", style("verbose:").dim());
+        }
+    }
+    pruned.len()
+}
+
+/// Drops entries already covered by a registered ancestor, clearing their
+/// own exclusion first so the path is restored along with the ancestor. A
+/// pre-existing exclusion predates veiled and is left in place; a child
+/// whose exclusion cannot be cleared stays tracked until a later run.
+fn drop_nested(reg: &mut registry::Registry, mode: tmutil::ExclusionMode) -> usize {
+    let nested = reg.drop_nested(|entry| {
+        let path = Path::new(&entry.path);
+        if entry.pre_existing || !path.exists() {
+            return true;
+        }
+        tmutil::remove_exclusion(path, mode)
+            .inspect_err(|e| log::warn(&format!("keeping nested entry {}: {e}", entry.path)))
+            .is_ok()
+    });
+    if verbose() {
+        for entry in &nested {
+            eprintln!(
+                "{} dropping entry covered by a parent: {entry}",
+                style("verbose:").dim()
+            );
+        }
+    }
+    nested.len()
+}
+
+/// Drops candidates smaller than `min_size` or larger than `max_size`.
+/// Sizes are only measured when one of the limits is set.
+fn filter_by_size(candidates: Vec<PathBuf>, config: &config::Config) -> Vec<PathBuf> {
+    let (min_size, max_size) = (config.min_size_bytes, config.max_size_bytes);
+    if min_size == 0 && max_size.is_none() {
+        return candidates;
+    }
+
+    candidates
         .into_iter()
-        .filter(|p| !reg.contains(&p.to_string_lossy()))
+        .filter(|path| {
+            let size = disksize::dir_size(path, config.size_mode);
+            let reason = if size < min_size {
+                "below threshold"
+            } else if max_size.is_some_and(|max| size > max) {
+                "above max_size_bytes"
+            } else {
+                return true;
+            };
+            if verbose() {
+                eprintln!(
+                    "{} skipping {} ({} {reason})",
+                    style("verbose:").dim(),
+                    path.display(),
+                    disksize::format_size(size, config.size_units)
+                );
+            }
+            false
+        })
+        .collect()
+}
+
+/// Whether `--interactive` can get answers: from a terminal, or from
+/// `--assume-yes`. The daemon never prompts.
+fn can_prompt() -> bool {
+    assume_yes() || (!daemon::is_daemon_context() && io::stdin().is_terminal())
+}
+
+/// An answer to a `run --interactive` prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Choice {
+    /// Exclude the directory now
+    Exclude,
+    /// Leave it for this run; the next scan asks again
+    Skip,
+    /// Leave it and add it to `ignore_paths` so no scan finds it again
+    Ignore,
+}
+
+impl Choice {
+    fn parse(answer: &str) -> Self {
+        match answer {
+            "y" | "yes" => Self::Exclude,
+            "i" | "ignore" => Self::Ignore,
+            _ => Self::Skip,
+        }
+    }
+}
+
+/// Asks about each path for `run --interactive`, showing its size. A
+/// declined path is only skipped for this run unless the answer was `i`,
+/// which the caller appends to `ignore_paths` once the registry is unlocked.
+fn select_interactively(paths: &[PathBuf], config: &config::Config) -> Vec<Choice> {
+    select_each(paths, |path| {
+        prompt::ask(
+            &format!(
+                "Exclude {} ({})? i = never ask again",
+                path.display(),
+                disksize::format_size(
+                    disksize::dir_size(path, config.size_mode),
+                    config.size_units
+                )
+            ),
+            "y/N/i",
+        )
+        .map(|answer| Choice::parse(&answer))
+    })
+}
+
+/// Reconciles the scan results behind the large-batch guardrail, or per
+/// path with `--interactive`. Returns the registry lock, which an
+/// interactive run gives up and reacquires, and the paths answered with `i`.
+fn reconcile_candidates(
+    guard: registry::LockedRegistry,
+    reg: &mut registry::Registry,
+    candidates: Vec<PathBuf>,
+    config: &config::Config,
+    warnings: &PathWarnings,
+    spinner: &ProgressBar,
+    options: &Options,
+) -> Result<(registry::LockedRegistry, Reconciled, Vec<PathBuf>), Error> {
+    if options.interactive {
+        return reconcile_interactively(guard, reg, candidates, config, warnings, spinner);
+    }
+    let reconciled = timed("reconcile", || {
+        reconcile(reg, candidates, config, warnings, |batch| {
+            let approved = options.force || spinner.suspend(|| confirm_large_batch(batch, config));
+            vec![approved; batch.len()]
+        })
+    });
+    Ok((guard, reconciled, Vec::new()))
+}
+
+/// `reconcile` for `run --interactive`. The registry is saved and unlocked
+/// while the user answers, so a slow answer never blocks the daemon or
+/// other commands, then locked and reloaded before excluding. Returns the
+/// reacquired lock and the paths answered with `i`.
+fn reconcile_interactively(
+    mut guard: registry::LockedRegistry,
+    reg: &mut registry::Registry,
+    candidates: Vec<PathBuf>,
+    config: &config::Config,
+    warnings: &PathWarnings,
+    spinner: &ProgressBar,
+) -> Result<(registry::LockedRegistry, Reconciled, Vec<PathBuf>), Error> {
+    let (mut result, pending) = register_excluded(reg, candidates, config);
+    if pending.is_empty() {
+        return Ok((guard, result, Vec::new()));
+    }
+    guard.save(reg)?;
+    drop(guard);
+
+    let choices = spinner.suspend(|| select_interactively(&pending, config));
+
+    let mut guard = registry::Registry::locked()?;
+    *reg = guard.load()?;
+    let selected: Vec<bool> = choices.iter().map(|c| *c == Choice::Exclude).collect();
+    let ignored = pending
+        .iter()
+        .zip(&choices)
+        .filter(|(_, choice)| **choice == Choice::Ignore)
+        .map(|(path, _)| path.clone())
         .collect();
+    exclude_selected(reg, pending, &selected, config, warnings, &mut result);
+    Ok((guard, result, ignored))
+}
 
-    if new_candidates.is_empty() {
-        return vec![];
+/// Appends the paths answered with `i` to `ignore_paths`. Takes the config
+/// lock, so it must run after the registry lock is released.
+fn persist_ignored(ignored: &[PathBuf]) {
+    if !ignored.is_empty()
+        && let Err(e) = ignore::persist(ignored)
+    {
+        log::warn(&format!(
+            "could not add declined paths to ignore_paths: {e}"
+        ));
     }
+}
 
-    let excluded_status = tmutil::are_excluded(&new_candidates);
+/// Collects one answer per path. A failed prompt counts as a skip.
+fn select_each<E: std::fmt::Display>(
+    paths: &[PathBuf],
+    mut ask: impl FnMut(&Path) -> Result<Choice, E>,
+) -> Vec<Choice> {
+    paths
+        .iter()
+        .map(|path| {
+            ask(path).unwrap_or_else(|e| {
+                log::warn(&format!("skipping {}: {e}", path.display()));
+                Choice::Skip
+            })
+        })
+        .collect()
+}
 
-    let mut added = Vec::new();
-    let mut to_exclude: Vec<(PathBuf, String)> = Vec::new();
+/// Asks before newly excluding more than `warn_threshold_count` directories
+/// or `warn_threshold_bytes` in one pass, so a too-broad search path does not
+/// silently hide wanted data from backups. The daemon and non-interactive
+/// runs never prompt; they skip the batch with a warning until `--force`.
+fn confirm_large_batch(paths: &[PathBuf], config: &config::Config) -> bool {
+    let count_exceeded =
+        config.warn_threshold_count > 0 && paths.len() > config.warn_threshold_count;
+    let total = if config.warn_threshold_bytes > 0 {
+        let paths: Vec<String> = paths
+            .iter()
+            .map(|p| p.to_string_lossy().into_owned())
+            .collect();
+        disksize::dir_sizes(&paths, config.size_mode)
+            .into_iter()
+            .fold(0u64, u64::saturating_add)
+    } else {
+        0
+    };
+    let bytes_exceeded = config.warn_threshold_bytes > 0 && total > config.warn_threshold_bytes;
+    if !count_exceeded && !bytes_exceeded {
+        return true;
+    }
 
-    for (path, is_excluded) in new_candidates.iter().zip(excluded_status.iter()) {
-        let s = path.to_string_lossy().into_owned();
+    let message = format!(
+        "this run would newly exclude {} {} ({}), above the {} guardrail",
+        paths.len(),
+        if paths.len() == 1 {
+            "directory"
+        } else {
+            "directories"
+        },
+        disksize::format_size(total, config.size_units),
+        if count_exceeded {
+            "warn_threshold_count"
+        } else {
+            "warn_threshold_bytes"
+        }
+    );
+    if !daemon::is_daemon_context()
+        && let Ok(answer) = prompt::confirm(&format!("Warning: {message}. Continue?"))
+    {
+        return answer;
+    }
+    log::warn(&format!(
+        "{message}; skipped them, rerun with `veiled run --force` to exclude anyway"
+    ));
+    false
+}
 
-        if *is_excluded {
+/// New paths `reconcile` registered, and the ones tmutil refused to exclude.
+#[derive(Debug, Default)]
+struct Reconciled {
+    added: Vec<String>,
+    failed: Vec<String>,
+}
+
+/// Excludes `paths` in one `batch` call, and when that fails retries each
+/// path with `single` so one bad directory does not block the rest. Returns
+/// whether each path ended up excluded.
+fn exclude_with_fallback<E: std::fmt::Display>(
+    paths: &[PathBuf],
+    batch: impl FnOnce(&[PathBuf]) -> Result<(), E>,
+    mut single: impl FnMut(&Path) -> Result<(), E>,
+    warnings: &PathWarnings,
+) -> Vec<bool> {
+    let Err(e) = batch(paths) else {
+        return vec![true; paths.len()];
+    };
+    warnings.warn(
+        &format!("batch exclusion failed, retrying individually: {e}"),
+        0,
+    );
+    paths
+        .iter()
+        .map(|path| {
+            // Once interrupted, the rest count as failed and the run still
+            // saves what already succeeded.
+            if interrupt::stop().is_set() {
+                return false;
+            }
+            match single(path) {
+                Ok(()) => true,
+                Err(e) => {
+                    warnings.warn(&format!("{}: {e}", path.display()), 1);
+                    false
+                }
+            }
+        })
+        .collect()
+}
+
+/// Registers new candidates, excluding the ones Time Machine does not
+/// already skip once `approve` accepts that batch. Only paths that were
+/// actually excluded get registered.
+fn reconcile(
+    reg: &mut registry::Registry,
+    candidates: Vec<PathBuf>,
+    config: &config::Config,
+    warnings: &PathWarnings,
+    select: impl FnOnce(&[PathBuf]) -> Vec<bool>,
+) -> Reconciled {
+    let (mut result, pending) = register_excluded(reg, candidates, config);
+    if !pending.is_empty() {
+        let selected = select(&pending);
+        exclude_selected(reg, pending, &selected, config, warnings, &mut result);
+    }
+    result
+}
+
+/// Registers the new candidates Time Machine already skips and returns the
+/// rest, which still need an exclusion.
+fn register_excluded(
+    reg: &mut registry::Registry,
+    candidates: Vec<PathBuf>,
+    config: &config::Config,
+) -> (Reconciled, Vec<PathBuf>) {
+    let new_candidates: Vec<PathBuf> = candidates.into_iter().filter(|p| !reg.covers(p)).collect();
+    let new_candidates = filter_by_size(new_candidates, config);
+
+    let mut result = Reconciled::default();
+    if new_candidates.is_empty() {
+        return (result, Vec::new());
+    }
+
+    let excluded_status = tmutil::are_excluded(&new_candidates, config.exclusion_mode);
+    let mut pending = Vec::new();
+    for (path, is_excluded) in new_candidates.into_iter().zip(excluded_status) {
+        if is_excluded {
+            let s = path.to_string_lossy().into_owned();
             reg.add(&s);
-            added.push(s);
+            result.added.push(s);
         } else {
-            to_exclude.push((path.clone(), s));
+            pending.push(path);
         }
     }
+    (result, pending)
+}
 
-    if !to_exclude.is_empty() {
-        let exclude_paths: Vec<PathBuf> = to_exclude.iter().map(|(p, _)| p.clone()).collect();
-        if let Err(e) = tmutil::add_exclusions(&exclude_paths) {
-            eprintln!(
-                "{} batch exclusion failed: {e}",
-                style("warning:").yellow().bold()
-            );
+/// Excludes the `pending` paths whose `selected` flag is set, registering
+/// the ones that succeed into `result.added` and the rest into `failed`.
+fn exclude_selected(
+    reg: &mut registry::Registry,
+    pending: Vec<PathBuf>,
+    selected: &[bool],
+    config: &config::Config,
+    warnings: &PathWarnings,
+    result: &mut Reconciled,
+) {
+    let to_exclude: Vec<PathBuf> = pending
+        .into_iter()
+        .zip(selected)
+        .filter_map(|(path, keep)| keep.then_some(path))
+        .collect();
+    if to_exclude.is_empty() {
+        return;
+    }
+    let mode = config.exclusion_mode;
+    let excluded = exclude_with_fallback(
+        &to_exclude,
+        |paths| tmutil::add_exclusions(paths, mode),
+        |path| tmutil::add_exclusion(path, mode),
+        warnings,
+    );
+    for (path, ok) in to_exclude.into_iter().zip(excluded) {
+        let s = path.to_string_lossy().into_owned();
+        if ok {
+            reg.add(&s);
+            result.added.push(s);
         } else {
-            for (_, s) in to_exclude {
-                reg.add(&s);
-                added.push(s);
-            }
+            result.failed.push(s);
         }
     }
-
-    added
 }
 
-fn print_summary(
-    re_applied: usize,
-    total_added: usize,
-    total_managed: usize,
-    saved_bytes: Option<u64>,
-) {
+fn print_summary(summary: &Summary, units: SizeUnits) {
+    let Summary {
+        re_applied,
+        added: total_added,
+        total_managed,
+        saved_bytes,
+        ref failed_paths,
+        ..
+    } = *summary;
+
     if re_applied > 0 {
         println!(
             "{} {} lost {}",
@@ -157,12 +771,12 @@ fn print_summary(
 
     if total_added > 0 {
         let details = match (total_added == total_managed, saved_bytes) {
-            (true, Some(b)) => format!(" ({} saved)", disksize::format_size(b)),
+            (true, Some(b)) => format!(" ({} saved)", disksize::format_size(b, units)),
             (true, None) => String::new(),
             (false, Some(b)) => {
                 format!(
                     " ({total_managed} total, {} saved)",
-                    disksize::format_size(b)
+                    disksize::format_size(b, units)
                 )
             }
             (false, None) => format!(" ({total_managed} total)"),
@@ -173,22 +787,43 @@ fn print_summary(
             total_added,
             if total_added == 1 { "path" } else { "paths" },
         );
-    } else if re_applied == 0 {
+    } else if re_applied == 0 && failed_paths.is_empty() {
         println!("{}", style("Nothing new to exclude.").dim());
     }
+
+    if !failed_paths.is_empty() {
+        println!(
+            "{} {} new {} could not be excluded",
+            style("Failed:").red().bold(),
+            failed_paths.len(),
+            if failed_paths.len() == 1 {
+                "path"
+            } else {
+                "paths"
+            },
+        );
+        for path in failed_paths {
+            println!("  {path}");
+        }
+    }
 }
 
-fn now_epoch() -> i64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map_or(0, |d| d.as_secs().cast_signed())
+fn print_stats(stats: &scanner::ScanStats) {
+    println!("{}", style("Scan stats:").bold());
+    println!("  Directories visited: {}", stats.dirs_visited);
+    println!(
+        "  Skipped: {} ignored, {} symlinked, {} hidden",
+        stats.skipped_ignored, stats.skipped_symlinks, stats.skipped_hidden
+    );
+    println!("  Git repos scanned: {}", stats.git_repos);
+    println!("  Scan time: {} ms", stats.elapsed_ms);
 }
 
-fn auto_update() -> Result<(), Box<dyn std::error::Error>> {
+fn auto_update(source: &updater::UpdateSource) -> Result<(), Box<dyn std::error::Error>> {
     let mut guard = registry::Registry::locked()?;
     let reg = guard.load()?;
 
-    let now = now_epoch();
+    let now = registry::now_epoch();
 
     if let Some(last) = reg.last_update_check
         && last <= now
@@ -206,7 +841,7 @@ fn auto_update() -> Result<(), Box<dyn std::error::Error>> {
 
     drop(guard);
 
-    let result = updater::check();
+    let result = updater::check(source);
 
     let mut guard = registry::Registry::locked()?;
     let mut reg = guard.load()?;
@@ -218,17 +853,251 @@ fn auto_update() -> Result<(), Box<dyn std::error::Error>> {
 
     match result {
         Ok(check_result) if check_result.updated => {
-            if let Err(e) = daemon::restart()
-                && verbose()
-            {
-                eprintln!("{} daemon restart failed: {e}", style("verbose:").dim());
+            if let Err(e) = daemon::restart() {
+                report_update_failure(&format!("daemon restart failed: {e}"));
             }
         }
-        Err(e) if verbose() => {
-            eprintln!("{} auto-update failed: {e}", style("verbose:").dim());
-        }
+        Ok(check_result) if check_result.blocked => report_update_failure(&format!(
+            "update available but blocked by max_update: {}",
+            check_result.new_version
+        )),
+        Err(e) => report_update_failure(&format!("auto-update failed: {e}")),
         _ => {}
     }
 
     Ok(())
 }
+
+/// `--skip-if-recent`: whether the last full run falls within the window.
+/// Checked before auto-update so a skipped run never touches the network.
+/// In JSON mode the skip is printed as `{"skipped":"recent"}` so the
+/// daemon's log still shows the run fired.
+fn skips_as_recent(options: &Options, json: bool) -> Result<bool, Error> {
+    let Some(window) = options.skip_if_recent.filter(|_| !options.force) else {
+        return Ok(false);
+    };
+    let last_full_run = registry::Registry::locked()?.load()?.last_full_run;
+    if !within_window(last_full_run, registry::now_epoch(), window) {
+        return Ok(false);
+    }
+
+    if json {
+        println!("{}", serde_json::json!({ "skipped": "recent" }));
+    } else if !quiet() {
+        println!("{}", style("Scanned recently, skipping.").dim());
+    }
+    Ok(true)
+}
+
+/// Whether `last` falls within `window` before `now`, with the same
+/// comparison as the auto-update cooldown. A timestamp in the future (clock
+/// moved back) never counts as recent.
+fn within_window(last: Option<i64>, now: i64, window: Duration) -> bool {
+    last.is_some_and(|last| last <= now && (now - last).unsigned_abs() < window.as_secs())
+}
+
+/// Auto-update problems never fail a scan. Interactive runs only mention
+/// them with `--verbose`; the daemon always records them in `stderr.log`.
+fn report_update_failure(message: &str) {
+    if daemon::is_daemon_context() {
+        log::warn(message);
+    } else if verbose() {
+        eprintln!("{} {message}", style("verbose:").dim());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn include_search_paths_appends_each_directory_once() {
+        let dir = TempDir::new().unwrap();
+        let canonical = fs::canonicalize(dir.path()).unwrap();
+        let path = dir.path().to_string_lossy().into_owned();
+        let mut config = config::Config {
+            search_paths: vec![],
+            ..config::Config::default()
+        };
+
+        include_search_paths(&mut config, &[path.clone(), path]).unwrap();
+
+        assert_eq!(
+            config.search_paths,
+            vec![canonical.to_string_lossy().into_owned()]
+        );
+    }
+
+    #[test]
+    fn include_search_paths_rejects_missing_directory() {
+        let mut config = config::Config::default();
+        let before = config.search_paths.clone();
+
+        let err =
+            include_search_paths(&mut config, &["/nonexistent/veiled".to_string()]).unwrap_err();
+
+        assert!(err.to_string().contains("no such directory"));
+        assert_eq!(config.search_paths, before);
+    }
+
+    #[test]
+    fn within_window_is_true_for_a_recent_run() {
+        let hour = Duration::from_hours(1);
+        assert!(within_window(Some(1_000), 1_000, hour));
+        assert!(within_window(Some(1_000), 1_000 + 3_599, hour));
+    }
+
+    #[test]
+    fn within_window_is_false_once_the_window_has_passed() {
+        let hour = Duration::from_hours(1);
+        assert!(!within_window(Some(1_000), 1_000 + 3_600, hour));
+        assert!(!within_window(None, 1_000, hour));
+    }
+
+    #[test]
+    fn within_window_ignores_timestamps_in_the_future() {
+        assert!(!within_window(Some(2_000), 1_000, Duration::from_hours(1)));
+    }
+
+    #[test]
+    fn exclude_with_fallback_retries_each_path_after_batch_failure() {
+        let paths: Vec<PathBuf> = ["/a", "/b", "/c"].iter().map(PathBuf::from).collect();
+        let mut attempted = Vec::new();
+
+        let excluded = exclude_with_fallback(
+            &paths,
+            |_| Err("batch failed"),
+            |path| {
+                attempted.push(path.to_path_buf());
+                if path == Path::new("/b") {
+                    Err("permission denied")
+                } else {
+                    Ok(())
+                }
+            },
+            &PathWarnings::new(false),
+        );
+
+        assert_eq!(excluded, vec![true, false, true]);
+        assert_eq!(attempted, paths);
+    }
+
+    #[test]
+    fn exclude_with_fallback_skips_retries_when_batch_succeeds() {
+        let paths = vec![PathBuf::from("/a"), PathBuf::from("/b")];
+
+        let excluded = exclude_with_fallback(
+            &paths,
+            |_| Ok::<(), &str>(()),
+            |_| panic!("no per-path retry expected"),
+            &PathWarnings::new(false),
+        );
+
+        assert_eq!(excluded, vec![true, true]);
+    }
+
+    #[test]
+    fn path_warnings_count_failures_for_the_summary_line() {
+        let warnings = PathWarnings::new(true);
+        warnings.warn("batch exclusion failed, retrying individually", 0);
+        warnings.warn("/a: permission denied", 1);
+        warnings.warn("/b: permission denied", 1);
+
+        assert_eq!(
+            warnings.aggregate().as_deref(),
+            Some("2 exclusions failed; run with --verbose for details")
+        );
+    }
+
+    #[test]
+    fn path_warnings_have_no_summary_line_unless_summary_only() {
+        let warnings = PathWarnings::new(false);
+        warnings.warn("/a: permission denied", 1);
+        assert!(warnings.aggregate().is_none());
+
+        assert!(PathWarnings::new(true).aggregate().is_none());
+    }
+
+    #[test]
+    fn select_each_treats_failed_prompts_as_skips() {
+        let paths: Vec<PathBuf> = ["/a", "/b", "/c", "/d"].iter().map(PathBuf::from).collect();
+        let mut answers = [
+            Ok(Choice::Exclude),
+            Ok(Choice::Ignore),
+            Err("no tty"),
+            Ok(Choice::Exclude),
+        ]
+        .into_iter();
+
+        let selected = select_each(&paths, |_| answers.next().unwrap());
+
+        assert_eq!(
+            selected,
+            vec![
+                Choice::Exclude,
+                Choice::Ignore,
+                Choice::Skip,
+                Choice::Exclude
+            ]
+        );
+    }
+
+    #[test]
+    fn choice_parse_defaults_to_skip() {
+        assert_eq!(Choice::parse("y"), Choice::Exclude);
+        assert_eq!(Choice::parse("yes"), Choice::Exclude);
+        assert_eq!(Choice::parse("i"), Choice::Ignore);
+        assert_eq!(Choice::parse("n"), Choice::Skip);
+        assert_eq!(Choice::parse(""), Choice::Skip);
+        assert_eq!(Choice::parse("maybe"), Choice::Skip);
+    }
+
+    #[test]
+    fn filter_by_size_skips_dirs_below_threshold() {
+        let small = TempDir::new().unwrap();
+        fs::write(small.path().join("pkg.js"), vec![0u8; 500 * 1024]).unwrap();
+        let large = TempDir::new().unwrap();
+        fs::write(large.path().join("pkg.js"), vec![0u8; 2 * 1024 * 1024]).unwrap();
+
+        let config = config::Config {
+            min_size_bytes: 1_048_576,
+            ..config::Config::default()
+        };
+        let results = filter_by_size(
+            vec![small.path().to_path_buf(), large.path().to_path_buf()],
+            &config,
+        );
+
+        assert_eq!(results, vec![large.path().to_path_buf()]);
+    }
+
+    #[test]
+    fn filter_by_size_skips_dirs_above_max_size() {
+        let normal = TempDir::new().unwrap();
+        fs::write(normal.path().join("pkg.js"), vec![0u8; 500 * 1024]).unwrap();
+        let huge = TempDir::new().unwrap();
+        fs::write(huge.path().join("media.bin"), vec![0u8; 2 * 1024 * 1024]).unwrap();
+
+        let config = config::Config {
+            max_size_bytes: Some(1_048_576),
+            ..config::Config::default()
+        };
+        let results = filter_by_size(
+            vec![normal.path().to_path_buf(), huge.path().to_path_buf()],
+            &config,
+        );
+
+        assert_eq!(results, vec![normal.path().to_path_buf()]);
+    }
+
+    #[test]
+    fn filter_by_size_keeps_everything_when_disabled() {
+        let empty = TempDir::new().unwrap();
+
+        let results = filter_by_size(vec![empty.path().to_path_buf()], &config::Config::default());
+
+        assert_eq!(results, vec![empty.path().to_path_buf()]);
+    }
+}
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use console::style;
+use indicatif::ProgressBar;
+use serde::Serialize;
+
+use super::paths::PathPrinter;
+use super::run::include_search_paths;
+use crate::error::Error;
+use crate::{config, disksize, quiet, scanner};
+
+#[derive(Serialize)]
+struct Found {
+    path: String,
+    size_bytes: u64,
+}
+
+#[derive(Serialize)]
+struct Report {
+    paths: Vec<Found>,
+    total_bytes: u64,
+}
+
+/// Lists what a scan would find, with sizes, without touching Time Machine
+/// or the registry. `include_paths` are scanned alongside the search paths.
+pub fn execute(include_paths: &[String], json: bool) -> Result<(), Error> {
+    let mut config = config::load()?;
+    include_search_paths(&mut config, include_paths)?;
+
+    let spinner = if quiet() || json {
+        ProgressBar::hidden()
+    } else {
+        let spinner = ProgressBar::new_spinner();
+        spinner.set_message("Scanning...");
+        spinner.enable_steady_tick(Duration::from_millis(80));
+        spinner
+    };
+    let paths: Vec<String> = scanner::scan(&config, None, &|found| {
+        spinner.set_message(format!("Scanning... {found} found"));
+    })
+    .into_iter()
+    .map(|p| p.to_string_lossy().into_owned())
+    .collect();
+    spinner.set_message("Measuring...");
+    let sizes = disksize::dir_sizes(&paths, config.size_mode);
+    spinner.finish_and_clear();
+
+    let total_bytes = sizes.iter().copied().fold(0u64, u64::saturating_add);
+
+    if json {
+        let report = Report {
+            paths: paths
+                .into_iter()
+                .zip(sizes)
+                .map(|(path, size_bytes)| Found { path, size_bytes })
+                .collect(),
+            total_bytes,
+        };
+        println!("{}", serde_json::to_string(&report)?);
+        return Ok(());
+    }
+
+    if paths.is_empty() {
+        if !quiet() {
+            println!("{}", style("No directories found.").dim());
+        }
+        return Ok(());
+    }
+
+    let printer = PathPrinter::new(&config);
+    for (path, size) in paths.iter().zip(&sizes) {
+        printer.print_row(path, Some(*size));
+    }
+
+    if !quiet() {
+        println!(
+            "{} {} {} ({})",
+            style("Total:").bold(),
+            paths.len(),
+            if paths.len() == 1 {
+                "directory"
+            } else {
+                "directories"
+            },
+            disksize::format_size(total_bytes, config.size_units)
+        );
+    }
+
+    Ok(())
+}
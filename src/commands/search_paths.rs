@@ -0,0 +1,103 @@
+use std::fs;
+
+use console::style;
+
+use crate::cli::SearchPathsAction;
+use crate::error::Error;
+use crate::{config, quiet};
+
+pub fn execute(action: &SearchPathsAction) -> Result<(), Error> {
+    match action {
+        SearchPathsAction::Add { path } => add(path),
+        SearchPathsAction::Remove { path } => remove(path),
+        SearchPathsAction::List => list(),
+    }
+    .map_err(Error::from)
+}
+
+fn add(path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let expanded = config::expand_tilde(path);
+    let canonical = fs::canonicalize(&expanded)
+        .map_err(|_| format!("{}: no such directory", expanded.display()))?;
+
+    if !canonical.is_dir() {
+        return Err(format!("{}: not a directory", canonical.display()).into());
+    }
+
+    let canonical_str = canonical.to_string_lossy().into_owned();
+    let display = config::collapse_tilde(&canonical_str);
+
+    let mut guard = config::Config::locked()?;
+    let mut cfg = guard.load()?;
+
+    if cfg.search_paths.contains(&canonical_str) {
+        if !quiet() {
+            println!("{display} is already a search path");
+        }
+        return Ok(());
+    }
+
+    cfg.search_paths.push(canonical_str);
+    guard.save(&cfg)?;
+
+    if !quiet() {
+        println!("{} {display}", style("Added:").bold());
+    }
+    Ok(())
+}
+
+fn remove(path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let expanded = config::expand_tilde(path);
+    let canonical = fs::canonicalize(&expanded).unwrap_or_else(|_| expanded.clone());
+
+    let mut guard = config::Config::locked()?;
+    let mut cfg = guard.load()?;
+
+    let matches =
+        |p: &String| *p == *expanded.to_string_lossy() || *p == *canonical.to_string_lossy();
+    if guard
+        .system()
+        .is_some_and(|system| system.search_paths.iter().any(matches))
+    {
+        return Err(format!(
+            "{path} comes from the system config {}; it can only be removed there",
+            config::system_config_path().display()
+        )
+        .into());
+    }
+
+    let before = cfg.search_paths.len();
+    cfg.search_paths.retain(|p| !matches(p));
+
+    if cfg.search_paths.len() == before {
+        return Err(format!("{path} is not a search path").into());
+    }
+
+    guard.save(&cfg)?;
+
+    if !quiet() {
+        println!(
+            "{} {}",
+            style("Removed:").bold(),
+            config::collapse_tilde(&expanded.to_string_lossy())
+        );
+    }
+    Ok(())
+}
+
+fn list() -> Result<(), Box<dyn std::error::Error>> {
+    let cfg = config::load()?;
+
+    if cfg.search_paths.is_empty() {
+        if !quiet() {
+            println!("{}", style("No search paths configured.").dim());
+        }
+        return Ok(());
+    }
+
+    for path in &cfg.search_paths {
+        println!("{}", config::collapse_tilde(path));
+    }
+
+    Ok(())
+}
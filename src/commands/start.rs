@@ -1,25 +1,44 @@
 use console::style;
 
-use crate::{daemon, registry};
+use crate::error::Error;
+use crate::{config, daemon, quiet, registry};
+
+pub fn execute(print: bool) -> Result<(), Error> {
+    if print {
+        println!(
+            "{}",
+            daemon::generate_plist(&current_binary()?, &config::load()?)?
+        );
+        return Ok(());
+    }
 
-pub fn execute() -> Result<(), Box<dyn std::error::Error>> {
     if daemon::is_installed()? {
-        println!("{}", style("Daemon is already running.").dim());
+        if let Some(stale) = daemon::stale_binary_path()? {
+            daemon::restart()?;
+            if !quiet() {
+                println!(
+                    "{} (was {})",
+                    style("Daemon reinstalled for this binary.").green().bold(),
+                    stale.display()
+                );
+            }
+        } else if !quiet() {
+            println!("{}", style("Daemon is already running.").dim());
+        }
         return Ok(());
     }
 
-    let binary_path =
-        std::env::current_exe().map_err(|e| format!("failed to resolve binary path: {e}"))?;
-
-    let plist = daemon::generate_plist(&binary_path)?;
+    let plist = daemon::generate_plist(&current_binary()?, &config::load()?)?;
     daemon::install(&plist)?;
 
-    println!("{}", style("Daemon activated.").green().bold());
+    if !quiet() {
+        println!("{}", style("Daemon activated.").green().bold());
+    }
 
     let needs_scan = {
         let mut guard = registry::Registry::locked()?;
         let reg = guard.load()?;
-        reg.list().is_empty()
+        reg.entries().is_empty()
     };
 
     if needs_scan {
@@ -28,3 +47,7 @@ pub fn execute() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+fn current_binary() -> Result<std::path::PathBuf, Error> {
+    std::env::current_exe().map_err(|e| format!("failed to resolve binary path: {e}").into())
+}
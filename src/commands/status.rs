@@ -1,41 +1,182 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use console::style;
 use indicatif::ProgressBar;
+use serde::Serialize;
 
-use crate::{daemon, disksize, registry};
+use super::paths::PathPrinter;
+use crate::builtins::BuiltinNames;
+use crate::disksize::SizeUnits;
+use crate::error::Error;
+use crate::{config, daemon, disksize, log, quiet, registry, tmutil};
 
-pub fn execute(refresh: bool) -> Result<(), Box<dyn std::error::Error>> {
-    if daemon::is_installed()? {
-        println!("{} {}", style("Daemon:").bold(), style("active").green());
-    } else {
-        println!("{} {}", style("Daemon:").bold(), style("inactive").yellow());
+#[derive(Serialize)]
+struct Largest {
+    path: String,
+    size_bytes: u64,
+}
+
+/// Managed exclusions sharing a builtin directory name, or `custom` for
+/// everything else.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct TypeTotal {
+    name: String,
+    size_bytes: u64,
+    count: usize,
+}
+
+/// The group `custom` exclusions (not named like a builtin) fall under.
+const CUSTOM_GROUP: &str = "custom";
+
+/// Groups `sized` paths by leaf directory name, largest group first.
+fn by_type(sized: &[(String, u64)], builtins: &BuiltinNames) -> Vec<TypeTotal> {
+    let mut groups: HashMap<&str, (u64, usize)> = HashMap::new();
+    for (path, size) in sized {
+        let name = Path::new(path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .filter(|n| builtins.contains(n))
+            .unwrap_or(CUSTOM_GROUP);
+        let group = groups.entry(name).or_default();
+        group.0 = group.0.saturating_add(*size);
+        group.1 += 1;
+    }
+
+    let mut totals: Vec<TypeTotal> = groups
+        .into_iter()
+        .map(|(name, (size_bytes, count))| TypeTotal {
+            name: name.to_string(),
+            size_bytes,
+            count,
+        })
+        .collect();
+    totals.sort_by(|a, b| {
+        b.size_bytes
+            .cmp(&a.size_bytes)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    totals
+}
+
+#[derive(Serialize)]
+struct Status {
+    daemon_active: bool,
+    managed_count: usize,
+    saved_bytes: Option<u64>,
+    last_full_run: Option<i64>,
+    last_update_check: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    largest: Option<Vec<Largest>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    by_type: Option<Vec<TypeTotal>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    drifted: Option<usize>,
+}
+
+/// What `status` reports beyond the daemon state and exclusion count.
+#[derive(Debug, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Options {
+    /// Re-measure sizes before reporting
+    pub refresh: bool,
+    /// With `refresh`, only re-measure directories whose mtime changed
+    pub stale_only: bool,
+    /// List this many of the largest exclusions
+    pub top: Option<usize>,
+    /// Break the total down by directory name
+    pub by_type: bool,
+    /// Ask tmutil whether managed paths are still excluded
+    pub verify: bool,
+}
+
+pub fn execute(options: &Options, json: bool) -> Result<(), Error> {
+    let Options {
+        refresh,
+        stale_only,
+        top,
+        by_type: breakdown,
+        verify,
+    } = *options;
+    let daemon_active = daemon::is_installed()?;
+    if daemon_active && let Some(stale) = daemon::stale_binary_path()? {
+        eprintln!(
+            "{} the daemon runs {}, not this binary; run `veiled start` to reinstall it",
+            style("warning:").yellow().bold(),
+            stale.display()
+        );
     }
 
+    let config = config::load()?;
     let mut guard = registry::Registry::locked()?;
     let mut reg = guard.load()?;
-    let count = reg.list().len();
+    let count = reg.entries().len();
 
-    if count == 0 {
-        println!("{}", style("No exclusions managed by veiled.").dim());
+    if refresh && count > 0 {
+        refresh_sizes(&mut guard, &mut reg, stale_only, json)?;
+    }
+
+    let largest: Option<Vec<Largest>> = match top {
+        Some(n) if n > 0 && count > 0 => Some(
+            reg.largest(config.size_mode)
+                .into_iter()
+                .take(n)
+                .map(|(path, size_bytes)| Largest { path, size_bytes })
+                .collect(),
+        ),
+        Some(_) => Some(vec![]),
+        None => None,
+    };
+
+    let by_type = if breakdown {
+        let builtins = BuiltinNames::new(&config.custom_builtins, &config.ignore_names)
+            .with_globs(&config.builtin_globs);
+        Some(by_type(&reg.largest(config.size_mode), &builtins))
+    } else {
+        None
+    };
+
+    let drifted = if verify {
+        Some(drift_count(&reg)?)
+    } else {
+        None
+    };
+
+    if json {
+        let status = Status {
+            daemon_active,
+            managed_count: count,
+            saved_bytes: reg.saved_bytes,
+            last_full_run: reg.last_full_run,
+            last_update_check: reg.last_update_check,
+            largest,
+            by_type,
+            drifted,
+        };
+        println!("{}", serde_json::to_string(&status)?);
         return Ok(());
     }
 
-    if refresh {
-        let spinner = ProgressBar::new_spinner();
-        spinner.set_message("Calculating saved space...");
-        spinner.enable_steady_tick(Duration::from_millis(80));
+    if quiet() {
+        return Ok(());
+    }
 
-        let total = disksize::calculate_total_size(reg.list());
-        reg.saved_bytes = Some(total);
-        guard.save(&reg)?;
+    if daemon_active {
+        println!("{} {}", style("Daemon:").bold(), style("active").green());
+    } else {
+        println!("{} {}", style("Daemon:").bold(), style("inactive").yellow());
+    }
+    print_activity(&reg);
 
-        spinner.finish_and_clear();
+    if count == 0 {
+        println!("{}", style("No exclusions managed by veiled.").dim());
+        return Ok(());
     }
 
     let saved = reg
         .saved_bytes
-        .map(|b| format!(" ({} saved)", disksize::format_size(b)));
+        .map(|b| format!(" ({} saved)", disksize::format_size(b, config.size_units)));
 
     println!(
         "{} {} {} excluded by veiled{}",
@@ -45,5 +186,191 @@ pub fn execute(refresh: bool) -> Result<(), Box<dyn std::error::Error>> {
         saved.unwrap_or_default(),
     );
 
+    if let Some(largest) = largest.filter(|l| !l.is_empty()) {
+        println!("{}", style("Largest:").bold());
+        let printer = PathPrinter::new(&config);
+        for entry in largest {
+            println!(
+                "{:>12}  {}",
+                disksize::format_size(entry.size_bytes, config.size_units),
+                printer.render(&entry.path)
+            );
+        }
+    }
+
+    if let Some(by_type) = by_type {
+        print_breakdown(&by_type, config.size_units);
+    }
+
+    if let Some(drifted) = drifted {
+        print_drift(drifted);
+    }
+
     Ok(())
 }
+
+fn print_drift(drifted: usize) {
+    if drifted == 0 {
+        println!(
+            "{}",
+            style("No drift: every managed path is excluded.").dim()
+        );
+        return;
+    }
+    println!(
+        "{}",
+        style(format!(
+            "{drifted} {} drifted (run `veiled reapply`)",
+            if drifted == 1 {
+                "exclusion"
+            } else {
+                "exclusions"
+            }
+        ))
+        .yellow()
+        .bold()
+    );
+}
+
+/// Counts managed paths that still exist but are no longer excluded, in one
+/// batched tmutil check. Suspended entries are meant to be included in
+/// backups and are left out.
+fn drift_count(reg: &registry::Registry) -> Result<usize, Box<dyn std::error::Error>> {
+    let now = registry::now_epoch();
+    let targets: Vec<PathBuf> = reg
+        .entries()
+        .iter()
+        .filter(|e| !e.is_suspended(now))
+        .map(|e| PathBuf::from(&e.path))
+        .filter(|p| p.exists())
+        .collect();
+    if targets.is_empty() {
+        return Ok(0);
+    }
+    let excluded = tmutil::are_excluded(&targets, config::load()?.exclusion_mode);
+    Ok(count_drifted(&excluded))
+}
+
+fn count_drifted(excluded: &[bool]) -> usize {
+    excluded.iter().filter(|excluded| !**excluded).count()
+}
+
+/// When the last full run and update check happened, relative to now.
+fn print_activity(reg: &registry::Registry) {
+    let now = registry::now_epoch();
+    let age =
+        |at: Option<i64>| at.map_or_else(|| "never".to_string(), |at| log::format_age(now - at));
+    println!("{} {}", style("Last run:").bold(), age(reg.last_full_run));
+    println!(
+        "{} {}",
+        style("Last update check:").bold(),
+        age(reg.last_update_check)
+    );
+}
+
+fn print_breakdown(by_type: &[TypeTotal], units: SizeUnits) {
+    println!("{}", style("By type:").bold());
+    for group in by_type {
+        println!(
+            "  {}: {} ({} {})",
+            group.name,
+            disksize::format_size(group.size_bytes, units),
+            group.count,
+            if group.count == 1 { "dir" } else { "dirs" }
+        );
+    }
+}
+
+/// Re-measures managed sizes for `--refresh` and stores the new total.
+fn refresh_sizes(
+    guard: &mut registry::LockedRegistry,
+    reg: &mut registry::Registry,
+    stale_only: bool,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let count = reg.entries().len();
+    let spinner = if json || quiet() {
+        ProgressBar::hidden()
+    } else {
+        let spinner = ProgressBar::new_spinner();
+        spinner.set_message("Calculating saved space...");
+        spinner.enable_steady_tick(Duration::from_millis(80));
+        spinner
+    };
+
+    let mode = config::load()?.size_mode;
+    let sizes = if stale_only {
+        reg.refresh_sizes(mode)
+    } else {
+        reg.recompute_sizes(mode)
+    };
+    reg.saved_bytes = Some(sizes.total);
+    guard.save(reg)?;
+
+    spinner.finish_and_clear();
+
+    if stale_only && !json && !quiet() {
+        println!(
+            "{} {} recomputed, {} cached",
+            style("Sizes:").bold(),
+            sizes.recomputed,
+            count - sizes.recomputed
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn total(name: &str, size_bytes: u64, count: usize) -> TypeTotal {
+        TypeTotal {
+            name: name.to_string(),
+            size_bytes,
+            count,
+        }
+    }
+
+    #[test]
+    fn by_type_groups_by_leaf_name_largest_first() {
+        let sized = vec![
+            ("/a/node_modules".to_string(), 300),
+            ("/b/target".to_string(), 1_000),
+            ("/c/node_modules".to_string(), 200),
+            ("/d/.bazel-out".to_string(), 50),
+        ];
+        let builtins = BuiltinNames::new(&[".bazel-out".to_string()], &[]);
+
+        assert_eq!(
+            by_type(&sized, &builtins),
+            vec![
+                total("target", 1_000, 1),
+                total("node_modules", 500, 2),
+                total(".bazel-out", 50, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn count_drifted_counts_paths_no_longer_excluded() {
+        assert_eq!(count_drifted(&[true, false, true, false]), 2);
+        assert_eq!(count_drifted(&[true, true]), 0);
+        assert_eq!(count_drifted(&[]), 0);
+    }
+
+    #[test]
+    fn by_type_puts_non_builtin_names_under_custom() {
+        let sized = vec![
+            ("/Users/dev/cache".to_string(), 40),
+            ("/Users/dev/scratch".to_string(), 60),
+            ("/x/target".to_string(), 10),
+        ];
+
+        assert_eq!(
+            by_type(&sized, &BuiltinNames::new(&[], &[])),
+            vec![total(CUSTOM_GROUP, 100, 2), total("target", 10, 1)]
+        );
+    }
+}
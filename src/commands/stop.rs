@@ -1,16 +1,21 @@
 use console::style;
 
-use crate::daemon;
+use crate::error::Error;
+use crate::{daemon, quiet};
 
-pub fn execute() -> Result<(), Box<dyn std::error::Error>> {
+pub fn execute() -> Result<(), Error> {
     if !daemon::is_installed()? {
-        println!("{}", style("Daemon is not running.").dim());
+        if !quiet() {
+            println!("{}", style("Daemon is not running.").dim());
+        }
         return Ok(());
     }
 
     daemon::uninstall()?;
 
-    println!("{}", style("Daemon deactivated.").yellow().bold());
+    if !quiet() {
+        println!("{}", style("Daemon deactivated.").yellow().bold());
+    }
 
     Ok(())
 }
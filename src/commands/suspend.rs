@@ -0,0 +1,51 @@
+use std::path::PathBuf;
+
+use console::style;
+
+use super::remove::lookup;
+use crate::error::Error;
+use crate::{config, quiet, registry, tmutil};
+
+/// Lifts the exclusion on a managed path so Time Machine backs it up, and
+/// keeps `run` from restoring it until `resume`.
+pub fn execute(path: &str) -> Result<(), Error> {
+    let cfg = config::load()?;
+    let mut guard = registry::Registry::locked()?;
+    let mut reg = guard.load()?;
+
+    let suspended = suspend_path(path, &cfg, &mut reg, None)?;
+    guard.save(&reg)?;
+
+    if !quiet() {
+        println!(
+            "{} {} (run `veiled resume` to exclude it again)",
+            style("Suspended:").bold(),
+            suspended.display()
+        );
+    }
+    Ok(())
+}
+
+/// Removes the exclusion on the registered `path` and suspends its entry
+/// until `until`, or until resumed when `None`.
+pub(super) fn suspend_path(
+    path: &str,
+    cfg: &config::Config,
+    reg: &mut registry::Registry,
+    until: Option<i64>,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let (lookup_path, exists) = lookup(path)?;
+    let lookup_str = lookup_path.to_string_lossy().into_owned();
+
+    if !reg.contains(&lookup_str) {
+        return Err(format!("{}: not managed by veiled", lookup_path.display()).into());
+    }
+    if !exists {
+        return Err(format!("{}: no such directory", lookup_path.display()).into());
+    }
+
+    tmutil::remove_exclusion(&lookup_path, cfg.exclusion_mode)?;
+    reg.suspend(&lookup_str, until);
+
+    Ok(lookup_path)
+}
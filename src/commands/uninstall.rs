@@ -0,0 +1,67 @@
+use std::fs;
+use std::path::PathBuf;
+
+use console::style;
+
+use super::{reset, stop};
+use crate::error::Error;
+use crate::{config, daemon, prompt, quiet, registry};
+
+pub fn execute(yes: bool) -> Result<(), Error> {
+    if !reset::remove_all(yes)? {
+        return Ok(());
+    }
+    stop::execute()?;
+
+    let dir = config::config_dir()?;
+    let files = footprint()?;
+    if files.is_empty() {
+        if !quiet() {
+            println!("{}", style("No config files to delete.").dim());
+        }
+        return Ok(());
+    }
+
+    if !yes
+        && !prompt::confirm(&format!(
+            "Delete config, registry and logs in {}?",
+            dir.display()
+        ))?
+    {
+        if !quiet() {
+            println!("{} {}", style("Kept:").bold(), dir.display());
+        }
+        return Ok(());
+    }
+
+    for file in &files {
+        fs::remove_file(file).map_err(|e| format!("could not delete {}: {e}", file.display()))?;
+        if !quiet() {
+            println!("{} {}", style("Deleted:").bold(), file.display());
+        }
+    }
+    // Only succeeds when nothing else was kept in the directory.
+    let _ = fs::remove_dir(&dir);
+
+    if !quiet() {
+        println!("{}", style("veiled has been uninstalled.").green().bold());
+    }
+
+    Ok(())
+}
+
+/// The files veiled keeps in its config dir that currently exist.
+fn footprint() -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let log_dir = daemon::log_dir()?;
+    let registry = registry::registry_path()?;
+    let mut files = vec![
+        config::config_path()?,
+        registry::lock_path(&registry),
+        registry,
+    ];
+    for name in [daemon::STDOUT_LOG, daemon::STDERR_LOG] {
+        files.push(log_dir.join(name));
+        files.push(log_dir.join(format!("{name}.1")));
+    }
+    Ok(files.into_iter().filter(|f| f.exists()).collect())
+}
@@ -1,31 +1,101 @@
 use console::style;
 
-use crate::{daemon, updater};
+use crate::error::{Error, exit_code};
+use crate::{config, daemon, quiet, updater};
 
-pub fn execute() -> Result<(), Box<dyn std::error::Error>> {
-    let current = updater::current_version();
-    println!(
-        "{} {}",
-        style("Checking for updates...").dim(),
-        style(format!("(current: {current})")).dim()
-    );
+/// What `update` found; `main` turns it into the process exit code.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// Up to date, updated, or held back by `max_update`.
+    Done,
+    /// `--check` found a newer version that `max_update` allows.
+    Available,
+}
 
-    let result = updater::check()?;
+impl Outcome {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Done => exit_code::SUCCESS,
+            Self::Available => exit_code::UPDATE_AVAILABLE,
+        }
+    }
+}
 
-    if result.updated {
+pub fn execute(check_only: bool) -> Result<Outcome, Error> {
+    let current = updater::current_version();
+    if !quiet() {
         println!(
-            "{} {} -> {}",
-            style("Updated:").bold(),
-            result.old_version,
-            result.new_version
+            "{} {}",
+            style("Checking for updates...").dim(),
+            style(format!("(current: {current})")).dim()
         );
+    }
+
+    let source = updater::UpdateSource::from_config(&config::load()?);
+
+    if check_only {
+        if let Some(latest) = updater::latest_available(&source)? {
+            if !source.allows(&latest)? {
+                if !quiet() {
+                    println!(
+                        "{} {current} -> {latest}",
+                        style("Update available but blocked by max_update:").yellow()
+                    );
+                }
+                return Ok(Outcome::Done);
+            }
+            if !quiet() {
+                println!(
+                    "{} {current} -> {latest}",
+                    style("Update available:").bold()
+                );
+            }
+            return Ok(Outcome::Available);
+        }
+        if !quiet() {
+            println!("{}", style("Already up to date.").dim());
+        }
+        return Ok(Outcome::Done);
+    }
+
+    let result = updater::check(&source)?;
+
+    if result.updated {
+        if !quiet() {
+            println!(
+                "{} {} -> {}",
+                style("Updated:").bold(),
+                result.old_version,
+                result.new_version
+            );
+        }
 
-        if daemon::restart()? {
+        if daemon::restart()? && !quiet() {
             println!("{}", style("Daemon restarted.").green().bold());
         }
-    } else {
+    } else if result.blocked {
+        if !quiet() {
+            println!(
+                "{} {} -> {}",
+                style("Update available but blocked by max_update:").yellow(),
+                result.old_version,
+                result.new_version
+            );
+        }
+    } else if !quiet() {
         println!("{}", style("Already up to date.").dim());
     }
 
-    Ok(())
+    Ok(Outcome::Done)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_an_available_update_changes_the_exit_code() {
+        assert_eq!(Outcome::Done.exit_code(), 0);
+        assert_eq!(Outcome::Available.exit_code(), 10);
+    }
 }
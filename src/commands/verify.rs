@@ -0,0 +1,85 @@
+use std::path::{Path, PathBuf};
+
+use console::style;
+
+use crate::error::Error;
+use crate::{config, quiet, registry, tmutil, verbose};
+
+pub fn execute() -> Result<(), Error> {
+    let mut guard = registry::Registry::locked()?;
+    let reg = guard.load()?;
+    let now = registry::now_epoch();
+    let entries: Vec<String> = reg
+        .entries()
+        .iter()
+        .filter(|e| !e.is_suspended(now))
+        .map(|e| e.path.clone())
+        .collect();
+    drop(guard);
+
+    if entries.is_empty() {
+        if !quiet() {
+            println!("{}", style("No exclusions managed by veiled.").dim());
+        }
+        return Ok(());
+    }
+
+    let (existing, missing): (Vec<_>, Vec<_>) =
+        entries.iter().partition(|p| Path::new(p.as_str()).exists());
+
+    if verbose() {
+        for path in &missing {
+            eprintln!(
+                "{} {path} no longer exists on disk, skipping",
+                style("verbose:").dim()
+            );
+        }
+    }
+
+    let paths: Vec<PathBuf> = existing.iter().map(PathBuf::from).collect();
+    let status = tmutil::are_excluded(&paths, config::load()?.exclusion_mode);
+
+    let drifted: Vec<&PathBuf> = paths
+        .iter()
+        .zip(status.iter())
+        .filter(|(_, excluded)| !**excluded)
+        .map(|(path, _)| path)
+        .collect();
+
+    for path in &drifted {
+        eprintln!(
+            "{} {} is no longer excluded",
+            style("drift:").yellow().bold(),
+            path.display()
+        );
+    }
+
+    if !drifted.is_empty() {
+        return Err(format!(
+            "{} of {} {} drifted",
+            drifted.len(),
+            paths.len(),
+            if paths.len() == 1 {
+                "exclusion"
+            } else {
+                "exclusions"
+            }
+        )
+        .into());
+    }
+
+    if !quiet() {
+        println!(
+            "{} {} {}",
+            style("Verified:").bold(),
+            paths.len(),
+            if paths.len() == 1 {
+                "exclusion"
+            } else {
+                "exclusions"
+            }
+        );
+    }
+
+    Ok(())
+}
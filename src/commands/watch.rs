@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::{Duration, Instant};
+
+use console::style;
+
+use crate::error::Error;
+use crate::tmutil::ExclusionMode;
+use crate::watcher::{Debouncer, Matcher};
+use crate::{config, log, quiet, registry, tmutil, watcher};
+
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+pub fn execute() -> Result<(), Error> {
+    let config = config::load()?;
+    if config.search_paths.is_empty() {
+        return Err("no search paths configured; add one with `veiled search-paths add`".into());
+    }
+
+    let matcher = Matcher::new(&config);
+    let events = watcher::watch(&config.search_paths)?;
+    let mut debouncer = Debouncer::new(DEBOUNCE);
+
+    if !quiet() {
+        println!(
+            "{} {} {} (Ctrl-C to stop)",
+            style("Watching").bold(),
+            config.search_paths.len(),
+            if config.search_paths.len() == 1 {
+                "search path"
+            } else {
+                "search paths"
+            }
+        );
+    }
+
+    loop {
+        match events.recv_timeout(DEBOUNCE) {
+            Ok(path) => debouncer.push(path, Instant::now()),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err("file system watcher stopped unexpectedly".into());
+            }
+        }
+
+        let targets: Vec<PathBuf> = debouncer
+            .drain_ready(Instant::now())
+            .into_iter()
+            .filter(|path| path.is_dir() && matcher.matches(path))
+            .collect();
+        if !targets.is_empty() {
+            exclude(&targets, config.exclusion_mode)?;
+        }
+    }
+}
+
+fn exclude(paths: &[PathBuf], mode: ExclusionMode) -> Result<(), Box<dyn std::error::Error>> {
+    let mut guard = registry::Registry::locked()?;
+    let mut reg = guard.load()?;
+    let mut added = 0usize;
+
+    for path in paths {
+        let path_str = path.to_string_lossy().into_owned();
+        if reg.contains(&path_str) {
+            continue;
+        }
+        if let Err(e) = tmutil::add_exclusion(path, mode) {
+            log::warn(&e.to_string());
+            continue;
+        }
+        reg.add(&path_str);
+        added += 1;
+        if !quiet() {
+            println!("{} {}", style("Excluded:").bold(), path.display());
+        }
+    }
+
+    if added > 0 {
+        guard.save(&reg)?;
+    }
+    Ok(())
+}
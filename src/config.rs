@@ -1,18 +1,67 @@
+use std::collections::HashSet;
+use std::ffi::OsString;
 use std::fs;
 use std::io::{Read as _, Seek, Write as _};
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 use console::style;
 use fs2::FileExt;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::disksize::{SizeMode, SizeUnits};
+use crate::error::Error;
+use crate::scanner::MatchDepth;
+use crate::tmutil::ExclusionMode;
+use crate::updater::UpdateChannel;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Config {
     pub search_paths: Vec<String>,
     pub extra_exclusions: Vec<String>,
     pub ignore_paths: Vec<String>,
     pub auto_update: bool,
+    pub custom_builtins: Vec<String>,
+    pub builtin_globs: Vec<String>,
+    pub exclude_file_globs: Vec<String>,
+    pub ignore_names: Vec<String>,
+    #[serde(deserialize_with = "deserialize_size")]
+    pub min_size_bytes: u64,
+    #[serde(
+        deserialize_with = "deserialize_optional_size",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_size_bytes: Option<u64>,
+    pub schedule_hour: u8,
+    pub schedule_minute: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scan_interval_hours: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<usize>,
+    pub follow_symlinks: bool,
+    pub include_hidden: bool,
+    pub match_depth: MatchDepth,
+    pub git_timeout_secs: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scan_concurrency: Option<usize>,
+    pub size_mode: SizeMode,
+    pub size_units: SizeUnits,
+    pub exclusion_mode: ExclusionMode,
+    pub update_channel: UpdateChannel,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_api_base: Option<String>,
+    pub update_mirror_origins: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_update: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_run_command: Option<String>,
+    pub notify_on_run: bool,
+    #[serde(deserialize_with = "deserialize_size")]
+    pub warn_threshold_bytes: u64,
+    pub warn_threshold_count: usize,
+    pub suspend_hours: u32,
 }
 
 impl Default for Config {
@@ -26,10 +75,86 @@ impl Default for Config {
                 "~/Downloads".to_string(),
             ],
             auto_update: true,
+            custom_builtins: vec![],
+            builtin_globs: vec![],
+            exclude_file_globs: vec![],
+            ignore_names: vec![],
+            min_size_bytes: 0,
+            max_size_bytes: None,
+            schedule_hour: 3,
+            schedule_minute: 0,
+            scan_interval_hours: None,
+            max_depth: None,
+            follow_symlinks: false,
+            include_hidden: false,
+            match_depth: MatchDepth::Any,
+            git_timeout_secs: 10,
+            scan_concurrency: None,
+            size_mode: SizeMode::Logical,
+            size_units: SizeUnits::Binary,
+            exclusion_mode: ExclusionMode::Sticky,
+            update_channel: UpdateChannel::Stable,
+            update_api_base: None,
+            update_mirror_origins: vec![],
+            max_update: None,
+            post_run_command: None,
+            notify_on_run: false,
+            warn_threshold_bytes: 50 * 1_073_741_824,
+            warn_threshold_count: 500,
+            suspend_hours: 24,
         }
     }
 }
 
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SizeValue {
+    Bytes(u64),
+    Human(String),
+}
+
+fn deserialize_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    match SizeValue::deserialize(deserializer)? {
+        SizeValue::Bytes(bytes) => Ok(bytes),
+        SizeValue::Human(s) => parse_size(&s).map_err(serde::de::Error::custom),
+    }
+}
+
+fn deserialize_optional_size<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u64>, D::Error> {
+    deserialize_size(deserializer).map(Some)
+}
+
+/// Parses sizes like `"500"`, `"10MB"`, `"1.5 GB"` or `"2GiB"` into bytes.
+/// Every unit is binary, whichever way it is spelled.
+pub fn parse_size(input: &str) -> Result<u64, String> {
+    let trimmed = input.trim();
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "KB" | "KIB" | "K" => 1_024,
+        "MB" | "MIB" | "M" => 1_048_576,
+        "GB" | "GIB" | "G" => 1_073_741_824,
+        other => return Err(format!("invalid size unit: {other}")),
+    };
+
+    let value: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid size: {input}"))?;
+
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    Ok((value * multiplier as f64) as u64)
+}
+
 #[derive(Deserialize)]
 #[serde(default, rename_all = "camelCase")]
 struct LegacyConfig {
@@ -57,16 +182,179 @@ impl From<LegacyConfig> for Config {
             extra_exclusions: legacy.extra_exclusions,
             ignore_paths: legacy.ignore_paths,
             auto_update: legacy.auto_update,
+            ..Self::default()
         }
     }
 }
 
-fn config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+static CONFIG_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+static CONFIG_FILE_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Points every piece of veiled state at `dir` for the rest of the process.
+/// Set once from the `--config-dir` flag before any command runs.
+pub fn set_config_dir(dir: PathBuf) {
+    let _ = CONFIG_DIR_OVERRIDE.set(dir);
+}
+
+/// Reads and writes the config at `file` for the rest of the process, while
+/// the registry and logs stay in `config_dir()`. Set once from the
+/// `--config` flag before any command runs.
+pub fn set_config_file(file: PathBuf) {
+    let _ = CONFIG_FILE_OVERRIDE.set(file);
+}
+
+/// Directory holding the config, registry and daemon logs: `--config-dir`,
+/// then `VEILED_CONFIG_DIR`, then `$XDG_CONFIG_HOME/veiled`, then
+/// `~/.config/veiled`.
+pub fn config_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if let Some(dir) = CONFIG_DIR_OVERRIDE.get() {
+        return Ok(dir.clone());
+    }
     if let Ok(dir) = std::env::var("VEILED_CONFIG_DIR") {
-        return Ok(PathBuf::from(dir).join("config.toml"));
+        return Ok(PathBuf::from(dir));
+    }
+    if let Some(base) = xdg_config_home() {
+        return Ok(base.join("veiled"));
     }
     let home = dirs::home_dir().ok_or("could not determine home directory")?;
-    Ok(home.join(".config/veiled/config.toml"))
+    Ok(home.join(".config/veiled"))
+}
+
+/// The directory `--config-dir` or `VEILED_CONFIG_DIR` picked, made
+/// absolute, so the daemon can be pointed at the same profile.
+pub fn config_dir_override() -> Option<PathBuf> {
+    CONFIG_DIR_OVERRIDE
+        .get()
+        .cloned()
+        .or_else(|| std::env::var_os("VEILED_CONFIG_DIR").map(PathBuf::from))
+        .and_then(|dir| std::path::absolute(dir).ok())
+}
+
+/// The file `--config` picked, made absolute, so the daemon can be pointed
+/// at the same config.
+pub fn config_file_override() -> Option<PathBuf> {
+    CONFIG_FILE_OVERRIDE
+        .get()
+        .and_then(|file| std::path::absolute(file).ok())
+}
+
+/// `$XDG_CONFIG_HOME`, if set to an absolute path.
+pub fn xdg_config_home() -> Option<PathBuf> {
+    parse_xdg_config_home(std::env::var_os("XDG_CONFIG_HOME"))
+}
+
+/// The XDG spec says empty and relative values must be ignored.
+fn parse_xdg_config_home(value: Option<OsString>) -> Option<PathBuf> {
+    value.map(PathBuf::from).filter(|path| path.is_absolute())
+}
+
+pub fn config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if let Some(file) = CONFIG_FILE_OVERRIDE.get() {
+        return Ok(file.clone());
+    }
+    Ok(config_dir()?.join("config.toml"))
+}
+
+/// Where a machine-wide baseline config is read from. `VEILED_SYSTEM_CONFIG`
+/// points it elsewhere.
+pub fn system_config_path() -> PathBuf {
+    std::env::var_os("VEILED_SYSTEM_CONFIG").map_or_else(
+        || PathBuf::from("/Library/Application Support/veiled/config.toml"),
+        PathBuf::from,
+    )
+}
+
+/// The system config, if one is installed.
+fn active_system_config() -> Option<PathBuf> {
+    Some(system_config_path()).filter(|path| path.is_file())
+}
+
+/// List keys where the user's entries are added to the system's instead of
+/// replacing them.
+const APPENDED_KEYS: &[&str] = &["search_paths", "extra_exclusions"];
+
+/// Overlays the user's config table on the system's: `APPENDED_KEYS` lists
+/// are concatenated (system entries first, duplicates dropped) and every
+/// other key the user set wins. Keys the user left out keep the system value.
+fn merge(system: toml::Table, user: toml::Table) -> toml::Table {
+    let mut merged = system;
+    for (key, value) in user {
+        match (merged.get_mut(&key), value) {
+            (Some(toml::Value::Array(base)), toml::Value::Array(extra))
+                if APPENDED_KEYS.contains(&key.as_str()) =>
+            {
+                for item in extra {
+                    if !base.contains(&item) {
+                        base.push(item);
+                    }
+                }
+            }
+            (_, value) => {
+                merged.insert(key, value);
+            }
+        }
+    }
+    merged
+}
+
+/// Reads `path` as a TOML table, warning and treating it as empty when it
+/// is missing or malformed.
+fn read_table(path: &Path) -> toml::Table {
+    let Ok(content) = fs::read_to_string(path) else {
+        return toml::Table::new();
+    };
+    toml::from_str(&content).unwrap_or_else(|e| {
+        eprintln!(
+            "{} failed to parse {}: {e}",
+            style("warning:").yellow().bold(),
+            path.display()
+        );
+        toml::Table::new()
+    })
+}
+
+/// Loads the user config at `path` merged over the system config at
+/// `system`. A missing user config is created empty so that it inherits
+/// every system setting.
+fn load_merged(system: &Path, path: &Path) -> Result<Config, Box<dyn std::error::Error>> {
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(
+            path,
+            format!("# Settings here extend or override {}\n", system.display()),
+        )?;
+    }
+
+    Ok(merged_config(
+        system,
+        read_table(system),
+        path,
+        read_table(path),
+    ))
+}
+
+/// Builds the config `user` (read from `path`) produces on top of `system`
+/// (read from `system_path`), falling back to defaults when the merged
+/// settings are invalid.
+fn merged_config(
+    system_path: &Path,
+    system: toml::Table,
+    path: &Path,
+    user: toml::Table,
+) -> Config {
+    let mut config: Config = merge(system, user).try_into().unwrap_or_else(|e| {
+        eprintln!(
+            "{} invalid settings in {} or {}: {e}",
+            style("warning:").yellow().bold(),
+            system_path.display(),
+            path.display()
+        );
+        Config::default()
+    });
+    expand_paths(&mut config);
+    config
 }
 
 pub fn expand_tilde(path: &str) -> PathBuf {
@@ -148,8 +436,22 @@ pub fn save_to(config: &Config, path: &Path) -> Result<(), Box<dyn std::error::E
     Ok(())
 }
 
+/// The effective config: the user's, merged over the system config when
+/// one is installed.
 pub fn load() -> Result<Config, Box<dyn std::error::Error>> {
-    load_from(&config_path()?)
+    let path = config_path()?;
+    match active_system_config() {
+        Some(system) => load_merged(&system, &path),
+        None => load_from(&path),
+    }
+}
+
+/// Parses config TOML strictly, without the fallback to defaults that
+/// `load_from` applies, and expands `~` in its paths.
+pub fn parse(content: &str) -> Result<Config, toml::de::Error> {
+    let mut config: Config = toml::from_str(content)?;
+    expand_paths(&mut config);
+    Ok(config)
 }
 
 pub fn load_from(path: &Path) -> Result<Config, Box<dyn std::error::Error>> {
@@ -192,26 +494,47 @@ pub fn load_from(path: &Path) -> Result<Config, Box<dyn std::error::Error>> {
     Ok(config)
 }
 
+/// Creates `path` if needed and takes an exclusive lock on it, blocking until
+/// any other holder releases it.
+pub(crate) fn open_locked(path: &Path) -> std::io::Result<fs::File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+    file.lock_exclusive()?;
+    Ok(file)
+}
+
+/// The user config file, held locked while a command edits it. With a
+/// system config installed, `load` returns the merged settings and `save`
+/// writes back only what differs from the system's, so untouched keys keep
+/// inheriting.
 pub struct LockedConfig {
     file: fs::File,
     path: PathBuf,
+    inherited: Option<Inherited>,
+}
+
+/// What a user file extends: the keys it set itself and the settings the
+/// system config provides on its own.
+struct Inherited {
+    user_keys: HashSet<String>,
+    system: Config,
 }
 
 impl LockedConfig {
     fn acquire(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        let file = fs::OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(false)
-            .open(path)?;
-        file.lock_exclusive()?;
+        let file = open_locked(path)
+            .map_err(|e| Error::Config(format!("failed to open config {}: {e}", path.display())))?;
         Ok(Self {
             file,
             path: path.to_path_buf(),
+            inherited: None,
         })
     }
 
@@ -219,37 +542,42 @@ impl LockedConfig {
         self.file.rewind()?;
         let metadata = self.file.metadata()?;
 
-        if metadata.len() == 0 {
-            if let Some(parent) = self.path.parent() {
-                let json_path = parent.join("config.json");
-                if json_path.exists() {
-                    let content = fs::read_to_string(&json_path)?;
-                    match serde_json::from_str::<LegacyConfig>(&content) {
-                        Ok(legacy) => {
-                            let config: Config = legacy.into();
-                            self.save(&config)?;
-                            fs::remove_file(&json_path)?;
-                            let mut expanded = config;
-                            expand_paths(&mut expanded);
-                            return Ok(expanded);
-                        }
-                        Err(e) => {
-                            eprintln!(
-                                "{} failed to parse {}: {e}",
-                                style("warning:").yellow().bold(),
-                                json_path.display()
-                            );
-                        }
+        if metadata.len() == 0
+            && let Some(parent) = self.path.parent()
+        {
+            let json_path = parent.join("config.json");
+            if json_path.exists() {
+                let content = fs::read_to_string(&json_path)?;
+                match serde_json::from_str::<LegacyConfig>(&content) {
+                    Ok(legacy) => {
+                        let config: Config = legacy.into();
+                        self.save(&config)?;
+                        fs::remove_file(&json_path)?;
+                        let mut expanded = config;
+                        expand_paths(&mut expanded);
+                        return Ok(expanded);
+                    }
+                    Err(e) => {
+                        eprintln!(
+                            "{} failed to parse {}: {e}",
+                            style("warning:").yellow().bold(),
+                            json_path.display()
+                        );
                     }
                 }
             }
-            let mut config = Config::default();
-            expand_paths(&mut config);
-            return Ok(config);
         }
 
         let mut content = String::new();
         self.file.read_to_string(&mut content)?;
+        if let Some(system_path) = active_system_config() {
+            return Ok(self.load_over_system(&system_path, &content));
+        }
+        if content.is_empty() {
+            let mut config = Config::default();
+            expand_paths(&mut config);
+            return Ok(config);
+        }
         let mut config: Config = match toml::from_str(&content) {
             Ok(c) => c,
             Err(e) => {
@@ -265,18 +593,77 @@ impl LockedConfig {
         Ok(config)
     }
 
+    /// Merges the user file's `content` over the system config at
+    /// `system_path`, remembering both so `save` can strip what is inherited.
+    fn load_over_system(&mut self, system_path: &Path, content: &str) -> Config {
+        let user = toml::from_str::<toml::Table>(content).unwrap_or_else(|e| {
+            eprintln!(
+                "{} failed to parse {}: {e}",
+                style("warning:").yellow().bold(),
+                self.path.display()
+            );
+            toml::Table::new()
+        });
+        let system = read_table(system_path);
+        self.inherited = Some(Inherited {
+            user_keys: user.keys().cloned().collect(),
+            system: merged_config(system_path, system.clone(), &self.path, toml::Table::new()),
+        });
+        merged_config(system_path, system, &self.path, user)
+    }
+
+    /// The settings the system config provides, when one is installed.
+    pub fn system(&self) -> Option<&Config> {
+        self.inherited.as_ref().map(|inherited| &inherited.system)
+    }
+
     pub fn save(&mut self, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
         let mut collapsed = config.clone();
         collapse_paths(&mut collapsed);
-        let content = toml::to_string_pretty(&collapsed)?;
+        let content = match &self.inherited {
+            Some(inherited) => {
+                let mut system = inherited.system.clone();
+                collapse_paths(&mut system);
+                toml::to_string_pretty(&user_owned(&collapsed, &system, &inherited.user_keys)?)?
+            }
+            None => toml::to_string_pretty(&collapsed)?,
+        };
+        self.write(content.as_bytes()).map_err(|e| {
+            Error::Config(format!("failed to write {}: {e}", self.path.display())).into()
+        })
+    }
+
+    fn write(&mut self, content: &[u8]) -> std::io::Result<()> {
         self.file.set_len(0)?;
         self.file.rewind()?;
-        self.file.write_all(content.as_bytes())?;
-        self.file.sync_data()?;
-        Ok(())
+        self.file.write_all(content)?;
+        self.file.sync_data()
     }
 }
 
+/// The part of the merged `config` that belongs in a user file extending
+/// `system`: `APPENDED_KEYS` lists lose the system's entries, and a key is
+/// kept when the user file already set it or it now differs from the system.
+fn user_owned(
+    config: &Config,
+    system: &Config,
+    keys: &HashSet<String>,
+) -> Result<toml::Table, Box<dyn std::error::Error>> {
+    let system = toml::Table::try_from(system)?;
+    let mut table = toml::Table::try_from(config)?;
+    table.retain(|key, value| {
+        if let (toml::Value::Array(list), Some(toml::Value::Array(inherited))) =
+            (&mut *value, system.get(key))
+            && APPENDED_KEYS.contains(&key)
+        {
+            list.retain(|item| !inherited.contains(item));
+            return keys.contains(key) || !list.is_empty();
+        }
+        keys.contains(key) || system.get(key) != Some(value)
+    });
+    Ok(table)
+}
+
 impl Config {
     pub fn locked() -> Result<LockedConfig, Box<dyn std::error::Error>> {
         LockedConfig::acquire(&config_path()?)
@@ -286,6 +673,22 @@ impl Config {
     pub fn locked_at(path: &Path) -> Result<LockedConfig, Box<dyn std::error::Error>> {
         LockedConfig::acquire(path)
     }
+
+    /// Search paths equal to or nested under an ignore path, which a scan
+    /// could never find anything in, each paired with the ignore path that
+    /// covers it.
+    pub fn ignored_search_paths(&self) -> Vec<(&str, &str)> {
+        self.search_paths
+            .iter()
+            .filter_map(|search| {
+                let search_path = expand_tilde(search);
+                self.ignore_paths
+                    .iter()
+                    .find(|ignore| search_path.starts_with(expand_tilde(ignore)))
+                    .map(|ignore| (search.as_str(), ignore.as_str()))
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -293,6 +696,101 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    fn table(content: &str) -> toml::Table {
+        toml::from_str(content).unwrap()
+    }
+
+    #[test]
+    fn merge_appends_user_lists_to_system_lists() {
+        let merged = merge(
+            table("search_paths = [\"/fleet/src\"]\nextra_exclusions = [\"/fleet/cache\"]\n"),
+            table("search_paths = [\"/Users/dev/code\", \"/fleet/src\"]\n"),
+        );
+
+        assert_eq!(
+            merged,
+            table(
+                "search_paths = [\"/fleet/src\", \"/Users/dev/code\"]\nextra_exclusions = [\"/fleet/cache\"]\n"
+            )
+        );
+    }
+
+    #[test]
+    fn merge_lets_user_scalars_win_and_falls_back_to_system() {
+        let merged = merge(
+            table("auto_update = false\nschedule_hour = 1\nignore_paths = [\"/fleet\"]\n"),
+            table("schedule_hour = 5\nignore_paths = [\"/mine\"]\n"),
+        );
+        let config: Config = merged.try_into().unwrap();
+
+        assert!(!config.auto_update);
+        assert_eq!(config.schedule_hour, 5);
+        assert_eq!(config.ignore_paths, vec!["/mine"]);
+        assert_eq!(config.schedule_minute, Config::default().schedule_minute);
+    }
+
+    #[test]
+    fn load_merged_creates_user_file_that_inherits_system_settings() {
+        let dir = TempDir::new().unwrap();
+        let system = dir.path().join("system.toml");
+        let path = dir.path().join("user/config.toml");
+        fs::write(
+            &system,
+            "search_paths = [\"/fleet/src\"]\nauto_update = false\n",
+        )
+        .unwrap();
+
+        let config = load_merged(&system, &path).unwrap();
+
+        assert!(path.exists());
+        assert_eq!(config.search_paths, vec!["/fleet/src"]);
+        assert!(!config.auto_update);
+    }
+
+    #[test]
+    fn user_owned_keeps_set_and_changed_keys_only() {
+        let system = Config {
+            search_paths: vec!["/fleet/src".to_string()],
+            ..Config::default()
+        };
+        let mut config = Config {
+            search_paths: vec!["/fleet/src".to_string(), "/Users/dev/code".to_string()],
+            ..system.clone()
+        };
+        config.schedule_hour = 6;
+        let keys: HashSet<String> = ["auto_update".to_string()].into();
+
+        let owned = user_owned(&config, &system, &keys).unwrap();
+        let mut owned_keys: Vec<&str> = owned.keys().map(String::as_str).collect();
+        owned_keys.sort_unstable();
+
+        assert_eq!(owned_keys, ["auto_update", "schedule_hour", "search_paths"]);
+        assert_eq!(
+            owned["search_paths"],
+            toml::Value::Array(vec!["/Users/dev/code".into()])
+        );
+    }
+
+    #[test]
+    fn user_owned_keeps_system_entries_of_replaced_lists() {
+        let system = Config {
+            ignore_paths: vec!["/fleet".to_string()],
+            ..Config::default()
+        };
+        let config = Config {
+            ignore_paths: vec!["/fleet".to_string(), "/Users/dev/x".to_string()],
+            ..system.clone()
+        };
+
+        let owned = user_owned(&config, &system, &HashSet::new()).unwrap();
+
+        assert_eq!(owned.keys().collect::<Vec<_>>(), ["ignore_paths"]);
+        let merged: Config = merge(toml::Table::try_from(&system).unwrap(), owned)
+            .try_into()
+            .unwrap();
+        assert_eq!(merged.ignore_paths, ["/fleet", "/Users/dev/x"]);
+    }
+
     #[test]
     fn creates_default_config_when_missing() {
         let dir = TempDir::new().unwrap();
@@ -389,8 +887,10 @@ mod tests {
         let dir = TempDir::new().unwrap();
         let path = dir.path().join("config.toml");
 
-        let mut config = Config::default();
-        config.extra_exclusions = vec!["/Users/dev/cache".to_string()];
+        let config = Config {
+            extra_exclusions: vec!["/Users/dev/cache".to_string()],
+            ..Config::default()
+        };
         save_to(&config, &path).unwrap();
 
         let loaded = load_from(&path).unwrap();
@@ -424,6 +924,271 @@ mod tests {
         assert_eq!(config.ignore_paths.len(), 3);
     }
 
+    #[test]
+    fn custom_builtins_defaults_to_empty() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+
+        fs::write(&path, "auto_update = false\n").unwrap();
+
+        let config = load_from(&path).unwrap();
+
+        assert!(config.custom_builtins.is_empty());
+    }
+
+    #[test]
+    fn xdg_config_home_requires_an_absolute_path() {
+        assert_eq!(
+            parse_xdg_config_home(Some("/Users/dev/dotfiles".into())),
+            Some(PathBuf::from("/Users/dev/dotfiles"))
+        );
+        assert_eq!(parse_xdg_config_home(Some("dotfiles".into())), None);
+        assert_eq!(parse_xdg_config_home(Some(OsString::new())), None);
+        assert_eq!(parse_xdg_config_home(None), None);
+    }
+
+    fn overlap_config(search: &str, ignore: &str) -> Config {
+        Config {
+            search_paths: vec![search.to_string()],
+            ignore_paths: vec![ignore.to_string()],
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn search_path_equal_to_ignore_path_is_reported() {
+        let config = overlap_config("~/Projects", "~/Projects/");
+        assert_eq!(
+            config.ignored_search_paths(),
+            vec![("~/Projects", "~/Projects/")]
+        );
+    }
+
+    #[test]
+    fn search_path_nested_under_ignore_path_is_reported() {
+        let home = dirs::home_dir().unwrap();
+        let ignore = home.join("Library").to_string_lossy().into_owned();
+        let config = overlap_config("~/Library/Mobile Documents", &ignore);
+        assert_eq!(
+            config.ignored_search_paths(),
+            vec![("~/Library/Mobile Documents", ignore.as_str())]
+        );
+    }
+
+    #[test]
+    fn disjoint_search_and_ignore_paths_are_not_reported() {
+        let config = overlap_config("~/Projects2", "~/Projects");
+        assert!(config.ignored_search_paths().is_empty());
+
+        let config = overlap_config("~/Projects", "~/Projects/archive");
+        assert!(config.ignored_search_paths().is_empty());
+    }
+
+    #[test]
+    fn loads_exclude_file_globs() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+
+        fs::write(&path, "exclude_file_globs = [\"*.iso\"]\n").unwrap();
+
+        let config = load_from(&path).unwrap();
+
+        assert_eq!(config.exclude_file_globs, vec!["*.iso"]);
+    }
+
+    #[test]
+    fn loads_ignore_names() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+
+        fs::write(&path, "ignore_names = [\"vendor\"]\n").unwrap();
+
+        let config = load_from(&path).unwrap();
+
+        assert_eq!(config.ignore_names, vec!["vendor"]);
+    }
+
+    #[test]
+    fn loads_custom_builtins() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+
+        fs::write(
+            &path,
+            "custom_builtins = [\".bazel-out\", \".cargo-cache\"]\n",
+        )
+        .unwrap();
+
+        let config = load_from(&path).unwrap();
+
+        assert_eq!(config.custom_builtins, vec![".bazel-out", ".cargo-cache"]);
+    }
+
+    #[test]
+    fn min_size_bytes_defaults_to_zero() {
+        let config = Config::default();
+        assert_eq!(config.min_size_bytes, 0);
+    }
+
+    #[test]
+    fn warn_thresholds_default_to_50_gb_and_500_dirs() {
+        let config = Config::default();
+        assert_eq!(config.warn_threshold_bytes, 50 * 1_073_741_824);
+        assert_eq!(config.warn_threshold_count, 500);
+    }
+
+    #[test]
+    fn loads_warn_threshold_bytes_as_human_string() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+
+        fs::write(&path, "warn_threshold_bytes = \"1GB\"\n").unwrap();
+
+        let config = load_from(&path).unwrap();
+
+        assert_eq!(config.warn_threshold_bytes, 1_073_741_824);
+    }
+
+    #[test]
+    fn loads_match_depth() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+
+        assert_eq!(Config::default().match_depth, MatchDepth::Any);
+        fs::write(&path, "match_depth = \"root-only\"\n").unwrap();
+
+        let config = load_from(&path).unwrap();
+
+        assert_eq!(config.match_depth, MatchDepth::RootOnly);
+    }
+
+    #[test]
+    fn loads_min_size_as_integer() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+
+        fs::write(&path, "min_size_bytes = 2048\n").unwrap();
+
+        let config = load_from(&path).unwrap();
+
+        assert_eq!(config.min_size_bytes, 2048);
+    }
+
+    #[test]
+    fn loads_min_size_as_human_string() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+
+        fs::write(&path, "min_size_bytes = \"10MB\"\n").unwrap();
+
+        let config = load_from(&path).unwrap();
+
+        assert_eq!(config.min_size_bytes, 10 * 1_048_576);
+    }
+
+    #[test]
+    fn max_size_defaults_to_none_and_loads_human_string() {
+        assert!(Config::default().max_size_bytes.is_none());
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "max_size_bytes = \"20GB\"\n").unwrap();
+
+        let config = load_from(&path).unwrap();
+
+        assert_eq!(config.max_size_bytes, Some(20 * 1_073_741_824));
+    }
+
+    #[test]
+    fn parse_size_handles_units() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("512B"), Ok(512));
+        assert_eq!(parse_size("1KB"), Ok(1_024));
+        assert_eq!(parse_size("10 MB"), Ok(10_485_760));
+        assert_eq!(parse_size("2GiB"), Ok(2_147_483_648));
+        assert_eq!(parse_size("1.5gb"), Ok(1_610_612_736));
+    }
+
+    #[test]
+    fn parse_size_rejects_invalid_input() {
+        assert!(parse_size("").is_err());
+        assert!(parse_size("MB").is_err());
+        assert!(parse_size("10 TB").is_err());
+        assert!(parse_size("ten MB").is_err());
+    }
+
+    #[test]
+    fn schedule_defaults_to_three_am() {
+        let config = Config::default();
+        assert_eq!(config.schedule_hour, 3);
+        assert_eq!(config.schedule_minute, 0);
+    }
+
+    #[test]
+    fn loads_scan_interval_hours() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+
+        fs::write(&path, "scan_interval_hours = 6\n").unwrap();
+
+        let config = load_from(&path).unwrap();
+
+        assert_eq!(config.scan_interval_hours, Some(6));
+    }
+
+    #[test]
+    fn scan_interval_hours_omitted_when_unset() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+
+        save_to(&Config::default(), &path).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("scan_interval_hours"));
+    }
+
+    #[test]
+    fn loads_size_mode() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+
+        fs::write(&path, "size_mode = \"allocated\"\n").unwrap();
+
+        let config = load_from(&path).unwrap();
+
+        assert_eq!(config.size_mode, SizeMode::Allocated);
+    }
+
+    #[test]
+    fn size_mode_defaults_to_logical() {
+        let config = Config::default();
+        assert_eq!(config.size_mode, SizeMode::Logical);
+    }
+
+    #[test]
+    fn loads_exclusion_mode() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+
+        fs::write(&path, "exclusion_mode = \"fixed\"\n").unwrap();
+
+        let config = load_from(&path).unwrap();
+
+        assert_eq!(config.exclusion_mode, ExclusionMode::Fixed);
+    }
+
+    #[test]
+    fn loads_update_channel() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+
+        fs::write(&path, "update_channel = \"prerelease\"\n").unwrap();
+
+        let config = load_from(&path).unwrap();
+
+        assert_eq!(config.update_channel, UpdateChannel::Prerelease);
+    }
+
     #[test]
     fn migrates_json_to_toml() {
         let dir = TempDir::new().unwrap();
@@ -473,8 +1238,10 @@ mod tests {
         let path = dir.path().join("config.toml");
         let home = dirs::home_dir().unwrap().to_string_lossy().into_owned();
 
-        let mut config = Config::default();
-        config.search_paths = vec![format!("{home}/Projects")];
+        let config = Config {
+            search_paths: vec![format!("{home}/Projects")],
+            ..Config::default()
+        };
         save_to(&config, &path).unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
@@ -488,8 +1255,10 @@ mod tests {
         let path = dir.path().join("config.toml");
         let home = dirs::home_dir().unwrap().to_string_lossy().into_owned();
 
-        let mut config = Config::default();
-        config.extra_exclusions = vec![format!("{home}/cache")];
+        let config = Config {
+            extra_exclusions: vec![format!("{home}/cache")],
+            ..Config::default()
+        };
         save_to(&config, &path).unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
@@ -533,6 +1302,43 @@ mod tests {
         assert_eq!(loaded.extra_exclusions.len(), 1);
     }
 
+    #[test]
+    fn locked_concurrent_writers_do_not_lose_updates() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+
+        let handles: Vec<_> = ["/Users/dev/cache-a", "/Users/dev/cache-b"]
+            .into_iter()
+            .map(|entry| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let mut guard = Config::locked_at(&path).unwrap();
+                    let mut config = guard.load().unwrap();
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                    config.extra_exclusions.push(entry.to_string());
+                    guard.save(&config).unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let loaded = Config::locked_at(&path).unwrap().load().unwrap();
+
+        assert_eq!(loaded.extra_exclusions.len(), 2);
+        assert!(
+            loaded
+                .extra_exclusions
+                .contains(&"/Users/dev/cache-a".to_string())
+        );
+        assert!(
+            loaded
+                .extra_exclusions
+                .contains(&"/Users/dev/cache-b".to_string())
+        );
+    }
+
     #[test]
     fn locked_creates_defaults_when_empty() {
         let dir = TempDir::new().unwrap();
@@ -625,6 +1431,11 @@ mod fuzz_tests {
             let _ = expand_tilde(&data);
         }
 
+        #[test]
+        fn parse_size_never_panics(data in "\\PC{0,64}") {
+            let _ = parse_size(&data);
+        }
+
         #[test]
         fn collapse_tilde_never_panics(data in "\\PC{0,256}") {
             let _ = collapse_tilde(&data);
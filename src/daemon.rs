@@ -2,7 +2,17 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
+use crate::config::{self, Config};
+use crate::error::Error;
+
 const LABEL: &str = "com.veiled.agent";
+const DAEMON_ENV: &str = "VEILED_DAEMON";
+pub const STDOUT_LOG: &str = "stdout.log";
+pub const STDERR_LOG: &str = "stderr.log";
+
+pub fn is_daemon_context() -> bool {
+    std::env::var_os(DAEMON_ENV).is_some_and(|v| !v.is_empty())
+}
 
 fn current_uid() -> u32 {
     // SAFETY: getuid() is a single syscall with no failure mode
@@ -24,9 +34,8 @@ pub fn plist_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
         .join(format!("{LABEL}.plist")))
 }
 
-fn log_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
-    let home = dirs::home_dir().ok_or("could not determine home directory")?;
-    Ok(home.join(".config/veiled"))
+pub fn log_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    config::config_dir()
 }
 
 fn escape_xml(s: &str) -> String {
@@ -37,9 +46,131 @@ fn escape_xml(s: &str) -> String {
         .replace('\'', "&apos;")
 }
 
-pub fn generate_plist(binary_path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+fn unescape_xml(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Extracts `ProgramArguments[0]`, the binary launchd runs, from a plist
+/// written by `generate_plist`.
+fn parse_binary_path(plist: &str) -> Option<PathBuf> {
+    let after_key = &plist[plist.find("<key>ProgramArguments</key>")?..];
+    let array = &after_key[after_key.find("<array>")?..];
+    let start = array.find("<string>")? + "<string>".len();
+    let len = array[start..].find("</string>")?;
+    Some(PathBuf::from(unescape_xml(
+        array[start..start + len].trim(),
+    )))
+}
+
+/// The binary the installed launch agent points at, if one is installed.
+pub fn installed_binary_path() -> Option<PathBuf> {
+    let plist = fs::read_to_string(plist_path().ok()?).ok()?;
+    parse_binary_path(&plist)
+}
+
+/// Returns the installed agent's binary when it is not the running
+/// executable, e.g. after veiled was moved to another directory.
+pub fn stale_binary_path() -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+    let Some(installed) = installed_binary_path() else {
+        return Ok(None);
+    };
+    let current =
+        std::env::current_exe().map_err(|e| format!("failed to resolve binary path: {e}"))?;
+
+    let resolve = |p: &Path| fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+    if resolve(&installed) == resolve(&current) {
+        Ok(None)
+    } else {
+        Ok(Some(installed))
+    }
+}
+
+fn validate_schedule(hour: u8, minute: u8) -> Result<(), Box<dyn std::error::Error>> {
+    if hour > 23 {
+        return Err(Error::Config(format!("invalid schedule_hour {hour}: expected 0-23")).into());
+    }
+    if minute > 59 {
+        return Err(
+            Error::Config(format!("invalid schedule_minute {minute}: expected 0-59")).into(),
+        );
+    }
+    Ok(())
+}
+
+/// Checks the schedule settings the plist would be generated from.
+pub fn check_schedule(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    schedule_keys(config).map(drop)
+}
+
+/// Builds the scheduling keys for the plist. `StartInterval` and
+/// `StartCalendarInterval` are mutually exclusive: a configured
+/// `scan_interval_hours` replaces the daily calendar time entirely.
+fn schedule_keys(config: &Config) -> Result<String, Box<dyn std::error::Error>> {
+    if let Some(hours) = config.scan_interval_hours {
+        if hours == 0 {
+            return Err(
+                Error::Config("invalid scan_interval_hours 0: expected at least 1".into()).into(),
+            );
+        }
+        let seconds = u64::from(hours) * 3600;
+        return Ok(format!(
+            "    <key>StartInterval</key>\n    <integer>{seconds}</integer>"
+        ));
+    }
+
+    let hour = config.schedule_hour;
+    let minute = config.schedule_minute;
+    validate_schedule(hour, minute)?;
+
+    Ok(format!(
+        r"    <key>StartCalendarInterval</key>
+    <dict>
+        <key>Hour</key>
+        <integer>{hour}</integer>
+        <key>Minute</key>
+        <integer>{minute}</integer>
+    </dict>"
+    ))
+}
+
+/// Extra `ProgramArguments` entries that keep the daemon on the profile the
+/// installing command used: `--config-dir` and `--config` for whichever of
+/// the config dir and config file were overridden.
+fn profile_arguments(config_dir: Option<&Path>, config_file: Option<&Path>) -> String {
+    [("--config-dir", config_dir), ("--config", config_file)]
+        .into_iter()
+        .filter_map(|(flag, path)| {
+            path.map(|path| {
+                format!(
+                    "\n        <string>{flag}</string>\n        <string>{}</string>",
+                    escape_xml(&path.display().to_string())
+                )
+            })
+        })
+        .collect()
+}
+
+pub fn generate_plist(
+    binary_path: &Path,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let schedule = schedule_keys(config)?;
     let binary = escape_xml(&binary_path.display().to_string());
+    let profile = profile_arguments(
+        config::config_dir_override().as_deref(),
+        config::config_file_override().as_deref(),
+    );
     let log = escape_xml(&log_dir()?.display().to_string());
+    let xdg = config::xdg_config_home().map_or_else(String::new, |dir| {
+        format!(
+            "\n        <key>XDG_CONFIG_HOME</key>\n        <string>{}</string>",
+            escape_xml(&dir.display().to_string())
+        )
+    });
 
     Ok(format!(
         r#"<?xml version="1.0" encoding="UTF-8"?>
@@ -50,22 +181,21 @@ pub fn generate_plist(binary_path: &Path) -> Result<String, Box<dyn std::error::
     <string>{LABEL}</string>
     <key>ProgramArguments</key>
     <array>
-        <string>{binary}</string>
+        <string>{binary}</string>{profile}
         <string>run</string>
     </array>
-    <key>StartCalendarInterval</key>
+    <key>EnvironmentVariables</key>
     <dict>
-        <key>Hour</key>
-        <integer>3</integer>
-        <key>Minute</key>
-        <integer>0</integer>
+        <key>{DAEMON_ENV}</key>
+        <string>1</string>{xdg}
     </dict>
+{schedule}
     <key>RunAtLoad</key>
     <false/>
     <key>StandardOutPath</key>
-    <string>{log}/stdout.log</string>
+    <string>{log}/{STDOUT_LOG}</string>
     <key>StandardErrorPath</key>
-    <string>{log}/stderr.log</string>
+    <string>{log}/{STDERR_LOG}</string>
 </dict>
 </plist>
 "#
@@ -92,12 +222,12 @@ pub fn install(plist_content: &str) -> Result<(), Box<dyn std::error::Error>> {
         .args(["bootstrap", &domain_target()])
         .arg(&path)
         .output()
-        .map_err(|e| format!("failed to run launchctl: {e}"))?;
+        .map_err(|e| Error::Daemon(format!("failed to run launchctl: {e}")))?;
 
     if !output.status.success() {
         fs::remove_file(&path).ok();
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("launchctl bootstrap failed: {stderr}").into());
+        return Err(Error::Daemon(format!("launchctl bootstrap failed: {stderr}")).into());
     }
 
     Ok(())
@@ -107,11 +237,11 @@ pub fn kickstart() -> Result<(), Box<dyn std::error::Error>> {
     let output = Command::new("launchctl")
         .args(["kickstart", &service_target()])
         .output()
-        .map_err(|e| format!("failed to run launchctl: {e}"))?;
+        .map_err(|e| Error::Daemon(format!("failed to run launchctl: {e}")))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("launchctl kickstart failed: {stderr}").into());
+        return Err(Error::Daemon(format!("launchctl kickstart failed: {stderr}")).into());
     }
 
     Ok(())
@@ -160,7 +290,7 @@ pub fn restart() -> Result<bool, Box<dyn std::error::Error>> {
     let binary_path =
         std::env::current_exe().map_err(|e| format!("failed to resolve binary path: {e}"))?;
 
-    let plist = generate_plist(&binary_path)?;
+    let plist = generate_plist(&binary_path, &config::load()?)?;
     install(&plist)?;
 
     Ok(true)
@@ -173,7 +303,10 @@ pub fn uninstall() -> Result<(), Box<dyn std::error::Error>> {
         let _ = kill_service();
 
         if let Err(retry) = bootout() {
-            return Err(format!("failed to stop service: {reason} (retry: {retry})").into());
+            return Err(Error::Daemon(format!(
+                "failed to stop service: {reason} (retry: {retry})"
+            ))
+            .into());
         }
     }
 
@@ -205,25 +338,39 @@ mod tests {
 
     #[test]
     fn generate_plist_contains_label() {
-        let plist = generate_plist(Path::new("/usr/local/bin/veiled")).unwrap();
+        let plist = generate_plist(Path::new("/usr/local/bin/veiled"), &Config::default()).unwrap();
         assert!(plist.contains(&format!("<string>{LABEL}</string>")));
     }
 
     #[test]
     fn generate_plist_contains_binary_path() {
-        let plist = generate_plist(Path::new("/opt/homebrew/bin/veiled")).unwrap();
+        let plist =
+            generate_plist(Path::new("/opt/homebrew/bin/veiled"), &Config::default()).unwrap();
         assert!(plist.contains("<string>/opt/homebrew/bin/veiled</string>"));
     }
 
+    #[test]
+    fn parse_binary_path_roundtrips_generated_plist() {
+        let binary = Path::new("/Users/dev/bin/a&b <veiled>");
+        let plist = generate_plist(binary, &Config::default()).unwrap();
+
+        assert_eq!(parse_binary_path(&plist), Some(binary.to_path_buf()));
+    }
+
+    #[test]
+    fn parse_binary_path_rejects_plist_without_arguments() {
+        assert_eq!(parse_binary_path("<plist><dict></dict></plist>"), None);
+    }
+
     #[test]
     fn generate_plist_contains_run_argument() {
-        let plist = generate_plist(Path::new("/usr/local/bin/veiled")).unwrap();
+        let plist = generate_plist(Path::new("/usr/local/bin/veiled"), &Config::default()).unwrap();
         assert!(plist.contains("<string>run</string>"));
     }
 
     #[test]
     fn generate_plist_has_calendar_interval() {
-        let plist = generate_plist(Path::new("/usr/local/bin/veiled")).unwrap();
+        let plist = generate_plist(Path::new("/usr/local/bin/veiled"), &Config::default()).unwrap();
         assert!(plist.contains("<key>StartCalendarInterval</key>"));
         assert!(plist.contains("<key>Hour</key>"));
         assert!(plist.contains("<integer>3</integer>"));
@@ -231,15 +378,118 @@ mod tests {
         assert!(plist.contains("<integer>0</integer>"));
     }
 
+    #[test]
+    fn generate_plist_uses_configured_schedule() {
+        let config = Config {
+            schedule_hour: 13,
+            schedule_minute: 45,
+            ..Config::default()
+        };
+        let plist = generate_plist(Path::new("/usr/local/bin/veiled"), &config).unwrap();
+        assert!(plist.contains("<key>Hour</key>\n        <integer>13</integer>"));
+        assert!(plist.contains("<key>Minute</key>\n        <integer>45</integer>"));
+    }
+
+    #[test]
+    fn generate_plist_rejects_invalid_hour() {
+        let config = Config {
+            schedule_hour: 24,
+            ..Config::default()
+        };
+        assert!(generate_plist(Path::new("/usr/local/bin/veiled"), &config).is_err());
+    }
+
+    #[test]
+    fn generate_plist_rejects_invalid_minute() {
+        let config = Config {
+            schedule_minute: 60,
+            ..Config::default()
+        };
+        assert!(generate_plist(Path::new("/usr/local/bin/veiled"), &config).is_err());
+    }
+
+    #[test]
+    fn generate_plist_uses_start_interval_when_configured() {
+        let config = Config {
+            scan_interval_hours: Some(6),
+            ..Config::default()
+        };
+        let plist = generate_plist(Path::new("/usr/local/bin/veiled"), &config).unwrap();
+        assert!(plist.contains("<key>StartInterval</key>\n    <integer>21600</integer>"));
+        assert!(!plist.contains("<key>StartCalendarInterval</key>"));
+    }
+
+    #[test]
+    fn generate_plist_uses_calendar_interval_without_interval() {
+        let plist = generate_plist(Path::new("/usr/local/bin/veiled"), &Config::default()).unwrap();
+        assert!(plist.contains("<key>StartCalendarInterval</key>"));
+        assert!(!plist.contains("<key>StartInterval</key>"));
+    }
+
+    #[test]
+    fn generate_plist_rejects_zero_interval() {
+        let config = Config {
+            scan_interval_hours: Some(0),
+            ..Config::default()
+        };
+        assert!(generate_plist(Path::new("/usr/local/bin/veiled"), &config).is_err());
+    }
+
+    #[test]
+    fn generate_plist_sets_daemon_env() {
+        let plist = generate_plist(Path::new("/usr/local/bin/veiled"), &Config::default()).unwrap();
+        assert!(plist.contains("<key>EnvironmentVariables</key>"));
+        assert!(plist.contains(&format!("<key>{DAEMON_ENV}</key>")));
+    }
+
+    #[test]
+    fn profile_arguments_point_the_daemon_at_the_config_dir() {
+        assert_eq!(profile_arguments(None, None), "");
+
+        let args = profile_arguments(Some(Path::new("/Users/dev/profiles/work")), None);
+
+        assert_eq!(
+            args,
+            "\n        <string>--config-dir</string>\n        <string>/Users/dev/profiles/work</string>"
+        );
+    }
+
+    #[test]
+    fn profile_arguments_point_the_daemon_at_the_config_file() {
+        let args = profile_arguments(
+            Some(Path::new("/Users/dev/profiles/work")),
+            Some(Path::new("/Users/dev/veiled & co.toml")),
+        );
+
+        assert_eq!(
+            args,
+            "\n        <string>--config-dir</string>\n        <string>/Users/dev/profiles/work</string>\
+             \n        <string>--config</string>\n        <string>/Users/dev/veiled &amp; co.toml</string>"
+        );
+    }
+
+    #[test]
+    fn generate_plist_passes_profile_arguments_before_run() {
+        let plist = generate_plist(Path::new("/usr/local/bin/veiled"), &Config::default()).unwrap();
+        let profile = profile_arguments(
+            config::config_dir_override().as_deref(),
+            config::config_file_override().as_deref(),
+        );
+
+        assert!(plist.contains(&format!(
+            "<string>/usr/local/bin/veiled</string>{profile}\n        <string>run</string>"
+        )));
+    }
+
     #[test]
     fn generate_plist_run_at_load_is_false() {
-        let plist = generate_plist(Path::new("/usr/local/bin/veiled")).unwrap();
+        let plist = generate_plist(Path::new("/usr/local/bin/veiled"), &Config::default()).unwrap();
         assert!(plist.contains("<false/>"));
     }
 
     #[test]
     fn generate_plist_has_log_paths() {
-        let plist = generate_plist(Path::new("/usr/local/bin/veiled")).unwrap();
+        let plist = generate_plist(Path::new("/usr/local/bin/veiled"), &Config::default()).unwrap();
         assert!(plist.contains("<key>StandardOutPath</key>"));
         assert!(plist.contains("stdout.log"));
         assert!(plist.contains("<key>StandardErrorPath</key>"));
@@ -312,7 +562,7 @@ mod tests {
 
     #[test]
     fn generate_plist_escapes_special_chars_in_path() {
-        let plist = generate_plist(Path::new("/opt/my&app/veiled")).unwrap();
+        let plist = generate_plist(Path::new("/opt/my&app/veiled"), &Config::default()).unwrap();
         assert!(plist.contains("<string>/opt/my&amp;app/veiled</string>"));
         assert!(!plist.contains("<string>/opt/my&app/veiled</string>"));
     }
@@ -1,8 +1,43 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::thread;
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+/// How file sizes are measured: `Logical` sums file lengths, `Allocated`
+/// sums the blocks actually reserved on disk (closer to what a backup costs).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeMode {
+    #[default]
+    Logical,
+    Allocated,
+}
+
+#[cfg(unix)]
+fn file_size(metadata: &fs::Metadata, mode: SizeMode) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+
+    match mode {
+        SizeMode::Logical => metadata.len(),
+        SizeMode::Allocated => metadata.blocks().saturating_mul(512),
+    }
+}
 
-pub fn dir_size(path: &Path) -> u64 {
+#[cfg(not(unix))]
+fn file_size(metadata: &fs::Metadata, _mode: SizeMode) -> u64 {
+    metadata.len()
+}
+
+/// Sums the files under `path`, or the file's own size when `path` is a
+/// file (candidates from `exclude_file_globs`).
+pub fn dir_size(path: &Path, mode: SizeMode) -> u64 {
+    if let Ok(metadata) = fs::symlink_metadata(path)
+        && metadata.is_file()
+    {
+        return file_size(&metadata, mode);
+    }
     let mut total = 0u64;
     let mut stack: Vec<PathBuf> = vec![path.to_path_buf()];
 
@@ -26,7 +61,7 @@ pub fn dir_size(path: &Path) -> u64 {
                 let Ok(metadata) = entry.metadata() else {
                     continue;
                 };
-                total = total.saturating_add(metadata.len());
+                total = total.saturating_add(file_size(&metadata, mode));
             }
         }
     }
@@ -34,35 +69,99 @@ pub fn dir_size(path: &Path) -> u64 {
     total
 }
 
-pub fn calculate_total_size(paths: &[String]) -> u64 {
-    let handles: Vec<_> = paths
-        .iter()
-        .map(|p| {
-            let p = PathBuf::from(p);
-            thread::spawn(move || dir_size(&p))
-        })
-        .collect();
-
-    handles
-        .into_iter()
-        .filter_map(|h| h.join().ok())
-        .fold(0u64, u64::saturating_add)
+/// The directory's own mtime in Unix seconds, which `cached_dir_size`
+/// compares against the value stored with a cached size.
+pub fn dir_mtime(path: &Path) -> Option<i64> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let secs = modified.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    i64::try_from(secs).ok()
+}
+
+/// Returns `cached_size` while the directory's mtime still equals
+/// `cached_mtime`, and re-measures otherwise. The flag is true when the size
+/// had to be measured.
+///
+/// Only the top-level mtime is checked. It changes when entries are added,
+/// removed or renamed directly inside `path`, but not when files deeper down
+/// grow, so a cache like `node_modules` whose top level rarely changes can
+/// keep reporting an outdated size.
+pub fn cached_dir_size(
+    path: &Path,
+    cached_size: Option<u64>,
+    cached_mtime: Option<i64>,
+    mode: SizeMode,
+) -> (u64, bool) {
+    match (cached_size, cached_mtime) {
+        (Some(size), Some(mtime)) if dir_mtime(path) == Some(mtime) => (size, false),
+        _ => (dir_size(path, mode), true),
+    }
+}
+
+const WORKERS: usize = 8;
+
+/// Applies `measure` to each item on a bounded pool of worker threads,
+/// returning the results in the same order as `items`.
+pub fn measure_parallel<T, R>(items: &[T], measure: impl Fn(&T) -> R + Sync) -> Vec<R>
+where
+    T: Sync,
+    R: Send + Clone + Default,
+{
+    if items.is_empty() {
+        return vec![];
+    }
+
+    let chunk_size = items.len().div_ceil(WORKERS);
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let measure = &measure;
+                let handle = scope.spawn(move || chunk.iter().map(measure).collect::<Vec<_>>());
+                (chunk.len(), handle)
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|(len, h)| h.join().unwrap_or_else(|_| vec![R::default(); len]))
+            .collect()
+    })
+}
+
+/// Sizes each path in parallel, returning the results in the same order as
+/// `paths`.
+pub fn dir_sizes(paths: &[String], mode: SizeMode) -> Vec<u64> {
+    measure_parallel(paths, |p| dir_size(Path::new(p), mode))
+}
+
+/// How sizes are printed: `Binary` steps by 1024 and labels KiB/MiB/GiB,
+/// `Decimal` steps by 1000 and labels KB/MB/GB, as Finder does.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeUnits {
+    #[default]
+    Binary,
+    Decimal,
 }
 
-pub fn format_size(bytes: u64) -> String {
-    const GB: f64 = 1_073_741_824.0;
-    const MB: f64 = 1_048_576.0;
-    const KB: f64 = 1_024.0;
+/// Formats `bytes` with one decimal in the largest unit it reaches, or as
+/// whole bytes below the first step. Callers pass the config's
+/// `size_units`.
+pub fn format_size(bytes: u64, units: SizeUnits) -> String {
+    let (step, labels) = match units {
+        SizeUnits::Binary => (1_024.0, ["KiB", "MiB", "GiB"]),
+        SizeUnits::Decimal => (1_000.0, ["KB", "MB", "GB"]),
+    };
 
     #[allow(clippy::cast_precision_loss)]
     let value = bytes as f64;
 
-    if value >= GB {
-        format!("{:.1} GB", value / GB)
-    } else if value >= MB {
-        format!("{:.1} MB", value / MB)
-    } else if value >= KB {
-        format!("{:.1} KB", value / KB)
+    if value >= step * step * step {
+        format!("{:.1} {}", value / (step * step * step), labels[2])
+    } else if value >= step * step {
+        format!("{:.1} {}", value / (step * step), labels[1])
+    } else if value >= step {
+        format!("{:.1} {}", value / step, labels[0])
     } else {
         format!("{bytes} B")
     }
@@ -78,7 +177,7 @@ mod tests {
     #[test]
     fn empty_dir_returns_zero() {
         let dir = TempDir::new().unwrap();
-        assert_eq!(dir_size(dir.path()), 0);
+        assert_eq!(dir_size(dir.path(), SizeMode::Logical), 0);
     }
 
     #[test]
@@ -88,7 +187,16 @@ mod tests {
         let mut file = File::create(&file_path).unwrap();
         file.write_all(b"hello").unwrap();
 
-        assert_eq!(dir_size(dir.path()), 5);
+        assert_eq!(dir_size(dir.path(), SizeMode::Logical), 5);
+    }
+
+    #[test]
+    fn file_path_returns_its_own_size() {
+        let dir = TempDir::new().unwrap();
+        let file_path = dir.path().join("disk.iso");
+        fs::write(&file_path, vec![0u8; 4096]).unwrap();
+
+        assert_eq!(dir_size(&file_path, SizeMode::Logical), 4096);
     }
 
     #[test]
@@ -104,16 +212,19 @@ mod tests {
         let mut f2 = File::create(sub.join("b.txt")).unwrap();
         f2.write_all(b"bbbbb").unwrap();
 
-        assert_eq!(dir_size(dir.path()), 8);
+        assert_eq!(dir_size(dir.path(), SizeMode::Logical), 8);
     }
 
     #[test]
     fn nonexistent_path_returns_zero() {
-        assert_eq!(dir_size(Path::new("/nonexistent/path")), 0);
+        assert_eq!(
+            dir_size(Path::new("/nonexistent/path"), SizeMode::Logical),
+            0
+        );
     }
 
     #[test]
-    fn calculate_total_size_sums_multiple_dirs() {
+    fn dir_sizes_measures_each_dir() {
         let d1 = TempDir::new().unwrap();
         let d2 = TempDir::new().unwrap();
 
@@ -128,16 +239,88 @@ mod tests {
             d2.path().to_string_lossy().into_owned(),
         ];
 
-        assert_eq!(calculate_total_size(&paths), 5);
+        assert_eq!(dir_sizes(&paths, SizeMode::Logical), vec![3, 2]);
     }
 
     #[test]
-    fn calculate_total_size_skips_nonexistent() {
+    fn dir_sizes_preserves_input_order() {
+        let d1 = TempDir::new().unwrap();
+        let d2 = TempDir::new().unwrap();
+
+        let mut f = File::create(d2.path().join("b.txt")).unwrap();
+        f.write_all(b"bb").unwrap();
+
+        let paths = vec![
+            d1.path().to_string_lossy().into_owned(),
+            d2.path().to_string_lossy().into_owned(),
+            "/nonexistent/three".to_string(),
+        ];
+
+        assert_eq!(dir_sizes(&paths, SizeMode::Logical), vec![0, 2, 0]);
+    }
+
+    #[test]
+    fn dir_sizes_matches_serial_measurement() {
+        let dirs: Vec<TempDir> = (0..20).map(|_| TempDir::new().unwrap()).collect();
+        for (i, d) in dirs.iter().enumerate() {
+            let sub = d.path().join("sub");
+            fs::create_dir(&sub).unwrap();
+            fs::write(d.path().join("a.bin"), vec![0u8; i * 100]).unwrap();
+            fs::write(sub.join("b.bin"), vec![0u8; i * 7 + 1]).unwrap();
+        }
+
+        let paths: Vec<String> = dirs
+            .iter()
+            .map(|d| d.path().to_string_lossy().into_owned())
+            .collect();
+        let serial: Vec<u64> = paths
+            .iter()
+            .map(|p| dir_size(Path::new(p), SizeMode::Logical))
+            .collect();
+
+        assert_eq!(dir_sizes(&paths, SizeMode::Logical), serial);
+    }
+
+    #[test]
+    fn dir_sizes_reports_zero_for_nonexistent() {
         let paths = vec![
             "/nonexistent/one".to_string(),
             "/nonexistent/two".to_string(),
         ];
-        assert_eq!(calculate_total_size(&paths), 0);
+        assert_eq!(dir_sizes(&paths, SizeMode::Logical), vec![0, 0]);
+    }
+
+    #[test]
+    fn cached_dir_size_returns_cache_for_unchanged_dir() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.bin"), vec![0u8; 100]).unwrap();
+        let mtime = dir_mtime(dir.path());
+
+        assert_eq!(
+            cached_dir_size(dir.path(), Some(7), mtime, SizeMode::Logical),
+            (7, false)
+        );
+    }
+
+    #[test]
+    fn cached_dir_size_recomputes_touched_dir() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.bin"), vec![0u8; 100]).unwrap();
+        let mtime = dir_mtime(dir.path());
+
+        File::open(dir.path())
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() - std::time::Duration::from_hours(1))
+            .unwrap();
+
+        assert_eq!(
+            cached_dir_size(dir.path(), Some(7), mtime, SizeMode::Logical),
+            (100, true)
+        );
+        assert_eq!(
+            cached_dir_size(dir.path(), Some(7), None, SizeMode::Logical),
+            (100, true)
+        );
     }
 
     #[cfg(unix)]
@@ -150,32 +333,81 @@ mod tests {
         f.write_all(b"hello").unwrap();
 
         std::os::unix::fs::symlink(dir.path(), dir.path().join("loop")).unwrap();
-        assert_eq!(dir_size(dir.path()), 5);
+        assert_eq!(dir_size(dir.path(), SizeMode::Logical), 5);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn allocated_size_counts_blocks_for_regular_file() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("data.bin"), vec![1u8; 10_000]).unwrap();
+
+        let allocated = dir_size(dir.path(), SizeMode::Allocated);
+
+        assert!(allocated > 0);
+        assert_eq!(allocated % 512, 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn allocated_size_is_smaller_for_sparse_file() {
+        let dir = TempDir::new().unwrap();
+        let file = File::create(dir.path().join("sparse.bin")).unwrap();
+        file.set_len(64 * 1024 * 1024).unwrap();
+
+        let logical = dir_size(dir.path(), SizeMode::Logical);
+        let allocated = dir_size(dir.path(), SizeMode::Allocated);
+
+        assert_eq!(logical, 64 * 1024 * 1024);
+        assert!(allocated < logical);
+    }
+
+    fn binary(bytes: u64) -> String {
+        format_size(bytes, SizeUnits::Binary)
+    }
+
+    fn decimal(bytes: u64) -> String {
+        format_size(bytes, SizeUnits::Decimal)
     }
 
     #[test]
     fn format_size_bytes() {
-        assert_eq!(format_size(0), "0 B");
-        assert_eq!(format_size(512), "512 B");
-        assert_eq!(format_size(1_023), "1023 B");
+        assert_eq!(binary(0), "0 B");
+        assert_eq!(binary(512), "512 B");
+        assert_eq!(binary(1_023), "1023 B");
     }
 
     #[test]
     fn format_size_kilobytes() {
-        assert_eq!(format_size(1_024), "1.0 KB");
-        assert_eq!(format_size(524_288), "512.0 KB");
-        assert_eq!(format_size(1_048_575), "1024.0 KB");
+        assert_eq!(binary(1_024), "1.0 KiB");
+        assert_eq!(binary(524_288), "512.0 KiB");
+        assert_eq!(binary(1_048_575), "1024.0 KiB");
     }
 
     #[test]
     fn format_size_megabytes() {
-        assert_eq!(format_size(1_048_576), "1.0 MB");
-        assert_eq!(format_size(268_959_334), "256.5 MB");
+        assert_eq!(binary(1_048_576), "1.0 MiB");
+        assert_eq!(binary(268_959_334), "256.5 MiB");
     }
 
     #[test]
     fn format_size_gigabytes() {
-        assert_eq!(format_size(1_073_741_824), "1.0 GB");
-        assert_eq!(format_size(13_207_024_435), "12.3 GB");
+        assert_eq!(binary(1_073_741_824), "1.0 GiB");
+        assert_eq!(binary(13_207_024_435), "12.3 GiB");
+    }
+
+    #[test]
+    fn format_size_decimal_steps_by_thousand() {
+        assert_eq!(decimal(1_000), "1.0 KB");
+        assert_eq!(decimal(1_024), "1.0 KB");
+        assert_eq!(decimal(1_500_000), "1.5 MB");
+        assert_eq!(decimal(13_207_024_435), "13.2 GB");
+    }
+
+    #[test]
+    fn format_size_decimal_prints_bytes_below_a_kilobyte() {
+        assert_eq!(decimal(0), "0 B");
+        assert_eq!(decimal(512), "512 B");
+        assert_eq!(decimal(999), "999 B");
     }
 }
@@ -0,0 +1,152 @@
+use std::fmt;
+use std::io;
+
+/// Exit codes returned by `veiled`, one per failure category so scripts can
+/// tell failures apart:
+///
+/// | code | meaning                                              |
+/// |------|------------------------------------------------------|
+/// | 0    | success                                              |
+/// | 1    | any other error (unmanaged path, invalid directory, ...) |
+/// | 2    | invalid command-line arguments (reported by clap)     |
+/// | 3    | config or registry could not be read, written or validated |
+/// | 4    | setting or clearing a Time Machine exclusion failed   |
+/// | 5    | network failure while checking for or downloading updates |
+/// | 6    | launchd refused to load, start or stop the daemon     |
+/// | 7    | permission denied; Full Disk Access is likely missing |
+/// | 10   | `update --check` found a newer version                |
+///
+/// Code 2 is left to clap, which rejects bad arguments before any command
+/// runs, so no category below reuses it.
+pub mod exit_code {
+    pub const SUCCESS: i32 = 0;
+    pub const GENERAL: i32 = 1;
+    pub const CONFIG: i32 = 3;
+    pub const TMUTIL: i32 = 4;
+    pub const NETWORK: i32 = 5;
+    pub const DAEMON: i32 = 6;
+    pub const ACCESS: i32 = 7;
+    pub const UPDATE_AVAILABLE: i32 = 10;
+}
+
+/// Error returned by every command, tagged with the category that decides
+/// the process exit code.
+#[derive(Debug)]
+pub enum Error {
+    Access(String),
+    Config(String),
+    Tmutil(String),
+    Network(String),
+    Daemon(String),
+    Other(String),
+}
+
+impl Error {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Access(_) => exit_code::ACCESS,
+            Self::Config(_) => exit_code::CONFIG,
+            Self::Tmutil(_) => exit_code::TMUTIL,
+            Self::Network(_) => exit_code::NETWORK,
+            Self::Daemon(_) => exit_code::DAEMON,
+            Self::Other(_) => exit_code::GENERAL,
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            Self::Access(m)
+            | Self::Config(m)
+            | Self::Tmutil(m)
+            | Self::Network(m)
+            | Self::Daemon(m)
+            | Self::Other(m) => m,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Recovers the category of an `Error` that was boxed on its way up through
+/// helpers returning `Box<dyn Error>`; anything else becomes `Other`.
+impl From<Box<dyn std::error::Error>> for Error {
+    fn from(err: Box<dyn std::error::Error>) -> Self {
+        match err.downcast::<Self>() {
+            Ok(err) => *err,
+            Err(err) => Self::Other(err.to_string()),
+        }
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Self::Other(message.to_string())
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Other(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Other(err.to_string())
+    }
+}
+
+impl From<toml::ser::Error> for Error {
+    fn from(err: toml::ser::Error) -> Self {
+        Self::Other(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn categories_map_to_distinct_exit_codes() {
+        let codes = [
+            Error::Other(String::new()).exit_code(),
+            Error::Access(String::new()).exit_code(),
+            Error::Config(String::new()).exit_code(),
+            Error::Tmutil(String::new()).exit_code(),
+            Error::Network(String::new()).exit_code(),
+            Error::Daemon(String::new()).exit_code(),
+        ];
+
+        assert_eq!(codes, [1, 7, 3, 4, 5, 6]);
+        // clap exits with 2 on invalid arguments.
+        assert!(!codes.contains(&2));
+    }
+
+    #[test]
+    fn category_survives_boxing() {
+        let boxed: Box<dyn std::error::Error> = Error::Network("offline".into()).into();
+        let err = Error::from(boxed);
+
+        assert_eq!(err.exit_code(), exit_code::NETWORK);
+        assert_eq!(err.to_string(), "offline");
+    }
+
+    #[test]
+    fn plain_boxed_errors_are_general() {
+        let boxed: Box<dyn std::error::Error> = "something broke".into();
+
+        assert_eq!(Error::from(boxed).exit_code(), exit_code::GENERAL);
+    }
+}
@@ -0,0 +1,114 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::error::Error;
+
+/// A stop request raised by a signal and honoured at the next safe point.
+#[derive(Debug)]
+pub struct StopFlag(AtomicBool);
+
+impl StopFlag {
+    pub const fn new() -> Self {
+        Self(AtomicBool::new(false))
+    }
+
+    /// Marks the flag, returning whether it was already set.
+    pub(crate) fn request(&self) -> bool {
+        self.0.swap(true, Ordering::SeqCst)
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Fails once a stop was requested, so callers can bail out with `?`
+    /// before starting work that would leave the registry half-updated.
+    pub fn check(&self) -> Result<(), Error> {
+        if self.is_set() {
+            return Err(Error::Other("interrupted".to_string()));
+        }
+        Ok(())
+    }
+}
+
+static STOP: StopFlag = StopFlag::new();
+
+/// Set while the process waits on the terminal. std retries reads that a
+/// signal interrupts, so a prompt would otherwise never see the flag.
+static PROMPTING: AtomicBool = AtomicBool::new(false);
+
+/// The process-wide flag set by SIGINT and SIGTERM once `install` ran.
+pub fn stop() -> &'static StopFlag {
+    &STOP
+}
+
+/// Runs `read` with signals exiting right away instead of only setting the
+/// flag. Nothing is mid-write while waiting on an answer, and the registry
+/// is only saved after the prompt returns.
+pub fn while_prompting<T>(read: impl FnOnce() -> T) -> T {
+    PROMPTING.store(true, Ordering::SeqCst);
+    let result = read();
+    PROMPTING.store(false, Ordering::SeqCst);
+    result
+}
+
+/// A first SIGINT or SIGTERM only sets the flag; a second one, or any
+/// during a prompt, exits right away with the conventional 128 + signal
+/// status.
+extern "C" fn on_signal(signal: libc::c_int) {
+    let prompting = PROMPTING.load(Ordering::SeqCst);
+    if STOP.request() || prompting {
+        // SAFETY: `write` and `_exit` are async-signal-safe; the newline
+        // ends the line the prompt left open.
+        unsafe {
+            if prompting {
+                libc::write(libc::STDOUT_FILENO, c"\n".as_ptr().cast(), 1);
+            }
+            libc::_exit(128 + signal);
+        }
+    }
+}
+
+/// Routes SIGINT and SIGTERM to the stop flag instead of killing the
+/// process mid-write.
+pub fn install() {
+    let handler: extern "C" fn(libc::c_int) = on_signal;
+    for signal in [libc::SIGINT, libc::SIGTERM] {
+        // SAFETY: the handler only touches an atomic and calls `_exit`, both
+        // async-signal-safe; the zeroed sigaction is then filled in fully.
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handler as libc::sighandler_t;
+            libc::sigemptyset(&raw mut action.sa_mask);
+            libc::sigaction(signal, &raw const action, std::ptr::null_mut());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_passes_until_a_stop_is_requested() {
+        let flag = StopFlag::new();
+        assert!(flag.check().is_ok());
+
+        assert!(!flag.request());
+
+        assert!(flag.is_set());
+        assert!(matches!(flag.check(), Err(Error::Other(msg)) if msg == "interrupted"));
+    }
+
+    #[test]
+    fn while_prompting_returns_the_result_and_clears_the_mark() {
+        assert!(while_prompting(|| PROMPTING.load(Ordering::SeqCst)));
+        assert!(!PROMPTING.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn second_request_reports_the_flag_was_already_set() {
+        let flag = StopFlag::new();
+        assert!(!flag.request());
+        assert!(flag.request());
+    }
+}
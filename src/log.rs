@@ -0,0 +1,155 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use console::style;
+
+use crate::{daemon, registry};
+
+/// Size at which the daemon's stdout/stderr logs are rotated.
+pub const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+/// Formats Unix epoch seconds as an ISO-8601 UTC timestamp
+/// (`2024-05-01T09:30:00Z`).
+fn format_timestamp(epoch_secs: i64) -> String {
+    let days = epoch_secs.div_euclid(86_400);
+    let secs = epoch_secs.rem_euclid(86_400);
+
+    // Civil-from-days conversion (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// Formats an elapsed number of seconds as a rough age such as
+/// `6 hours ago`, in the largest whole unit up to days.
+pub fn format_age(secs: i64) -> String {
+    let (count, unit) = match secs {
+        ..=0 => return "just now".to_string(),
+        1..60 => (secs, "second"),
+        60..3_600 => (secs / 60, "minute"),
+        3_600..86_400 => (secs / 3_600, "hour"),
+        _ => (secs / 86_400, "day"),
+    };
+    format!("{count} {unit}{} ago", if count == 1 { "" } else { "s" })
+}
+
+pub fn timestamp() -> String {
+    format_timestamp(registry::now_epoch())
+}
+
+/// Prints a warning to stderr, prefixed with a timestamp when running under
+/// the daemon so `stderr.log` entries can be told apart.
+pub fn warn(message: &str) {
+    if daemon::is_daemon_context() {
+        eprintln!("{} warning: {message}", timestamp());
+    } else {
+        eprintln!("{} {message}", style("warning:").yellow().bold());
+    }
+}
+
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".1");
+    path.with_file_name(name)
+}
+
+/// Renames `path` to `path.1` (replacing any previous `.1`) once it reaches
+/// `max_bytes`. Returns whether a rotation happened; a missing file is not
+/// an error.
+pub fn rotate(path: &Path, max_bytes: u64) -> io::Result<bool> {
+    let len = match fs::metadata(path) {
+        Ok(metadata) => metadata.len(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+
+    if len < max_bytes {
+        return Ok(false);
+    }
+
+    fs::rename(path, rotated_path(path))?;
+    Ok(true)
+}
+
+/// Rotates the daemon's stdout/stderr logs. Run at the end of a scan: the
+/// current process keeps writing to the renamed file, and launchd opens a
+/// fresh one on the next launch.
+pub fn rotate_daemon_logs() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = daemon::log_dir()?;
+    for name in [daemon::STDOUT_LOG, daemon::STDERR_LOG] {
+        rotate(&dir.join(name), MAX_LOG_BYTES)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn formats_epoch_as_iso8601() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_timestamp(1_714_555_800), "2024-05-01T09:30:00Z");
+    }
+
+    #[test]
+    fn format_age_picks_the_largest_whole_unit() {
+        assert_eq!(format_age(0), "just now");
+        assert_eq!(format_age(-5), "just now");
+        assert_eq!(format_age(1), "1 second ago");
+        assert_eq!(format_age(59), "59 seconds ago");
+        assert_eq!(format_age(60), "1 minute ago");
+        assert_eq!(format_age(3_599), "59 minutes ago");
+        assert_eq!(format_age(3_600), "1 hour ago");
+        assert_eq!(format_age(6 * 3_600 + 1_800), "6 hours ago");
+        assert_eq!(format_age(86_400), "1 day ago");
+        assert_eq!(format_age(10 * 86_400), "10 days ago");
+    }
+
+    #[test]
+    fn rotate_leaves_small_files_alone() {
+        let dir = TempDir::new().unwrap();
+        let log = dir.path().join("stdout.log");
+        fs::write(&log, vec![b'x'; 99]).unwrap();
+
+        assert!(!rotate(&log, 100).unwrap());
+        assert!(log.exists());
+        assert!(!dir.path().join("stdout.log.1").exists());
+    }
+
+    #[test]
+    fn rotate_renames_at_threshold_and_replaces_previous() {
+        let dir = TempDir::new().unwrap();
+        let log = dir.path().join("stdout.log");
+        let old = dir.path().join("stdout.log.1");
+        fs::write(&old, "old").unwrap();
+        fs::write(&log, vec![b'x'; 100]).unwrap();
+
+        assert!(rotate(&log, 100).unwrap());
+        assert!(!log.exists());
+        assert_eq!(fs::read(&old).unwrap().len(), 100);
+    }
+
+    #[test]
+    fn rotate_ignores_missing_file() {
+        let dir = TempDir::new().unwrap();
+
+        assert!(!rotate(&dir.path().join("stderr.log"), 100).unwrap());
+    }
+}
@@ -10,30 +10,71 @@ pub fn verbose() -> bool {
     VERBOSE.get().copied().unwrap_or(false)
 }
 
+static ASSUME_YES: OnceLock<bool> = OnceLock::new();
+
+pub fn assume_yes() -> bool {
+    ASSUME_YES.get().copied().unwrap_or(false)
+}
+
+static PATH_STYLE: OnceLock<cli::PathStyle> = OnceLock::new();
+
+pub fn path_style() -> cli::PathStyle {
+    PATH_STYLE.get().copied().unwrap_or_default()
+}
+
+static QUIET: OnceLock<bool> = OnceLock::new();
+
+pub fn quiet() -> bool {
+    QUIET.get().copied().unwrap_or(false)
+}
+
 mod builtins;
 mod cli;
 mod commands;
 mod config;
 mod daemon;
 mod disksize;
+mod error;
+mod interrupt;
+mod log;
+mod notify;
+mod prompt;
 mod registry;
 mod scanner;
 mod tmutil;
 mod updater;
+mod watcher;
 
 fn main() {
     let cli = cli::Cli::parse();
 
     let _ = VERBOSE.set(cli.verbose);
+    let _ = QUIET.set(cli.quiet);
+    let _ = ASSUME_YES.set(cli.assume_yes);
+    let _ = PATH_STYLE.set(cli.path_style);
+    if let Some(dir) = &cli.config_dir {
+        config::set_config_dir(dir.clone());
+    }
+    if let Some(file) = &cli.config {
+        config::set_config_file(file.clone());
+    }
 
     if matches!(
         cli.command,
-        cli::Commands::Start
-            | cli::Commands::Run
+        cli::Commands::Start { print: false }
+            | cli::Commands::Run { .. }
             | cli::Commands::Add { .. }
             | cli::Commands::Remove { .. }
             | cli::Commands::Reset { .. }
+            | cli::Commands::Uninstall { .. }
             | cli::Commands::Status { .. }
+            | cli::Commands::Verify
+            | cli::Commands::Reapply
+            | cli::Commands::Suspend { .. }
+            | cli::Commands::Resume { .. }
+            | cli::Commands::Watch
+            | cli::Commands::Import { apply: true, .. }
+            | cli::Commands::List { verify: true, .. }
     ) && let Err(detail) = tmutil::check_access()
     {
         eprintln!(
@@ -45,20 +86,113 @@ fn main() {
         }
     }
 
-    let result = match cli.command {
-        cli::Commands::Start => commands::start::execute(),
-        cli::Commands::Stop => commands::stop::execute(),
-        cli::Commands::Run => commands::run::execute(),
-        cli::Commands::List => commands::list::execute(),
-        cli::Commands::Reset { yes } => commands::reset::execute(yes),
-        cli::Commands::Add { ref path } => commands::add::execute(path),
-        cli::Commands::Remove { ref path } => commands::remove::execute(path),
-        cli::Commands::Status { refresh } => commands::status::execute(refresh),
-        cli::Commands::Update => commands::update::execute(),
-    };
-
-    if let Err(e) = result {
-        eprintln!("{} {e}", style("error:").red().bold());
-        process::exit(1);
+    match dispatch(&cli) {
+        Ok(error::exit_code::SUCCESS) => {}
+        Ok(code) => process::exit(code),
+        Err(e) => {
+            eprintln!("{} {e}", style("error:").red().bold());
+            process::exit(e.exit_code());
+        }
     }
 }
+
+/// Runs the selected command and returns the exit code for a successful run;
+/// only `update --check` reports something other than success that way.
+fn dispatch(cli: &cli::Cli) -> Result<i32, error::Error> {
+    match cli.command {
+        cli::Commands::Start { print } => commands::start::execute(print),
+        cli::Commands::Stop => commands::stop::execute(),
+        cli::Commands::Run {
+            since,
+            format,
+            include_hidden,
+            force_verify,
+            force,
+            skip_if_recent,
+            ref only,
+            ref include_path,
+            stats,
+            interactive,
+            summary_only,
+        } => {
+            let format = if cli.json {
+                cli::OutputFormat::Json
+            } else {
+                format.unwrap_or_default()
+            };
+            let auto_update = if cli.auto_update {
+                Some(true)
+            } else if cli.no_auto_update {
+                Some(false)
+            } else {
+                None
+            };
+            commands::run::execute(&commands::run::Options {
+                since,
+                format,
+                include_hidden,
+                auto_update,
+                force_verify,
+                force,
+                skip_if_recent,
+                only: only.clone(),
+                include_paths: include_path.clone(),
+                stats,
+                interactive,
+                summary_only,
+            })
+        }
+        cli::Commands::Scan { ref include_path } => commands::scan::execute(include_path, cli.json),
+        cli::Commands::List { sort, verify } => {
+            commands::list::execute(sort.unwrap_or_default(), verify, cli.json)
+        }
+        cli::Commands::Reset { yes, dry_run } => commands::reset::execute(yes, dry_run),
+        cli::Commands::Uninstall { yes } => commands::uninstall::execute(yes),
+        cli::Commands::Add { ref paths, force } => commands::add::execute(paths, force),
+        cli::Commands::Remove {
+            ref paths,
+            all_extra,
+            keep_registry,
+            ref name,
+            yes,
+        } => match name {
+            Some(name) => commands::remove::execute_by_name(name, yes),
+            None => commands::remove::execute(paths, all_extra, keep_registry),
+        },
+        cli::Commands::Prune => commands::prune::execute(),
+        cli::Commands::Status {
+            refresh,
+            stale_only,
+            top,
+            by_type,
+            verify,
+        } => commands::status::execute(
+            &commands::status::Options {
+                refresh,
+                stale_only,
+                top,
+                by_type,
+                verify,
+            },
+            cli.json,
+        ),
+        cli::Commands::Update { check } => {
+            return commands::update::execute(check).map(|outcome| outcome.exit_code());
+        }
+        cli::Commands::Verify => commands::verify::execute(),
+        cli::Commands::Reapply => commands::reapply::execute(),
+        cli::Commands::Suspend { ref path } => commands::suspend::execute(path),
+        cli::Commands::Resume { ref path } => commands::resume::execute(path),
+        cli::Commands::Doctor => commands::doctor::execute(cli.json),
+        cli::Commands::Watch => commands::watch::execute(),
+        cli::Commands::Logs { lines, follow } => commands::logs::execute(lines, follow),
+        cli::Commands::Export { ref output } => commands::export::execute(output.as_ref()),
+        cli::Commands::Import { ref input, apply } => commands::import::execute(input, apply),
+        cli::Commands::Completions { shell } => commands::completions::execute(shell),
+        cli::Commands::SearchPaths { ref action } => commands::search_paths::execute(action),
+        cli::Commands::Ignore { ref action } => commands::ignore::execute(action),
+        cli::Commands::Config { ref action } => commands::config::execute(action),
+    }?;
+
+    Ok(error::exit_code::SUCCESS)
+}
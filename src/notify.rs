@@ -0,0 +1,53 @@
+use std::process::Command;
+
+/// Posts a macOS user notification through `osascript`.
+pub fn notify(title: &str, body: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let output = Command::new("osascript")
+        .args(osascript_args(title, body))
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("osascript failed: {}", stderr.trim()).into());
+    }
+    Ok(())
+}
+
+fn osascript_args(title: &str, body: &str) -> [String; 2] {
+    [
+        "-e".to_string(),
+        format!(
+            "display notification {} with title {}",
+            applescript_string(body),
+            applescript_string(title)
+        ),
+    ]
+}
+
+/// Quotes `s` as an `AppleScript` string literal.
+fn applescript_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osascript_args_build_display_notification() {
+        assert_eq!(
+            osascript_args("veiled", "Excluded 2 new paths"),
+            [
+                "-e",
+                r#"display notification "Excluded 2 new paths" with title "veiled""#
+            ]
+        );
+    }
+
+    #[test]
+    fn osascript_args_escape_quotes_and_backslashes() {
+        assert_eq!(
+            osascript_args(r#"say "hi""#, r#"C:\dir and a "quote""#)[1],
+            r#"display notification "C:\\dir and a \"quote\"" with title "say \"hi\"""#
+        );
+    }
+}
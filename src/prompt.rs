@@ -0,0 +1,32 @@
+use std::io::{self, IsTerminal, Write};
+
+use crate::{assume_yes, interrupt};
+
+/// Asks a yes/no `question`, defaulting to no. `--assume-yes` answers it
+/// without asking, and when stdin is not a terminal this fails right away
+/// instead of blocking on input that will never arrive.
+pub fn confirm(question: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    Ok(ask(question, "y/N")? == "y")
+}
+
+/// Asks `question` with the answers listed in `choices` and returns the
+/// reply trimmed and lowercased. `--assume-yes` answers `y`; a non-terminal
+/// stdin fails like `confirm`.
+pub fn ask(question: &str, choices: &str) -> Result<String, Box<dyn std::error::Error>> {
+    if assume_yes() {
+        return Ok("y".to_string());
+    }
+    if !io::stdin().is_terminal() {
+        return Err(
+            "confirmation required but stdin is not a terminal; rerun with --assume-yes".into(),
+        );
+    }
+
+    print!("{question} [{choices}] ");
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    interrupt::while_prompting(|| io::stdin().read_line(&mut input))?;
+
+    Ok(input.trim().to_ascii_lowercase())
+}
@@ -1,54 +1,145 @@
+use std::collections::HashSet;
 use std::fs;
-use std::io::{BufReader, Seek};
+use std::io::{BufReader, Write};
 use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use console::style;
-use fs2::FileExt;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::config;
+use crate::disksize::{self, SizeMode};
+use crate::error::Error;
+
+/// A managed exclusion with the size it had when last measured (and the
+/// directory mtime at that point, which keeps the size cached until it
+/// changes), when veiled first excluded it and last confirmed the exclusion
+/// (Unix seconds), whether the path was already excluded by something
+/// else before veiled took it over, and whether its exclusion is suspended
+/// (indefinitely, or until `suspended_until`) so it can be backed up.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_mtime: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub added_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_verified: Option<i64>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pre_existing: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub suspended: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suspended_until: Option<i64>,
+}
+
+impl Entry {
+    /// Whether the exclusion is deliberately off at `now`. A suspension
+    /// whose deadline has passed no longer counts.
+    pub fn is_suspended(&self, now: i64) -> bool {
+        self.suspended && self.suspended_until.is_none_or(|until| now < until)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredEntry {
+    Path(String),
+    Full(Entry),
+}
+
+/// Accepts both the current entry objects and the plain path strings
+/// written by older versions.
+fn deserialize_entries<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Entry>, D::Error> {
+    let stored = Vec::<StoredEntry>::deserialize(deserializer)?;
+    Ok(stored
+        .into_iter()
+        .map(|entry| match entry {
+            StoredEntry::Path(path) => Entry {
+                path,
+                size_bytes: None,
+                size_mtime: None,
+                added_at: None,
+                last_verified: None,
+                pre_existing: false,
+                suspended: false,
+                suspended_until: None,
+            },
+            StoredEntry::Full(entry) => entry,
+        })
+        .collect())
+}
+
+pub fn now_epoch() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs().cast_signed())
+}
+
+/// The summed size after a refresh, and how many entries were measured
+/// rather than taken from their cached size.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SizeRefresh {
+    pub total: u64,
+    pub recomputed: usize,
+}
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Registry {
-    pub paths: Vec<String>,
+    #[serde(deserialize_with = "deserialize_entries")]
+    pub paths: Vec<Entry>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub saved_bytes: Option<u64>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub last_update_check: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_full_run: Option<i64>,
 }
 
-fn registry_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
-    if let Ok(dir) = std::env::var("VEILED_CONFIG_DIR") {
-        return Ok(PathBuf::from(dir).join("registry.json"));
-    }
-    let home = dirs::home_dir().ok_or("could not determine home directory")?;
-    Ok(home.join(".config/veiled/registry.json"))
+pub fn registry_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(config::config_dir()?.join("registry.json"))
 }
 
+/// The lock file guarding the registry at `path`, `registry.lock` beside
+/// `registry.json`.
+pub fn lock_path(path: &Path) -> PathBuf {
+    path.with_extension("lock")
+}
+
+/// The registry, held locked while a command reads and rewrites it. The lock
+/// lives on a sibling file so it survives the rename that replaces the
+/// registry on every save.
 pub struct LockedRegistry {
-    file: fs::File,
+    _lock: fs::File,
+    path: PathBuf,
 }
 
 impl LockedRegistry {
     fn acquire(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        let file = fs::OpenOptions::new()
-            .read(true)
-            .write(true)
+        let open_error = |e: std::io::Error| {
+            Error::Config(format!("failed to open registry {}: {e}", path.display()))
+        };
+        let lock = config::open_locked(&lock_path(path)).map_err(open_error)?;
+        fs::OpenOptions::new()
             .create(true)
-            .truncate(false)
-            .open(path)?;
-        file.lock_exclusive()?;
-        Ok(Self { file })
+            .append(true)
+            .open(path)
+            .map_err(open_error)?;
+        Ok(Self {
+            _lock: lock,
+            path: path.to_path_buf(),
+        })
     }
 
     pub fn load(&mut self) -> Result<Registry, Box<dyn std::error::Error>> {
-        self.file.rewind()?;
-        let metadata = self.file.metadata()?;
-        if metadata.len() == 0 {
+        let file = fs::File::open(&self.path)?;
+        if file.metadata()?.len() == 0 {
             return Ok(Registry::default());
         }
-        let reader = BufReader::new(&self.file);
+        let reader = BufReader::new(file);
         match serde_json::from_reader(reader) {
             Ok(registry) => Ok(registry),
             Err(e) => {
@@ -62,11 +153,21 @@ impl LockedRegistry {
     }
 
     pub fn save(&mut self, registry: &Registry) -> Result<(), Box<dyn std::error::Error>> {
-        self.file.set_len(0)?;
-        self.file.rewind()?;
-        serde_json::to_writer_pretty(&self.file, registry)?;
-        self.file.sync_data()?;
-        Ok(())
+        let content = serde_json::to_vec_pretty(registry)?;
+        self.write(&content)
+            .map_err(|e| Error::Config(format!("failed to write registry: {e}")).into())
+    }
+
+    /// Writes `content` to a temp file beside the registry, syncs it and
+    /// renames it into place, so an interrupt or crash mid-write leaves the
+    /// previous registry intact rather than an empty or partial one.
+    fn write(&mut self, content: &[u8]) -> std::io::Result<()> {
+        let dir = self.path.parent().unwrap_or_else(|| Path::new("."));
+        let mut temp = tempfile::NamedTempFile::new_in(dir)?;
+        temp.write_all(content)?;
+        temp.as_file().sync_all()?;
+        temp.persist(&self.path).map_err(|e| e.error)?;
+        fs::File::open(dir)?.sync_all()
     }
 }
 
@@ -81,24 +182,198 @@ impl Registry {
     }
 
     pub fn add(&mut self, path: &str) {
+        self.insert(path, false);
+    }
+
+    /// Records a path whose exclusion was set before veiled managed it.
+    pub fn add_pre_existing(&mut self, path: &str) {
+        self.insert(path, true);
+    }
+
+    /// Every caller has just excluded `path` or found it excluded, so the
+    /// new entry counts as verified.
+    fn insert(&mut self, path: &str, pre_existing: bool) {
         if !self.contains(path) {
-            self.paths.push(path.to_string());
+            let now = now_epoch();
+            self.paths.push(Entry {
+                path: path.to_string(),
+                size_bytes: None,
+                size_mtime: None,
+                added_at: Some(now),
+                last_verified: Some(now),
+                pre_existing,
+                suspended: false,
+                suspended_until: None,
+            });
         }
     }
 
+    pub fn is_pre_existing(&self, path: &str) -> bool {
+        self.paths.iter().any(|e| e.path == path && e.pre_existing)
+    }
+
+    /// Marks `path` as deliberately not excluded, until `until` or, with
+    /// `None`, until it is resumed. Returns false when `path` is not managed.
+    pub fn suspend(&mut self, path: &str, until: Option<i64>) -> bool {
+        let Some(entry) = self.paths.iter_mut().find(|e| e.path == path) else {
+            return false;
+        };
+        entry.suspended = true;
+        entry.suspended_until = until;
+        true
+    }
+
+    /// Clears the suspension on `path`. Returns false when `path` is not
+    /// managed or was not suspended.
+    pub fn resume(&mut self, path: &str) -> bool {
+        let Some(entry) = self
+            .paths
+            .iter_mut()
+            .find(|e| e.path == path && e.suspended)
+        else {
+            return false;
+        };
+        entry.suspended = false;
+        entry.suspended_until = None;
+        entry.last_verified = Some(now_epoch());
+        true
+    }
+
     pub fn remove(&mut self, path: &str) -> bool {
         let len = self.paths.len();
-        self.paths.retain(|p| p != path);
+        self.paths.retain(|e| e.path != path);
         self.paths.len() < len
     }
 
     pub fn contains(&self, path: &str) -> bool {
-        self.paths.iter().any(|p| p == path)
+        self.paths.iter().any(|e| e.path == path)
+    }
+
+    /// Whether `path` or one of its ancestors is registered. Time Machine
+    /// exclusions are recursive, so a covered path needs no entry of its own.
+    pub fn covers(&self, path: &Path) -> bool {
+        let registered: HashSet<&Path> = self.paths.iter().map(|e| Path::new(&e.path)).collect();
+        path.ancestors()
+            .any(|ancestor| registered.contains(ancestor))
     }
 
-    pub fn list(&self) -> &[String] {
+    /// Drops entries nested inside another registered path and returns them,
+    /// taking their cached sizes off `saved_bytes` so they are not counted
+    /// twice. Each nested entry is first passed to `release`, which clears
+    /// its own exclusion; entries it returns false for stay tracked so a
+    /// later `remove` or `reset` can still restore them.
+    pub fn drop_nested(&mut self, mut release: impl FnMut(&Entry) -> bool) -> Vec<String> {
+        let registered: HashSet<PathBuf> =
+            self.paths.iter().map(|e| PathBuf::from(&e.path)).collect();
+        let (nested, kept): (Vec<Entry>, Vec<Entry>) = self.paths.drain(..).partition(|e| {
+            Path::new(&e.path)
+                .ancestors()
+                .skip(1)
+                .any(|ancestor| registered.contains(ancestor))
+                && release(e)
+        });
+        self.paths = kept;
+
+        let nested_bytes = nested
+            .iter()
+            .filter_map(|e| e.size_bytes)
+            .fold(0u64, u64::saturating_add);
+        if let Some(saved) = self.saved_bytes.as_mut() {
+            *saved = saved.saturating_sub(nested_bytes);
+        }
+        nested.into_iter().map(|e| e.path).collect()
+    }
+
+    pub fn list(&self) -> Vec<String> {
+        self.paths.iter().map(|e| e.path.clone()).collect()
+    }
+
+    pub fn entries(&self) -> &[Entry] {
         &self.paths
     }
+
+    /// Paths whose exclusion was not confirmed in the `cooldown_secs`
+    /// before `now`. A confirmation time in the future counts as stale, and
+    /// suspended entries are left out.
+    pub fn unverified(&self, now: i64, cooldown_secs: i64) -> Vec<String> {
+        self.paths
+            .iter()
+            .filter(|e| !e.is_suspended(now))
+            .filter(|e| {
+                e.last_verified
+                    .is_none_or(|at| at > now || now - at >= cooldown_secs)
+            })
+            .map(|e| e.path.clone())
+            .collect()
+    }
+
+    /// Records that the exclusions of `paths` were confirmed at `now`,
+    /// which also ends any lapsed suspension.
+    pub fn mark_verified(&mut self, paths: &[String], now: i64) {
+        for entry in &mut self.paths {
+            if paths.contains(&entry.path) {
+                entry.last_verified = Some(now);
+                entry.suspended = false;
+                entry.suspended_until = None;
+            }
+        }
+    }
+
+    /// Re-measures every entry whose directory mtime changed since its size
+    /// was cached, storing each size with that mtime.
+    pub fn refresh_sizes(&mut self, mode: SizeMode) -> SizeRefresh {
+        self.measure_sizes(mode, true)
+    }
+
+    /// Re-measures every entry, ignoring cached sizes.
+    pub fn recompute_sizes(&mut self, mode: SizeMode) -> SizeRefresh {
+        self.measure_sizes(mode, false)
+    }
+
+    fn measure_sizes(&mut self, mode: SizeMode, use_cache: bool) -> SizeRefresh {
+        let measured = disksize::measure_parallel(&self.paths, |e| {
+            let path = Path::new(&e.path);
+            let mtime = disksize::dir_mtime(path);
+            let cached = if use_cache { e.size_bytes } else { None };
+            let (size, recomputed) = disksize::cached_dir_size(path, cached, e.size_mtime, mode);
+            (size, mtime, recomputed)
+        });
+        let mut refresh = SizeRefresh::default();
+        for (entry, (size, mtime, recomputed)) in self.paths.iter_mut().zip(measured) {
+            entry.size_bytes = Some(size);
+            entry.size_mtime = mtime;
+            refresh.total = refresh.total.saturating_add(size);
+            refresh.recomputed += usize::from(recomputed);
+        }
+        refresh
+    }
+
+    /// Entries ordered largest first, using the tracked size and measuring
+    /// only the entries that have none yet.
+    pub fn largest(&self, mode: SizeMode) -> Vec<(String, u64)> {
+        let mut sized: Vec<(String, u64)> = self
+            .paths
+            .iter()
+            .map(|e| {
+                let size = e
+                    .size_bytes
+                    .unwrap_or_else(|| disksize::dir_size(Path::new(&e.path), mode));
+                (e.path.clone(), size)
+            })
+            .collect();
+        sized.sort_by(|(a_path, a), (b_path, b)| b.cmp(a).then_with(|| a_path.cmp(b_path)));
+        sized
+    }
+
+    /// Drops entries whose path no longer exists on disk and returns them.
+    pub fn prune_missing(&mut self) -> Vec<String> {
+        let (kept, missing): (Vec<Entry>, Vec<Entry>) = self
+            .paths
+            .drain(..)
+            .partition(|e| Path::new(&e.path).exists());
+        self.paths = kept;
+        missing.into_iter().map(|e| e.path).collect()
+    }
 }
 
 #[cfg(test)]
@@ -175,6 +450,102 @@ mod tests {
         assert!(registry.contains("/Users/dev/project/target"));
     }
 
+    #[test]
+    fn drop_nested_removes_descendants_of_registered_paths() {
+        let mut registry = Registry::default();
+        registry.add("/Users/dev/Projects/app");
+        registry.add("/Users/dev/Projects/app/node_modules");
+        registry.add("/Users/dev/Projects/app/web/dist");
+        registry.add("/Users/dev/Projects/app-two/node_modules");
+
+        let dropped = registry.drop_nested(|_| true);
+
+        assert_eq!(
+            dropped,
+            vec![
+                "/Users/dev/Projects/app/node_modules",
+                "/Users/dev/Projects/app/web/dist"
+            ]
+        );
+        assert_eq!(
+            registry.list(),
+            vec![
+                "/Users/dev/Projects/app",
+                "/Users/dev/Projects/app-two/node_modules"
+            ]
+        );
+    }
+
+    #[test]
+    fn drop_nested_subtracts_child_sizes_from_saved_bytes() {
+        let mut registry = Registry::default();
+        registry.add("/Users/dev/Projects/app");
+        registry.add("/Users/dev/Projects/app/node_modules");
+        registry.paths[0].size_bytes = Some(5_000);
+        registry.paths[1].size_bytes = Some(3_000);
+        registry.saved_bytes = Some(8_000);
+
+        registry.drop_nested(|_| true);
+
+        assert_eq!(registry.saved_bytes, Some(5_000));
+    }
+
+    #[test]
+    fn drop_nested_keeps_unrelated_paths() {
+        let mut registry = Registry::default();
+        registry.add("/Users/dev/a/node_modules");
+        registry.add("/Users/dev/b/target");
+
+        assert!(
+            registry
+                .drop_nested(|_| panic!("nothing is nested"))
+                .is_empty()
+        );
+        assert_eq!(registry.entries().len(), 2);
+    }
+
+    #[test]
+    fn drop_nested_keeps_children_that_could_not_be_released() {
+        let mut registry = Registry::default();
+        registry.add("/Users/dev/Projects/app");
+        registry.add("/Users/dev/Projects/app/node_modules");
+        registry.add("/Users/dev/Projects/app/target");
+        registry.paths[1].size_bytes = Some(3_000);
+        registry.saved_bytes = Some(3_000);
+
+        let dropped = registry.drop_nested(|e| e.path.ends_with("target"));
+
+        assert_eq!(dropped, vec!["/Users/dev/Projects/app/target"]);
+        assert!(registry.contains("/Users/dev/Projects/app/node_modules"));
+        assert_eq!(registry.saved_bytes, Some(3_000));
+    }
+
+    #[test]
+    fn covers_matches_self_and_descendants_only() {
+        let mut registry = Registry::default();
+        registry.add("/Users/dev/app");
+
+        assert!(registry.covers(Path::new("/Users/dev/app")));
+        assert!(registry.covers(Path::new("/Users/dev/app/node_modules")));
+        assert!(!registry.covers(Path::new("/Users/dev/app-two/node_modules")));
+        assert!(!registry.covers(Path::new("/Users/dev")));
+    }
+
+    #[test]
+    fn prune_missing_removes_only_nonexistent_paths() {
+        let dir = TempDir::new().unwrap();
+        let existing = dir.path().to_string_lossy().into_owned();
+
+        let mut registry = Registry::default();
+        registry.add(&existing);
+        registry.add("/nonexistent/project/node_modules");
+
+        let pruned = registry.prune_missing();
+
+        assert_eq!(pruned, vec!["/nonexistent/project/node_modules"]);
+        assert_eq!(registry.list(), [existing]);
+    }
+
     #[test]
     fn save_and_load_roundtrip() {
         let dir = TempDir::new().unwrap();
@@ -245,6 +616,7 @@ mod tests {
         reg.add("/Users/dev/project/node_modules");
         reg.saved_bytes = Some(500_000_000);
         reg.last_update_check = Some(1_700_000_000);
+        reg.last_full_run = Some(1_700_003_600);
         guard.save(&reg).unwrap();
         drop(guard);
 
@@ -255,6 +627,7 @@ mod tests {
         assert!(loaded.contains("/Users/dev/project/node_modules"));
         assert_eq!(loaded.saved_bytes, Some(500_000_000));
         assert_eq!(loaded.last_update_check, Some(1_700_000_000));
+        assert_eq!(loaded.last_full_run, Some(1_700_003_600));
     }
 
     #[test]
@@ -269,6 +642,27 @@ mod tests {
         assert!(path.exists());
     }
 
+    #[test]
+    fn save_replaces_the_registry_without_leaving_temp_files() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("registry.json");
+        let mut guard = Registry::locked_at(&path).unwrap();
+        let mut reg = Registry::default();
+        reg.add("/Users/dev/app/node_modules");
+
+        guard.save(&reg).unwrap();
+        reg.add("/Users/dev/app/target");
+        guard.save(&reg).unwrap();
+
+        let mut names: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["registry.json", "registry.lock"]);
+        assert_eq!(guard.load().unwrap().list().len(), 2);
+    }
+
     #[test]
     fn last_update_check_defaults_to_none() {
         let registry = Registry::default();
@@ -307,5 +701,267 @@ mod tests {
         assert_eq!(loaded.list().len(), 1);
         assert_eq!(loaded.saved_bytes, Some(1024));
         assert!(loaded.last_update_check.is_none());
+        assert!(loaded.last_full_run.is_none());
+    }
+
+    #[test]
+    fn add_records_discovery_time() {
+        let before = now_epoch();
+        let mut registry = Registry::default();
+        registry.add("/Users/dev/project/node_modules");
+
+        let entry = &registry.entries()[0];
+        assert!(entry.added_at.is_some_and(|t| t >= before));
+        assert!(entry.size_bytes.is_none());
+    }
+
+    #[test]
+    fn legacy_string_paths_load_as_entries() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("registry.json");
+
+        fs::write(
+            &path,
+            r#"{"paths": ["/Users/dev/node_modules", {"path": "/Users/dev/target", "size_bytes": 42, "added_at": 1700000000}]}"#,
+        )
+        .unwrap();
+
+        let mut guard = Registry::locked_at(&path).unwrap();
+        let loaded = guard.load().unwrap();
+
+        assert_eq!(
+            loaded.entries(),
+            [
+                Entry {
+                    path: "/Users/dev/node_modules".to_string(),
+                    size_bytes: None,
+                    size_mtime: None,
+                    added_at: None,
+                    last_verified: None,
+                    pre_existing: false,
+                    suspended: false,
+                    suspended_until: None,
+                },
+                Entry {
+                    path: "/Users/dev/target".to_string(),
+                    size_bytes: Some(42),
+                    size_mtime: None,
+                    added_at: Some(1_700_000_000),
+                    last_verified: None,
+                    pre_existing: false,
+                    suspended: false,
+                    suspended_until: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unverified_skips_suspended_entries_until_deadline() {
+        let mut reg = Registry::default();
+        reg.add("/timed");
+        reg.add("/open-ended");
+        reg.paths[0].last_verified = None;
+        reg.paths[1].last_verified = None;
+
+        assert!(reg.suspend("/timed", Some(2_000)));
+        assert!(reg.suspend("/open-ended", None));
+        assert!(!reg.suspend("/unmanaged", None));
+
+        assert!(reg.unverified(1_999, 0).is_empty());
+        assert_eq!(reg.unverified(2_000, 0), vec!["/timed"]);
+        assert!(reg.entries()[1].is_suspended(i64::MAX));
+    }
+
+    #[test]
+    fn resume_clears_suspension_only_for_suspended_entries() {
+        let mut reg = Registry::default();
+        reg.add("/held");
+        reg.add("/active");
+        reg.suspend("/held", None);
+
+        assert!(reg.resume("/held"));
+        assert!(!reg.entries()[0].suspended);
+        assert!(!reg.resume("/active"));
+        assert!(!reg.resume("/unmanaged"));
+    }
+
+    #[test]
+    fn mark_verified_clears_lapsed_suspension() {
+        let mut reg = Registry::default();
+        reg.add("/timed");
+        reg.suspend("/timed", Some(10));
+
+        reg.mark_verified(&["/timed".to_string()], 20);
+
+        assert!(!reg.entries()[0].suspended);
+        assert!(reg.entries()[0].suspended_until.is_none());
+    }
+
+    #[test]
+    fn unverified_skips_recently_verified_and_returns_stale() {
+        let mut reg = Registry::default();
+        reg.add("/recent");
+        reg.add("/stale");
+        reg.add("/never");
+        reg.paths[2].last_verified = None;
+        let now = 1_700_000_000;
+        reg.mark_verified(&["/recent".to_string()], now - 60);
+        reg.mark_verified(&["/stale".to_string()], now - 7 * 3600);
+
+        assert_eq!(reg.unverified(now, 6 * 3600), vec!["/stale", "/never"]);
+        assert_eq!(reg.unverified(now, 0), vec!["/recent", "/stale", "/never"]);
+    }
+
+    #[test]
+    fn unverified_treats_future_timestamps_as_stale() {
+        let mut reg = Registry::default();
+        reg.add("/skewed");
+        reg.mark_verified(&["/skewed".to_string()], 2_000_000_000);
+
+        assert_eq!(reg.unverified(1_700_000_000, 6 * 3600), vec!["/skewed"]);
+    }
+
+    #[test]
+    fn pre_existing_flag_roundtrips_and_is_omitted_when_unset() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("registry.json");
+
+        let mut guard = Registry::locked_at(&path).unwrap();
+        let mut registry = Registry::default();
+        registry.add("/Users/dev/app/node_modules");
+        registry.add_pre_existing("/Users/dev/Library/Caches");
+        guard.save(&registry).unwrap();
+        drop(guard);
+
+        let raw = fs::read_to_string(&path).unwrap();
+        assert_eq!(raw.matches("pre_existing").count(), 1);
+
+        let loaded = Registry::locked_at(&path).unwrap().load().unwrap();
+        assert!(!loaded.is_pre_existing("/Users/dev/app/node_modules"));
+        assert!(loaded.is_pre_existing("/Users/dev/Library/Caches"));
+    }
+
+    #[test]
+    fn entries_roundtrip_with_size_and_time() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("registry.json");
+
+        let mut guard = Registry::locked_at(&path).unwrap();
+        let mut registry = Registry::default();
+        registry.add("/Users/dev/app/node_modules");
+        registry.paths[0].size_bytes = Some(2048);
+        guard.save(&registry).unwrap();
+        drop(guard);
+
+        let loaded = Registry::locked_at(&path).unwrap().load().unwrap();
+
+        assert_eq!(loaded.entries(), registry.entries());
+    }
+
+    #[test]
+    fn largest_orders_by_size_and_measures_unknown() {
+        let unknown = TempDir::new().unwrap();
+        fs::write(unknown.path().join("a.bin"), vec![0u8; 50]).unwrap();
+        let unknown_path = unknown.path().to_string_lossy().into_owned();
+
+        let mut registry = Registry::default();
+        registry.add("/Users/dev/small");
+        registry.add("/Users/dev/big");
+        registry.add(&unknown_path);
+        registry.paths[0].size_bytes = Some(10);
+        registry.paths[1].size_bytes = Some(300);
+
+        assert_eq!(
+            registry.largest(SizeMode::Logical),
+            vec![
+                ("/Users/dev/big".to_string(), 300),
+                (unknown_path, 50),
+                ("/Users/dev/small".to_string(), 10),
+            ]
+        );
+    }
+
+    #[test]
+    fn refresh_sizes_stores_per_entry_sizes() {
+        let d1 = TempDir::new().unwrap();
+        let d2 = TempDir::new().unwrap();
+        fs::write(d1.path().join("a.bin"), vec![0u8; 300]).unwrap();
+        fs::write(d2.path().join("b.bin"), vec![0u8; 700]).unwrap();
+
+        let mut registry = Registry::default();
+        registry.add(&d1.path().to_string_lossy());
+        registry.add(&d2.path().to_string_lossy());
+
+        let total = registry.refresh_sizes(SizeMode::Logical).total;
+
+        assert_eq!(total, 1000);
+        assert_eq!(registry.entries()[0].size_bytes, Some(300));
+        assert_eq!(registry.entries()[1].size_bytes, Some(700));
+    }
+
+    #[test]
+    fn refresh_sizes_reuses_cache_until_mtime_changes() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.bin"), vec![0u8; 300]).unwrap();
+        let mut registry = Registry::default();
+        registry.add(&dir.path().to_string_lossy());
+        registry.refresh_sizes(SizeMode::Logical);
+
+        registry.paths[0].size_bytes = Some(5);
+        assert_eq!(registry.refresh_sizes(SizeMode::Logical).total, 5);
+
+        registry.paths[0].size_mtime = Some(0);
+        assert_eq!(registry.refresh_sizes(SizeMode::Logical).total, 300);
+    }
+
+    #[test]
+    fn refresh_sizes_recomputes_only_touched_dirs() {
+        let untouched = TempDir::new().unwrap();
+        let touched = TempDir::new().unwrap();
+        fs::write(untouched.path().join("a.bin"), vec![0u8; 100]).unwrap();
+        fs::write(touched.path().join("b.bin"), vec![0u8; 200]).unwrap();
+        let mut registry = Registry::default();
+        registry.add(&untouched.path().to_string_lossy());
+        registry.add(&touched.path().to_string_lossy());
+        registry.refresh_sizes(SizeMode::Logical);
+        registry.paths[0].size_bytes = Some(1);
+        registry.paths[1].size_bytes = Some(2);
+
+        fs::File::open(touched.path())
+            .unwrap()
+            .set_modified(SystemTime::now() - std::time::Duration::from_hours(1))
+            .unwrap();
+        let refresh = registry.refresh_sizes(SizeMode::Logical);
+
+        assert_eq!(
+            refresh,
+            SizeRefresh {
+                total: 201,
+                recomputed: 1
+            }
+        );
+        assert_eq!(registry.entries()[0].size_bytes, Some(1));
+        assert_eq!(registry.entries()[1].size_bytes, Some(200));
+    }
+
+    #[test]
+    fn recompute_sizes_ignores_cache() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.bin"), vec![0u8; 100]).unwrap();
+        let mut registry = Registry::default();
+        registry.add(&dir.path().to_string_lossy());
+        registry.refresh_sizes(SizeMode::Logical);
+        registry.paths[0].size_bytes = Some(1);
+
+        let refresh = registry.recompute_sizes(SizeMode::Logical);
+
+        assert_eq!(
+            refresh,
+            SizeRefresh {
+                total: 100,
+                recomputed: 1
+            }
+        );
     }
 }
@@ -1,17 +1,91 @@
 use std::collections::HashSet;
 use std::fs;
+use std::io::Read;
+use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Condvar, Mutex, PoisonError};
 use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
 use console::style;
+use serde::{Deserialize, Serialize};
 
-use crate::builtins;
+use crate::builtins::{BuiltinNames, glob_matches};
 use crate::config::Config;
-use crate::verbose;
+use crate::interrupt::{self, StopFlag};
+use crate::{log, verbose};
+
+/// Marker file that opts any directory into exclusion regardless of its name.
+const NOBACKUP_MARKER: &str = ".nobackup";
+
+/// Worker threads reading directories during the walk.
+const WALK_THREADS: usize = 8;
+
+/// How often a running `git` process is checked against its deadline.
+const GIT_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// What one scan did, for tuning `ignore_paths`: directories read, the ones
+/// passed over and why, git repositories handed to `git ls-files`, and the
+/// wall-clock time it all took.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct ScanStats {
+    pub dirs_visited: usize,
+    pub skipped_ignored: usize,
+    pub skipped_symlinks: usize,
+    pub skipped_hidden: usize,
+    pub git_repos: usize,
+    pub elapsed_ms: u64,
+}
+
+/// Walk counters shared by the worker threads.
+#[derive(Debug, Default)]
+struct WalkCounters {
+    visited: AtomicUsize,
+    ignored: AtomicUsize,
+    symlinks: AtomicUsize,
+    hidden: AtomicUsize,
+}
+
+impl WalkCounters {
+    fn bump(counter: &AtomicUsize) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn stats(&self, git_repos: usize) -> ScanStats {
+        ScanStats {
+            dirs_visited: self.visited.load(Ordering::Relaxed),
+            skipped_ignored: self.ignored.load(Ordering::Relaxed),
+            skipped_symlinks: self.symlinks.load(Ordering::Relaxed),
+            skipped_hidden: self.hidden.load(Ordering::Relaxed),
+            git_repos,
+            elapsed_ms: 0,
+        }
+    }
+}
+
+/// Collects exclusion candidates. With `modified_since`, directories below
+/// the search paths whose mtime is older than the cutoff are not descended
+/// into.
+pub fn scan(
+    config: &Config,
+    modified_since: Option<SystemTime>,
+    on_found: &dyn Fn(usize),
+) -> Vec<PathBuf> {
+    scan_with_stats(config, modified_since, on_found).0
+}
 
-pub fn scan(config: &Config, on_found: &dyn Fn(usize)) -> Vec<PathBuf> {
-    let candidates = collect_paths(config, on_found);
+/// `scan`, also returning what the walk did.
+pub fn scan_with_stats(
+    config: &Config,
+    modified_since: Option<SystemTime>,
+    on_found: &dyn Fn(usize),
+) -> (Vec<PathBuf>, ScanStats) {
+    let started = Instant::now();
+    let (candidates, mut stats) = collect_paths(config, modified_since, on_found);
+    stats.elapsed_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
 
     if verbose() && candidates.is_empty() {
         eprintln!(
@@ -20,14 +94,44 @@ pub fn scan(config: &Config, on_found: &dyn Fn(usize)) -> Vec<PathBuf> {
         );
     }
 
-    candidates
+    (candidates, stats)
 }
 
-fn collect_paths(config: &Config, on_found: &dyn Fn(usize)) -> Vec<PathBuf> {
-    let mut paths: HashSet<PathBuf> =
-        traverse(&config.search_paths, &config.ignore_paths, on_found)
-            .into_iter()
-            .collect();
+fn collect_paths(
+    config: &Config,
+    modified_since: Option<SystemTime>,
+    on_found: &dyn Fn(usize),
+) -> (Vec<PathBuf>, ScanStats) {
+    let options = WalkOptions {
+        ignore_paths: &config.ignore_paths,
+        custom_builtins: &config.custom_builtins,
+        builtin_globs: &config.builtin_globs,
+        file_globs: &config.exclude_file_globs,
+        ignore_names: &config.ignore_names,
+        max_depth: config.max_depth,
+        follow_symlinks: config.follow_symlinks,
+        include_hidden: config.include_hidden,
+        match_depth: config.match_depth,
+        modified_since,
+        git_timeout: Some(Duration::from_secs(config.git_timeout_secs)),
+        scan_concurrency: config.scan_concurrency,
+        stop: Some(interrupt::stop()),
+    };
+    let ignored = config.ignored_search_paths();
+    for (search, ignore) in &ignored {
+        log::warn(&format!(
+            "search path {search} is inside ignore path {ignore}, skipping it"
+        ));
+    }
+    let search_paths: Vec<String> = config
+        .search_paths
+        .iter()
+        .filter(|path| !ignored.iter().any(|(search, _)| search == path))
+        .cloned()
+        .collect();
+
+    let (found, stats) = traverse(&search_paths, &options, on_found);
+    let mut paths: HashSet<PathBuf> = found.into_iter().collect();
 
     for extra in &config.extra_exclusions {
         let path = PathBuf::from(extra);
@@ -38,7 +142,7 @@ fn collect_paths(config: &Config, on_found: &dyn Fn(usize)) -> Vec<PathBuf> {
 
     let mut results: Vec<PathBuf> = paths.into_iter().collect();
     results.sort();
-    results
+    (results, stats)
 }
 
 pub fn parse_git_ignored(repo_path: &Path, output: &str) -> Vec<PathBuf> {
@@ -59,29 +163,89 @@ pub fn parse_git_ignored(repo_path: &Path, output: &str) -> Vec<PathBuf> {
     paths.into_iter().collect()
 }
 
-pub fn scan_git_repo(repo_path: &Path) -> Vec<PathBuf> {
-    let output = Command::new("git")
+/// Parses `git config -z --get-regexp` output over `.gitmodules` into the
+/// submodule directories below `repo_path`.
+fn parse_submodule_paths(repo_path: &Path, output: &str) -> Vec<PathBuf> {
+    output
+        .split('\0')
+        .filter_map(|entry| entry.split_once('\n'))
+        .map(|(_, path)| repo_path.join(path))
+        .collect()
+}
+
+/// Submodules of `repo_path` that are checked out. Repos without a
+/// `.gitmodules` file are answered without running `git`.
+fn submodule_paths(repo_path: &Path) -> Vec<PathBuf> {
+    if !repo_path.join(".gitmodules").is_file() {
+        return vec![];
+    }
+    let Ok(output) = Command::new("git")
         .arg("-C")
         .arg(repo_path)
         .args([
-            "ls-files",
-            "--ignored",
-            "--others",
-            "--exclude-standard",
-            "--directory",
+            "config",
+            "--file",
+            ".gitmodules",
             "-z",
+            "--get-regexp",
+            r"^submodule\..*\.path$",
         ])
-        .output();
+        .output()
+    else {
+        return vec![];
+    };
 
-    let Ok(output) = output else {
-        if verbose() {
-            eprintln!(
-                "{} git command failed in {}",
-                style("verbose:").dim(),
-                repo_path.display()
-            );
+    parse_submodule_paths(repo_path, &String::from_utf8_lossy(&output.stdout))
+        .into_iter()
+        .filter(|path| path.join(".git").exists())
+        .collect()
+}
+
+/// Lists the ignored directories of the repository at `repo_path` and,
+/// recursively, of its checked-out submodules, whose contents the parent's
+/// `git ls-files` does not report. A `git` that runs longer than `timeout`
+/// is killed and that repo skipped.
+pub fn scan_git_repo(repo_path: &Path, timeout: Option<Duration>) -> Vec<PathBuf> {
+    let mut paths: HashSet<PathBuf> = git_ignored_dirs(repo_path, timeout).into_iter().collect();
+    for submodule in submodule_paths(repo_path) {
+        paths.extend(scan_git_repo(&submodule, timeout));
+    }
+    paths.into_iter().collect()
+}
+
+fn git_ignored_dirs(repo_path: &Path, timeout: Option<Duration>) -> Vec<PathBuf> {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(repo_path).args([
+        "ls-files",
+        "--ignored",
+        "--others",
+        "--exclude-standard",
+        "--directory",
+        "-z",
+    ]);
+
+    let output = match output_with_timeout(&mut command, timeout) {
+        Ok(Some(output)) => output,
+        Ok(None) => {
+            if verbose() {
+                eprintln!(
+                    "{} git ls-files timed out in {}, skipping it",
+                    style("verbose:").dim(),
+                    repo_path.display()
+                );
+            }
+            return vec![];
+        }
+        Err(_) => {
+            if verbose() {
+                eprintln!(
+                    "{} git command failed in {}",
+                    style("verbose:").dim(),
+                    repo_path.display()
+                );
+            }
+            return vec![];
         }
-        return vec![];
     };
 
     if !output.status.success() {
@@ -101,17 +265,282 @@ pub fn scan_git_repo(repo_path: &Path) -> Vec<PathBuf> {
     parse_git_ignored(repo_path, &stdout)
 }
 
+/// Runs `command` like `Command::output`, but kills it and returns `None`
+/// once `timeout` elapses. Stdout and stderr are drained on their own
+/// threads so a chatty process cannot stall on a full pipe.
+fn output_with_timeout(
+    command: &mut Command,
+    timeout: Option<Duration>,
+) -> std::io::Result<Option<Output>> {
+    let Some(timeout) = timeout else {
+        return command.output().map(Some);
+    };
+
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            kill(&mut child);
+            return Ok(None);
+        }
+        thread::sleep(GIT_POLL_INTERVAL);
+    };
+
+    Ok(Some(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    }))
+}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+fn kill(child: &mut Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Where builtin names count: `Any` depth the walk reaches, or `RootOnly`
+/// for the immediate children of a search path. Deeper builtin-named
+/// directories are then neither matched nor walked into.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MatchDepth {
+    #[default]
+    Any,
+    RootOnly,
+}
+
+/// Settings for `traverse`, mirroring the scan-related config keys.
+#[derive(Debug, Default)]
+pub struct WalkOptions<'a> {
+    pub ignore_paths: &'a [String],
+    pub custom_builtins: &'a [String],
+    pub builtin_globs: &'a [String],
+    pub file_globs: &'a [String],
+    pub ignore_names: &'a [String],
+    pub max_depth: Option<usize>,
+    pub follow_symlinks: bool,
+    pub include_hidden: bool,
+    pub match_depth: MatchDepth,
+    pub modified_since: Option<SystemTime>,
+    pub git_timeout: Option<Duration>,
+    pub scan_concurrency: Option<usize>,
+    /// Checked before each directory and git repo; once set, the walk
+    /// returns what it found so far.
+    pub stop: Option<&'static StopFlag>,
+}
+
+/// Walks `search_paths` collecting builtin directories. Search paths are at
+/// depth 0; with `max_depth` set, directories at that depth are still
+/// recorded but never descended into. Symlinked directories are skipped
+/// unless `follow_symlinks` is set, in which case each directory is visited
+/// at most once by device and inode. Hidden directories that are not
+/// builtins are skipped unless `include_hidden` is set. Directories below the
+/// search paths last modified before `modified_since` are skipped entirely.
+/// A repo whose `git ls-files` outlasts `git_timeout` is skipped. Git repos
+/// are scanned on `scan_concurrency` threads, defaulting to the available
+/// parallelism. Files whose name matches one of `file_globs` are candidates
+/// too; with none set only directories are. Returns
+/// the candidates along with counts of what the walk visited and skipped.
 pub fn traverse(
     search_paths: &[String],
-    ignore_paths: &[String],
+    options: &WalkOptions,
     on_found: &dyn Fn(usize),
-) -> Vec<PathBuf> {
-    let ignore_set: HashSet<PathBuf> = ignore_paths.iter().map(PathBuf::from).collect();
+) -> (Vec<PathBuf>, ScanStats) {
+    let counters = WalkCounters::default();
+    let (mut results, git_repos) = walk(search_paths, options, WALK_THREADS, &counters, on_found);
+    let threads = options.scan_concurrency.unwrap_or_else(|| {
+        thread::available_parallelism().map_or(WALK_THREADS, std::num::NonZero::get)
+    });
+    scan_git_repos(&git_repos, threads, options, &mut results, on_found);
+    // Gitignored dirs come from git, not the walk; drop the ignored ones.
+    results.retain(|path| {
+        !options
+            .ignore_paths
+            .iter()
+            .any(|ignored| path.starts_with(ignored))
+    });
+    (results, counters.stats(git_repos.len()))
+}
+
+/// What a walk worker reports back to the thread collecting results.
+enum Discovery {
+    Candidate(PathBuf),
+    GitRepo(PathBuf),
+}
+
+/// Directories waiting to be read. `active` counts workers currently
+/// reading one; the walk is done once nothing is pending and none are.
+#[derive(Default)]
+struct WorkQueue {
+    pending: Vec<(PathBuf, usize)>,
+    active: usize,
+}
+
+struct Walker<'a> {
+    options: &'a WalkOptions<'a>,
+    counters: &'a WalkCounters,
+    ignore_set: HashSet<PathBuf>,
+    builtins: BuiltinNames,
+    visited: Mutex<HashSet<(u64, u64)>>,
+    queue: Mutex<WorkQueue>,
+    ready: Condvar,
+}
+
+/// Walks the plain directory tree on `threads` workers sharing one queue,
+/// returning the candidates found and the git repositories to hand to
+/// `scan_git_repos`. The set of paths does not depend on `threads`, only
+/// their order does.
+fn walk(
+    search_paths: &[String],
+    options: &WalkOptions,
+    threads: usize,
+    counters: &WalkCounters,
+    on_found: &dyn Fn(usize),
+) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let walker = Walker {
+        options,
+        counters,
+        ignore_set: options.ignore_paths.iter().map(PathBuf::from).collect(),
+        builtins: BuiltinNames::new(options.custom_builtins, options.ignore_names)
+            .with_globs(options.builtin_globs),
+        visited: Mutex::new(HashSet::new()),
+        queue: Mutex::new(WorkQueue {
+            pending: search_paths.iter().map(|p| (PathBuf::from(p), 0)).collect(),
+            active: 0,
+        }),
+        ready: Condvar::new(),
+    };
     let mut results = Vec::new();
     let mut git_repos = Vec::new();
-    let mut stack: Vec<PathBuf> = search_paths.iter().map(PathBuf::from).collect();
 
-    while let Some(dir) = stack.pop() {
+    thread::scope(|scope| {
+        let (tx, rx) = mpsc::channel();
+        for _ in 0..threads.max(1) {
+            let tx = tx.clone();
+            let walker = &walker;
+            scope.spawn(move || walker.work(&tx));
+        }
+        drop(tx);
+
+        for discovery in rx {
+            match discovery {
+                Discovery::Candidate(path) => {
+                    results.push(path);
+                    on_found(results.len());
+                }
+                Discovery::GitRepo(path) => git_repos.push(path),
+            }
+        }
+    });
+
+    (results, git_repos)
+}
+
+impl Walker<'_> {
+    fn work(&self, found: &Sender<Discovery>) {
+        while let Some((dir, depth)) = self.next_dir() {
+            let children = self.visit(&dir, depth, found);
+            let mut queue = self.queue.lock().unwrap_or_else(PoisonError::into_inner);
+            queue.pending.extend(children);
+            queue.active -= 1;
+            self.ready.notify_all();
+        }
+    }
+
+    /// Blocks until a directory is available, or returns `None` once the
+    /// queue is drained and no other worker can add to it, or a stop was
+    /// requested.
+    fn next_dir(&self) -> Option<(PathBuf, usize)> {
+        let mut queue = self.queue.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            if self.options.stop.is_some_and(StopFlag::is_set) {
+                return None;
+            }
+            if let Some(item) = queue.pending.pop() {
+                queue.active += 1;
+                return Some(item);
+            }
+            if queue.active == 0 {
+                return None;
+            }
+            queue = self
+                .ready
+                .wait(queue)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Whether `path` is an ignore path, counting it as skipped if so.
+    fn is_ignored(&self, path: &Path) -> bool {
+        let ignored = self.ignore_set.contains(path);
+        if ignored {
+            WalkCounters::bump(&self.counters.ignored);
+        }
+        ignored
+    }
+
+    /// Whether `entry` is a directory, following symlinks only when
+    /// `follow_symlinks` is set. `None` for entries to skip outright.
+    fn entry_is_dir(&self, entry: &fs::DirEntry) -> Option<bool> {
+        let ft = entry.file_type().ok()?;
+        if !ft.is_symlink() {
+            return Some(ft.is_dir());
+        }
+        if !self.options.follow_symlinks {
+            WalkCounters::bump(&self.counters.symlinks);
+            return None;
+        }
+        Some(entry.path().is_dir())
+    }
+
+    /// Whether the file at `path` matches an `exclude_file_globs` pattern
+    /// and is not itself an ignore path.
+    fn matches_file(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            return false;
+        };
+        self.options
+            .file_globs
+            .iter()
+            .any(|glob| glob_matches(glob, name))
+            && !self.is_ignored(path)
+    }
+
+    fn first_visit(&self, path: &Path) -> bool {
+        fs::metadata(path).map_or(true, |m| {
+            self.visited
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert((m.dev(), m.ino()))
+        })
+    }
+
+    /// Reads one directory, reporting builtins and git repositories through
+    /// `found` and returning the subdirectories still to walk.
+    fn visit(&self, dir: &Path, depth: usize, found: &Sender<Discovery>) -> Vec<(PathBuf, usize)> {
+        let options = self.options;
+        let mut children = Vec::new();
+
         if !dir.is_dir() {
             if verbose() {
                 eprintln!(
@@ -120,18 +549,49 @@ pub fn traverse(
                     dir.display()
                 );
             }
-            continue;
+            return children;
         }
 
-        if ignore_set.contains(&dir) {
-            continue;
+        if self.is_ignored(dir) {
+            return children;
+        }
+
+        if options.follow_symlinks && !self.first_visit(dir) {
+            WalkCounters::bump(&self.counters.symlinks);
+            return children;
+        }
+
+        if depth > 0
+            && options
+                .modified_since
+                .is_some_and(|cutoff| modified_before(dir, cutoff))
+        {
+            if verbose() {
+                eprintln!(
+                    "{} not modified recently, skipping: {}",
+                    style("verbose:").dim(),
+                    dir.display()
+                );
+            }
+            return children;
         }
 
         if dir.join(".git").is_dir() {
-            git_repos.push(dir.clone());
+            let _ = found.send(Discovery::GitRepo(dir.to_path_buf()));
+        }
+
+        if options.max_depth.is_some_and(|max| depth >= max) {
+            if verbose() {
+                eprintln!(
+                    "{} max depth reached, not descending: {}",
+                    style("verbose:").dim(),
+                    dir.display()
+                );
+            }
+            return children;
         }
 
-        let Ok(entries) = fs::read_dir(&dir) else {
+        let Ok(entries) = fs::read_dir(dir) else {
             if verbose() {
                 eprintln!(
                     "{} cannot read directory: {}",
@@ -139,29 +599,69 @@ pub fn traverse(
                     dir.display()
                 );
             }
-            continue;
+            return children;
         };
+        WalkCounters::bump(&self.counters.visited);
 
         for entry in entries.flatten() {
-            let Ok(ft) = entry.file_type() else {
+            let path = entry.path();
+            let Some(is_dir) = self.entry_is_dir(&entry) else {
                 continue;
             };
-            if !ft.is_dir() || ft.is_symlink() {
+            if !is_dir {
+                if self.matches_file(&path) {
+                    let _ = found.send(Discovery::Candidate(path));
+                }
                 continue;
             }
-            let path = entry.path();
-            if let Some(name) = path.file_name()
-                && builtins::is_builtin(&name.to_string_lossy())
-            {
-                results.push(path);
-                on_found(results.len());
+            if self.is_ignored(&path) {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let builtin = self.builtins.matches_dir(&path);
+            let matched = builtin && (depth == 0 || options.match_depth == MatchDepth::Any);
+            if matched || path.join(NOBACKUP_MARKER).is_file() {
+                if options.follow_symlinks && !self.first_visit(&path) {
+                    continue;
+                }
+                let _ = found.send(Discovery::Candidate(path));
+            } else if builtin {
+                if verbose() {
+                    eprintln!(
+                        "{} builtin below root with match_depth root-only, skipping: {}",
+                        style("verbose:").dim(),
+                        path.display()
+                    );
+                }
+            } else if options.include_hidden || !name.starts_with('.') {
+                children.push((path, depth + 1));
             } else {
-                stack.push(path);
+                WalkCounters::bump(&self.counters.hidden);
             }
         }
+
+        children
     }
+}
+
+/// Splits `repos` repositories into at most `threads` chunks whose sizes
+/// differ by no more than one repo.
+fn git_chunk_size(repos: usize, threads: usize) -> usize {
+    repos.div_ceil(threads.max(1)).max(1)
+}
 
-    let chunk_size = (git_repos.len() / 8).max(1);
+/// Runs `scan_git_repo` over `git_repos` on up to `threads` threads,
+/// appending the ignored directories to `results` as each thread finishes.
+/// Repos not yet started when a stop is requested are skipped.
+fn scan_git_repos(
+    git_repos: &[PathBuf],
+    threads: usize,
+    options: &WalkOptions,
+    results: &mut Vec<PathBuf>,
+    on_found: &dyn Fn(usize),
+) {
+    let (timeout, stop) = (options.git_timeout, options.stop);
+    let chunk_size = git_chunk_size(git_repos.len(), threads);
     let chunks: Vec<Vec<PathBuf>> = git_repos
         .chunks(chunk_size)
         .map(<[PathBuf]>::to_vec)
@@ -173,7 +673,8 @@ pub fn traverse(
             thread::spawn(move || {
                 chunk
                     .iter()
-                    .flat_map(|repo| scan_git_repo(repo))
+                    .take_while(|_| !stop.is_some_and(StopFlag::is_set))
+                    .flat_map(|repo| scan_git_repo(repo, timeout))
                     .collect::<Vec<_>>()
             })
         })
@@ -185,13 +686,25 @@ pub fn traverse(
             on_found(results.len());
         }
     }
+}
 
-    results
+fn modified_before(dir: &Path, cutoff: SystemTime) -> bool {
+    fs::metadata(dir)
+        .and_then(|m| m.modified())
+        .is_ok_and(|mtime| mtime < cutoff)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    fn traverse(
+        search_paths: &[String],
+        options: &WalkOptions,
+        on_found: &dyn Fn(usize),
+    ) -> Vec<PathBuf> {
+        super::traverse(search_paths, options, on_found).0
+    }
     use tempfile::TempDir;
 
     #[test]
@@ -299,7 +812,7 @@ mod tests {
         fs::create_dir(repo.join("src")).unwrap();
         fs::write(repo.join("src/main.rs"), "fn main() {}").unwrap();
 
-        let results = scan_git_repo(repo);
+        let results = scan_git_repo(repo, None);
 
         assert_eq!(results.len(), 3);
         assert!(results.contains(&repo.join("node_modules")));
@@ -309,14 +822,123 @@ mod tests {
         assert!(!results.contains(&repo.join("debug.log")));
     }
 
+    #[test]
+    fn scan_git_repo_keeps_non_builtin_names_and_only_topmost_dir() {
+        let dir = TempDir::new().unwrap();
+        let repo = dir.path();
+
+        Command::new("git").arg("init").arg(repo).output().unwrap();
+        fs::write(repo.join(".gitignore"), "generated/\n.artifacts/\n").unwrap();
+        fs::create_dir_all(repo.join("generated/api/v1")).unwrap();
+        fs::write(repo.join("generated/api/v1/client.ts"), "").unwrap();
+        fs::create_dir(repo.join(".artifacts")).unwrap();
+        fs::write(repo.join(".artifacts/report.xml"), "").unwrap();
+
+        let mut results = scan_git_repo(repo, None);
+        results.sort();
+
+        assert_eq!(
+            results,
+            vec![repo.join(".artifacts"), repo.join("generated")]
+        );
+    }
+
+    #[test]
+    fn parse_submodule_paths_joins_paths_to_repo() {
+        let repo = Path::new("/Users/dev/project");
+        let output = "submodule.libs/ui.path\nlibs/ui\0submodule.my tool.path\nvendor/my tool\0";
+
+        assert_eq!(
+            parse_submodule_paths(repo, output),
+            vec![repo.join("libs/ui"), repo.join("vendor/my tool")]
+        );
+    }
+
+    #[test]
+    fn scan_git_repo_recurses_into_submodules() {
+        let dir = TempDir::new().unwrap();
+        let git = |cwd: &Path, args: &[&str]| {
+            let status = Command::new("git")
+                .current_dir(cwd)
+                .args([
+                    "-c",
+                    "user.name=veiled",
+                    "-c",
+                    "user.email=veiled@example.com",
+                ])
+                .args(["-c", "protocol.file.allow=always"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {args:?} failed");
+        };
+        let child = dir.path().join("child");
+        fs::create_dir(&child).unwrap();
+        git(&child, &["init", "-q"]);
+        fs::write(child.join(".gitignore"), "node_modules/\n").unwrap();
+        git(&child, &["add", ".gitignore"]);
+        git(&child, &["commit", "-q", "-m", "init"]);
+
+        let parent = dir.path().join("parent");
+        fs::create_dir(&parent).unwrap();
+        git(&parent, &["init", "-q"]);
+        fs::write(parent.join(".gitignore"), "target/\n").unwrap();
+        git(
+            &parent,
+            &["submodule", "add", "-q", "../child", "libs/child"],
+        );
+        fs::create_dir_all(parent.join("target")).unwrap();
+        fs::write(parent.join("target/out"), "bin").unwrap();
+        fs::create_dir_all(parent.join("libs/child/node_modules")).unwrap();
+        fs::write(parent.join("libs/child/node_modules/pkg.json"), "{}").unwrap();
+
+        let mut results = scan_git_repo(&parent, None);
+        results.sort();
+
+        assert_eq!(
+            results,
+            vec![
+                parent.join("libs/child/node_modules"),
+                parent.join("target")
+            ]
+        );
+    }
+
     #[test]
     fn scan_git_repo_returns_empty_for_non_git_dir() {
         let dir = TempDir::new().unwrap();
-        let results = scan_git_repo(dir.path());
+        let results = scan_git_repo(dir.path(), Some(Duration::from_secs(10)));
 
         assert!(results.is_empty());
     }
 
+    #[test]
+    fn output_with_timeout_kills_slow_command() {
+        let started = Instant::now();
+        let output = output_with_timeout(
+            Command::new("sleep").arg("5"),
+            Some(Duration::from_millis(100)),
+        )
+        .unwrap();
+
+        assert!(output.is_none());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn output_with_timeout_returns_output_of_fast_command() {
+        let output = output_with_timeout(
+            Command::new("echo").arg("done"),
+            Some(Duration::from_secs(10)),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"done\n");
+    }
+
     #[test]
     fn traverse_finds_builtin_in_git_repo() {
         let dir = TempDir::new().unwrap();
@@ -328,11 +950,207 @@ mod tests {
         fs::create_dir(repo.join("node_modules")).unwrap();
         fs::write(repo.join("node_modules/pkg.json"), "{}").unwrap();
 
-        let results = traverse(&[dir.path().to_string_lossy().into_owned()], &[], &|_| {});
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions::default(),
+            &|_| {},
+        );
 
         assert!(results.iter().any(|p| p.ends_with("node_modules")));
     }
 
+    #[test]
+    fn git_chunk_size_spreads_repos_over_threads() {
+        assert_eq!(git_chunk_size(1, 1), 1);
+        assert_eq!(git_chunk_size(1, 8), 1);
+        assert_eq!(git_chunk_size(0, 4), 1);
+        assert_eq!(git_chunk_size(16, 8), 2);
+        assert_eq!(git_chunk_size(17, 8), 3);
+        assert_eq!(git_chunk_size(5, 0), 5);
+        // Never more chunks than threads.
+        assert!(10usize.div_ceil(git_chunk_size(10, 3)) <= 3);
+    }
+
+    #[test]
+    fn traverse_results_do_not_depend_on_scan_concurrency() {
+        let dir = TempDir::new().unwrap();
+        for i in 0..5 {
+            let repo = dir.path().join(format!("repo{i}"));
+            fs::create_dir(&repo).unwrap();
+            Command::new("git").arg("init").arg(&repo).output().unwrap();
+            fs::write(repo.join(".gitignore"), "node_modules/\ntarget/\n").unwrap();
+            fs::create_dir(repo.join("node_modules")).unwrap();
+            fs::create_dir(repo.join("target")).unwrap();
+        }
+        let search = [dir.path().to_string_lossy().into_owned()];
+        let scan = |scan_concurrency| {
+            let mut results = traverse(
+                &search,
+                &WalkOptions {
+                    scan_concurrency,
+                    ..WalkOptions::default()
+                },
+                &|_| {},
+            );
+            results.sort();
+            results.dedup();
+            results
+        };
+
+        let baseline = scan(Some(1));
+        assert_eq!(baseline.len(), 10);
+        for threads in [Some(2), Some(3), Some(16), None] {
+            assert_eq!(scan(threads), baseline);
+        }
+    }
+
+    #[test]
+    fn traverse_reports_progress_from_walk_and_git_repos() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("plain/node_modules")).unwrap();
+        let repo = dir.path().join("repo");
+        fs::create_dir(&repo).unwrap();
+        Command::new("git").arg("init").arg(&repo).output().unwrap();
+        fs::write(repo.join(".gitignore"), "scratch-output/\n").unwrap();
+        fs::create_dir(repo.join("scratch-output")).unwrap();
+        fs::write(repo.join("scratch-output/data"), "x").unwrap();
+
+        let counts = std::cell::RefCell::new(Vec::new());
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions::default(),
+            &|found| counts.borrow_mut().push(found),
+        );
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(*counts.borrow(), vec![1, 2]);
+    }
+
+    #[test]
+    fn parallel_walk_matches_serial_walk() {
+        let dir = TempDir::new().unwrap();
+        for project in 0..6 {
+            for module in 0..4 {
+                let base = dir.path().join(format!("p{project}/m{module}"));
+                fs::create_dir_all(base.join("node_modules")).unwrap();
+                fs::create_dir_all(base.join("src/build")).unwrap();
+                fs::write(base.join("src/Makefile"), "").unwrap();
+                fs::create_dir_all(base.join("docs/assets")).unwrap();
+            }
+            let repo = dir.path().join(format!("p{project}"));
+            fs::create_dir(repo.join(".git")).unwrap();
+        }
+        fs::create_dir_all(dir.path().join("notes/raw")).unwrap();
+        fs::write(dir.path().join("notes/raw").join(NOBACKUP_MARKER), "").unwrap();
+        let search = [dir.path().to_string_lossy().into_owned()];
+        let options = WalkOptions::default();
+
+        let sorted = |(mut found, mut repos): (Vec<PathBuf>, Vec<PathBuf>)| {
+            found.sort();
+            repos.sort();
+            (found, repos)
+        };
+        let serial_counters = WalkCounters::default();
+        let parallel_counters = WalkCounters::default();
+        let serial = sorted(walk(&search, &options, 1, &serial_counters, &|_| {}));
+        let parallel = sorted(walk(&search, &options, 8, &parallel_counters, &|_| {}));
+
+        assert_eq!(serial.0.len(), 49);
+        assert_eq!(serial.1.len(), 6);
+        assert_eq!(serial, parallel);
+        assert_eq!(serial_counters.stats(0), parallel_counters.stats(0));
+    }
+
+    #[test]
+    fn traverse_counts_visited_and_skipped_dirs() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("app/node_modules")).unwrap();
+        fs::create_dir_all(dir.path().join("app/src")).unwrap();
+        fs::create_dir(dir.path().join(".local")).unwrap();
+        fs::create_dir(dir.path().join("archive")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("app"), dir.path().join("app-link")).unwrap();
+        let repo = dir.path().join("repo");
+        fs::create_dir(&repo).unwrap();
+        Command::new("git").arg("init").arg(&repo).output().unwrap();
+
+        let ignore = [dir.path().join("archive").to_string_lossy().into_owned()];
+        let options = WalkOptions {
+            ignore_paths: &ignore,
+            ..WalkOptions::default()
+        };
+        let (results, stats) = super::traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &options,
+            &|_| {},
+        );
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            stats,
+            ScanStats {
+                // The search path, app, app/src and repo.
+                dirs_visited: 4,
+                skipped_ignored: 1,
+                skipped_symlinks: 1,
+                // .local and repo/.git.
+                skipped_hidden: 2,
+                git_repos: 1,
+                elapsed_ms: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn traverse_does_not_match_ignored_names() {
+        let dir = TempDir::new().unwrap();
+        let project = dir.path().join("app");
+        fs::create_dir_all(project.join("vendor")).unwrap();
+        fs::create_dir_all(project.join("node_modules")).unwrap();
+
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions {
+                ignore_names: &["vendor".to_string()],
+                ..WalkOptions::default()
+            },
+            &|_| {},
+        );
+
+        assert_eq!(results, vec![project.join("node_modules")]);
+    }
+
+    #[test]
+    fn traverse_skips_hidden_non_builtin_dirs_by_default() {
+        let dir = TempDir::new().unwrap();
+        let hidden = dir.path().join(".local-tool/node_modules");
+        fs::create_dir_all(&hidden).unwrap();
+        fs::create_dir_all(dir.path().join("app/.venv")).unwrap();
+        let search = [dir.path().to_string_lossy().into_owned()];
+
+        let results = traverse(&search, &WalkOptions::default(), &|_| {});
+
+        assert_eq!(results, vec![dir.path().join("app/.venv")]);
+    }
+
+    #[test]
+    fn traverse_scans_hidden_dirs_when_included() {
+        let dir = TempDir::new().unwrap();
+        let hidden = dir.path().join(".local-tool/node_modules");
+        fs::create_dir_all(&hidden).unwrap();
+        let search = [dir.path().to_string_lossy().into_owned()];
+
+        let results = traverse(
+            &search,
+            &WalkOptions {
+                include_hidden: true,
+                ..WalkOptions::default()
+            },
+            &|_| {},
+        );
+
+        assert_eq!(results, vec![hidden]);
+    }
+
     #[test]
     fn traverse_finds_builtin_in_non_git_dir() {
         let dir = TempDir::new().unwrap();
@@ -340,11 +1158,62 @@ mod tests {
         fs::create_dir(&project).unwrap();
         fs::create_dir(project.join("node_modules")).unwrap();
 
-        let results = traverse(&[dir.path().to_string_lossy().into_owned()], &[], &|_| {});
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions::default(),
+            &|_| {},
+        );
 
         assert!(results.iter().any(|p| p.ends_with("node_modules")));
     }
 
+    fn nested_builtins() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("node_modules")).unwrap();
+        fs::create_dir_all(dir.path().join("web/node_modules")).unwrap();
+        fs::create_dir_all(dir.path().join("web/api/.venv")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn traverse_matches_builtins_at_any_depth_by_default() {
+        let dir = nested_builtins();
+
+        let mut results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions::default(),
+            &|_| {},
+        );
+        results.sort();
+
+        assert_eq!(
+            results,
+            vec![
+                dir.path().join("node_modules"),
+                dir.path().join("web/api/.venv"),
+                dir.path().join("web/node_modules"),
+            ]
+        );
+    }
+
+    #[test]
+    fn traverse_root_only_matches_direct_children_of_search_path() {
+        let dir = nested_builtins();
+
+        let (results, stats) = super::traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions {
+                match_depth: MatchDepth::RootOnly,
+                ..WalkOptions::default()
+            },
+            &|_| {},
+        );
+
+        assert_eq!(results, vec![dir.path().join("node_modules")]);
+        // The deeper node_modules is not walked into either.
+        assert_eq!(stats.dirs_visited, 3);
+    }
+
     #[test]
     fn traverse_skips_ignore_paths() {
         let dir = TempDir::new().unwrap();
@@ -354,16 +1223,51 @@ mod tests {
 
         let results = traverse(
             &[dir.path().to_string_lossy().into_owned()],
-            &[ignored.to_string_lossy().into_owned()],
+            &WalkOptions {
+                ignore_paths: &[ignored.to_string_lossy().into_owned()],
+                ..WalkOptions::default()
+            },
             &|_| {},
         );
 
         assert!(results.is_empty());
     }
 
+    #[test]
+    fn traverse_skips_ignored_builtin_and_gitignored_dirs() {
+        let dir = TempDir::new().unwrap();
+        let app = dir.path().join("app");
+        fs::create_dir_all(app.join("node_modules")).unwrap();
+        let repo = dir.path().join("repo");
+        fs::create_dir(&repo).unwrap();
+        Command::new("git").arg("init").arg(&repo).output().unwrap();
+        fs::write(repo.join(".gitignore"), "generated/\n").unwrap();
+        fs::create_dir(repo.join("generated")).unwrap();
+        fs::write(repo.join("generated/out.ts"), "").unwrap();
+
+        let ignore = [
+            app.join("node_modules").to_string_lossy().into_owned(),
+            repo.join("generated").to_string_lossy().into_owned(),
+        ];
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions {
+                ignore_paths: &ignore,
+                ..WalkOptions::default()
+            },
+            &|_| {},
+        );
+
+        assert!(results.is_empty(), "{results:?}");
+    }
+
     #[test]
     fn traverse_skips_nonexistent_search_path() {
-        let results = traverse(&["/nonexistent/search/path".to_string()], &[], &|_| {});
+        let results = traverse(
+            &["/nonexistent/search/path".to_string()],
+            &WalkOptions::default(),
+            &|_| {},
+        );
 
         assert!(results.is_empty());
     }
@@ -378,12 +1282,123 @@ mod tests {
 
         std::os::unix::fs::symlink(&project, project.join("link")).unwrap();
 
-        let results = traverse(&[dir.path().to_string_lossy().into_owned()], &[], &|_| {});
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions::default(),
+            &|_| {},
+        );
 
         assert_eq!(results.len(), 1);
         assert!(results[0].ends_with("node_modules"));
     }
 
+    fn backdate(dir: &Path, age: std::time::Duration) {
+        fs::File::open(dir)
+            .unwrap()
+            .set_modified(SystemTime::now() - age)
+            .unwrap();
+    }
+
+    #[test]
+    fn traverse_with_cutoff_skips_stale_subtrees() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("fresh/node_modules")).unwrap();
+        fs::create_dir_all(dir.path().join("stale/node_modules")).unwrap();
+        backdate(
+            &dir.path().join("stale"),
+            std::time::Duration::from_hours(48),
+        );
+        let search = [dir.path().to_string_lossy().into_owned()];
+        let cutoff = SystemTime::now() - std::time::Duration::from_hours(1);
+
+        let recent = traverse(
+            &search,
+            &WalkOptions {
+                modified_since: Some(cutoff),
+                ..WalkOptions::default()
+            },
+            &|_| {},
+        );
+        let mut all = traverse(&search, &WalkOptions::default(), &|_| {});
+        all.sort();
+
+        assert_eq!(recent, vec![dir.path().join("fresh/node_modules")]);
+        assert_eq!(
+            all,
+            vec![
+                dir.path().join("fresh/node_modules"),
+                dir.path().join("stale/node_modules"),
+            ]
+        );
+    }
+
+    #[test]
+    fn traverse_with_cutoff_still_walks_stale_search_root() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("app/target")).unwrap();
+        fs::write(dir.path().join("app/Cargo.toml"), "").unwrap();
+        backdate(dir.path(), std::time::Duration::from_hours(48));
+        let cutoff = SystemTime::now() - std::time::Duration::from_hours(1);
+
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions {
+                modified_since: Some(cutoff),
+                ..WalkOptions::default()
+            },
+            &|_| {},
+        );
+
+        assert_eq!(results, vec![dir.path().join("app/target")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn traverse_follows_symlinked_projects_only_when_enabled() {
+        let real = TempDir::new().unwrap();
+        fs::create_dir_all(real.path().join("app/node_modules")).unwrap();
+
+        let root = TempDir::new().unwrap();
+        std::os::unix::fs::symlink(real.path(), root.path().join("work")).unwrap();
+        let search = [root.path().to_string_lossy().into_owned()];
+
+        let skipped = traverse(&search, &WalkOptions::default(), &|_| {});
+        let followed = traverse(
+            &search,
+            &WalkOptions {
+                follow_symlinks: true,
+                ..WalkOptions::default()
+            },
+            &|_| {},
+        );
+
+        assert!(skipped.is_empty());
+        assert_eq!(followed, vec![root.path().join("work/app/node_modules")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn traverse_following_symlinks_terminates_on_loops() {
+        let dir = TempDir::new().unwrap();
+        let project = dir.path().join("project");
+        fs::create_dir(&project).unwrap();
+        fs::create_dir(project.join("node_modules")).unwrap();
+
+        std::os::unix::fs::symlink(&project, project.join("link")).unwrap();
+        std::os::unix::fs::symlink(dir.path(), project.join("up")).unwrap();
+
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions {
+                follow_symlinks: true,
+                ..WalkOptions::default()
+            },
+            &|_| {},
+        );
+
+        assert_eq!(results, vec![project.join("node_modules")]);
+    }
+
     #[test]
     fn traverse_does_not_descend_into_builtin_dirs() {
         let dir = TempDir::new().unwrap();
@@ -394,12 +1409,277 @@ mod tests {
         // nested builtin inside node_modules should not appear separately
         fs::create_dir(nm.join("target")).unwrap();
 
-        let results = traverse(&[dir.path().to_string_lossy().into_owned()], &[], &|_| {});
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions::default(),
+            &|_| {},
+        );
 
         assert_eq!(results.len(), 1);
         assert!(results[0].ends_with("node_modules"));
     }
 
+    #[test]
+    fn traverse_finds_custom_builtin_in_git_repo() {
+        let dir = TempDir::new().unwrap();
+        let repo = dir.path().join("project");
+        fs::create_dir(&repo).unwrap();
+
+        Command::new("git").arg("init").arg(&repo).output().unwrap();
+        fs::create_dir(repo.join(".bazel-out")).unwrap();
+
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions {
+                custom_builtins: &[".bazel-out".to_string()],
+                ..WalkOptions::default()
+            },
+            &|_| {},
+        );
+
+        assert!(results.contains(&repo.join(".bazel-out")));
+    }
+
+    #[test]
+    fn traverse_finds_custom_builtin_in_non_git_dir() {
+        let dir = TempDir::new().unwrap();
+        let project = dir.path().join("project");
+        fs::create_dir(&project).unwrap();
+        fs::create_dir(project.join(".cargo-cache")).unwrap();
+
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions {
+                custom_builtins: &[".cargo-cache".to_string()],
+                ..WalkOptions::default()
+            },
+            &|_| {},
+        );
+
+        assert_eq!(results, vec![project.join(".cargo-cache")]);
+    }
+
+    #[test]
+    fn traverse_matches_generic_builtin_only_beside_manifest() {
+        let dir = TempDir::new().unwrap();
+        let app = dir.path().join("app");
+        let photos = dir.path().join("photos");
+        fs::create_dir_all(app.join("build")).unwrap();
+        fs::write(app.join("package.json"), "{}").unwrap();
+        fs::create_dir_all(photos.join("build/2024")).unwrap();
+
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions::default(),
+            &|_| {},
+        );
+
+        assert_eq!(results, vec![app.join("build")]);
+    }
+
+    #[test]
+    fn traverse_matches_builtin_globs_without_descending() {
+        let dir = TempDir::new().unwrap();
+        let project = dir.path().join("project");
+        fs::create_dir_all(project.join("cmake-build-debug/deps/node_modules")).unwrap();
+        fs::create_dir(project.join("cmake-scripts")).unwrap();
+
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions {
+                builtin_globs: &["cmake-build-*".to_string()],
+                ..WalkOptions::default()
+            },
+            &|_| {},
+        );
+
+        assert_eq!(results, vec![project.join("cmake-build-debug")]);
+    }
+
+    #[test]
+    fn traverse_returns_nothing_once_a_stop_is_requested() {
+        static STOP: StopFlag = StopFlag::new();
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("app/node_modules")).unwrap();
+        let repo = dir.path().join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        STOP.request();
+
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions {
+                stop: Some(&STOP),
+                ..WalkOptions::default()
+            },
+            &|_| {},
+        );
+
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn traverse_matches_file_globs() {
+        let dir = TempDir::new().unwrap();
+        let project = dir.path().join("project");
+        fs::create_dir_all(project.join("vm")).unwrap();
+        fs::write(project.join("vm/ubuntu.iso"), b"iso").unwrap();
+        fs::write(project.join("notes.txt"), b"notes").unwrap();
+        fs::create_dir(project.join("images.iso")).unwrap();
+
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions {
+                file_globs: &["*.iso".to_string()],
+                ..WalkOptions::default()
+            },
+            &|_| {},
+        );
+
+        assert_eq!(results, vec![project.join("vm/ubuntu.iso")]);
+    }
+
+    #[test]
+    fn traverse_skips_files_without_file_globs() {
+        let dir = TempDir::new().unwrap();
+        let project = dir.path().join("project");
+        fs::create_dir_all(project.join("node_modules")).unwrap();
+        fs::write(project.join("package.json"), b"{}").unwrap();
+        fs::write(project.join("ubuntu.iso"), b"iso").unwrap();
+
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions::default(),
+            &|_| {},
+        );
+
+        assert_eq!(results, vec![project.join("node_modules")]);
+    }
+
+    #[test]
+    fn traverse_does_not_match_ignored_files() {
+        let dir = TempDir::new().unwrap();
+        let image = dir.path().join("ubuntu.iso");
+        fs::write(&image, b"iso").unwrap();
+        let ignore = [image.to_string_lossy().into_owned()];
+
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions {
+                ignore_paths: &ignore,
+                file_globs: &["*.iso".to_string()],
+                ..WalkOptions::default()
+            },
+            &|_| {},
+        );
+
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn traverse_ignores_custom_builtin_with_different_case() {
+        let dir = TempDir::new().unwrap();
+        let project = dir.path().join("project");
+        fs::create_dir(&project).unwrap();
+        fs::create_dir(project.join(".Cargo-Cache")).unwrap();
+
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions {
+                custom_builtins: &[".cargo-cache".to_string()],
+                ..WalkOptions::default()
+            },
+            &|_| {},
+        );
+
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn traverse_finds_dir_with_nobackup_marker() {
+        let dir = TempDir::new().unwrap();
+        let assets = dir.path().join("project/generated-assets");
+        fs::create_dir_all(&assets).unwrap();
+        fs::write(assets.join(NOBACKUP_MARKER), "").unwrap();
+        fs::create_dir(assets.join("node_modules")).unwrap();
+
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions::default(),
+            &|_| {},
+        );
+
+        assert_eq!(results, vec![assets]);
+    }
+
+    #[test]
+    fn traverse_ignores_nobackup_directory_marker() {
+        let dir = TempDir::new().unwrap();
+        let assets = dir.path().join("project/assets");
+        fs::create_dir_all(assets.join(NOBACKUP_MARKER)).unwrap();
+
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions::default(),
+            &|_| {},
+        );
+
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn traverse_respects_max_depth() {
+        let dir = TempDir::new().unwrap();
+        let shallow = dir.path().join("a/node_modules");
+        let deep = dir.path().join("a/b/c/target");
+        fs::create_dir_all(&shallow).unwrap();
+        fs::create_dir_all(&deep).unwrap();
+
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions {
+                max_depth: Some(2),
+                ..WalkOptions::default()
+            },
+            &|_| {},
+        );
+
+        assert_eq!(results, vec![shallow]);
+    }
+
+    #[test]
+    fn traverse_records_builtin_at_depth_limit() {
+        let dir = TempDir::new().unwrap();
+        let at_limit = dir.path().join("a/b/node_modules");
+        fs::create_dir_all(at_limit.join("nested/target")).unwrap();
+
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions {
+                max_depth: Some(3),
+                ..WalkOptions::default()
+            },
+            &|_| {},
+        );
+
+        assert_eq!(results, vec![at_limit]);
+    }
+
+    #[test]
+    fn traverse_without_max_depth_is_unbounded() {
+        let dir = TempDir::new().unwrap();
+        let deep = dir.path().join("a/b/c/d/e/f/target");
+        fs::create_dir_all(&deep).unwrap();
+        fs::write(dir.path().join("a/b/c/d/e/f/Cargo.toml"), "").unwrap();
+
+        let results = traverse(
+            &[dir.path().to_string_lossy().into_owned()],
+            &WalkOptions::default(),
+            &|_| {},
+        );
+
+        assert_eq!(results, vec![deep]);
+    }
+
     fn test_config(
         search_paths: Vec<String>,
         ignore_paths: Vec<String>,
@@ -410,6 +1690,7 @@ mod tests {
             ignore_paths,
             extra_exclusions,
             auto_update: false,
+            ..Config::default()
         }
     }
 
@@ -426,11 +1707,26 @@ mod tests {
             vec![],
         );
 
-        let results = collect_paths(&config, &|_| {});
+        let results = collect_paths(&config, None, &|_| {}).0;
 
         assert!(results.iter().any(|p| p.ends_with("node_modules")));
     }
 
+    #[test]
+    fn collect_paths_skips_search_path_inside_ignore_path() {
+        let dir = TempDir::new().unwrap();
+        let nested = dir.path().join("archive/project");
+        fs::create_dir_all(nested.join("node_modules")).unwrap();
+
+        let config = test_config(
+            vec![nested.to_string_lossy().into_owned()],
+            vec![dir.path().join("archive").to_string_lossy().into_owned()],
+            vec![],
+        );
+
+        assert!(collect_paths(&config, None, &|_| {}).0.is_empty());
+    }
+
     #[test]
     fn collect_paths_includes_extra_exclusions() {
         let dir = TempDir::new().unwrap();
@@ -439,7 +1735,7 @@ mod tests {
 
         let config = test_config(vec![], vec![], vec![extra.to_string_lossy().into_owned()]);
 
-        let results = collect_paths(&config, &|_| {});
+        let results = collect_paths(&config, None, &|_| {}).0;
 
         assert_eq!(results.len(), 1);
         assert_eq!(results[0], extra);
@@ -449,7 +1745,7 @@ mod tests {
     fn collect_paths_skips_nonexistent_extra_exclusions() {
         let config = test_config(vec![], vec![], vec!["/nonexistent/extra/path".to_string()]);
 
-        let results = collect_paths(&config, &|_| {});
+        let results = collect_paths(&config, None, &|_| {}).0;
 
         assert!(results.is_empty());
     }
@@ -468,7 +1764,7 @@ mod tests {
             vec![nm.to_string_lossy().into_owned()],
         );
 
-        let results = collect_paths(&config, &|_| {});
+        let results = collect_paths(&config, None, &|_| {}).0;
 
         assert_eq!(
             results
@@ -494,7 +1790,7 @@ mod tests {
             vec![],
         );
 
-        let results = collect_paths(&config, &|_| {});
+        let results = collect_paths(&config, None, &|_| {}).0;
         let sorted: Vec<_> = {
             let mut s = results.clone();
             s.sort();
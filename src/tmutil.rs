@@ -1,5 +1,25 @@
+use std::ffi::OsString;
+use std::io;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// How exclusions are recorded: `Sticky` tags the directory itself with an
+/// xattr, which is lost when the directory is deleted and recreated; `Fixed`
+/// registers the path with `tmutil addexclusion -p` so it survives
+/// recreation, but needs root.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExclusionMode {
+    #[default]
+    Sticky,
+    Fixed,
+}
 
 const XATTR_KEY: &str = "com.apple.metadata:com_apple_backup_excludeItem";
 
@@ -12,6 +32,38 @@ const XATTR_VALUE: [u8; 61] = [
     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1C,
 ];
 
+/// Number of extra attempts made after a transient xattr or tmutil failure.
+pub const MAX_RETRIES: u32 = 3;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+fn is_transient(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::Interrupted
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::ResourceBusy
+    )
+}
+
+/// Runs `op`, retrying transient failures up to `MAX_RETRIES` times with
+/// exponential backoff (100ms, 200ms, 400ms). `sleep` is injected so tests
+/// can run the loop without waiting.
+fn with_retry<T>(mut op: impl FnMut() -> io::Result<T>, sleep: impl Fn(Duration)) -> io::Result<T> {
+    let mut delay = INITIAL_BACKOFF;
+    let mut attempt = 0;
+    loop {
+        match op() {
+            Err(e) if attempt < MAX_RETRIES && is_transient(&e) => {
+                sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
 pub fn check_access() -> Result<(), String> {
     let output = Command::new("tmutil")
         .arg("isexcluded")
@@ -27,61 +79,158 @@ pub fn check_access() -> Result<(), String> {
     }
 }
 
-pub fn add_exclusion(path: &Path) -> Result<(), String> {
-    xattr::set(path, XATTR_KEY, &XATTR_VALUE)
-        .map_err(|e| format!("failed to set exclusion on {}: {e}", path.display()))
+/// Permission errors usually mean Full Disk Access is missing, so they get
+/// their own category; everything else is a plain tmutil failure.
+fn exclusion_error(err: &io::Error, message: String) -> Error {
+    if err.kind() == io::ErrorKind::PermissionDenied {
+        Error::Access(message)
+    } else {
+        Error::Tmutil(message)
+    }
 }
 
-pub fn add_exclusions(paths: &[PathBuf]) -> Result<(), String> {
-    let mut errors = Vec::new();
-    for path in paths {
-        if let Err(e) = add_exclusion(path) {
-            errors.push(e);
-        }
-    }
+/// Runs `op` on every path, joining all failures into one error. Each call
+/// is its own xattr syscall or tmutil run, so batches need no chunking.
+fn apply_each(paths: &[PathBuf], op: impl Fn(&Path) -> Result<(), Error>) -> Result<(), Error> {
+    let errors: Vec<Error> = paths.iter().filter_map(|path| op(path).err()).collect();
     if errors.is_empty() {
-        Ok(())
+        return Ok(());
+    }
+
+    let message = errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ");
+    if errors.iter().all(|e| matches!(e, Error::Access(_))) {
+        Err(Error::Access(message))
     } else {
-        Err(errors.join("; "))
+        Err(Error::Tmutil(message))
     }
 }
 
-pub fn remove_exclusion(path: &Path) -> Result<(), String> {
-    match xattr::remove(path, XATTR_KEY) {
-        Ok(()) => Ok(()),
-        Err(e) if e.raw_os_error() == Some(93) => Ok(()),
-        Err(e) => Err(format!(
-            "failed to remove exclusion from {}: {e}",
-            path.display()
-        )),
+/// Arguments for `tmutil <action> [-p] <path>`; `-p` selects a fixed-path
+/// exclusion.
+fn tmutil_args(action: &str, path: &Path, mode: ExclusionMode) -> Vec<OsString> {
+    let mut args = vec![OsString::from(action)];
+    if mode == ExclusionMode::Fixed {
+        args.push(OsString::from("-p"));
     }
+    args.push(path.as_os_str().to_owned());
+    args
 }
 
-pub fn remove_exclusions(paths: &[PathBuf]) -> Result<(), String> {
-    let mut errors = Vec::new();
-    for path in paths {
-        if let Err(e) = remove_exclusion(path) {
-            errors.push(e);
-        }
+fn run_tmutil(args: &[OsString]) -> io::Result<String> {
+    let output = Command::new("tmutil").args(args).output()?;
+    if output.status.success() {
+        return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
     }
-    if errors.is_empty() {
-        Ok(())
+
+    Err(tmutil_failure(
+        String::from_utf8_lossy(&output.stderr).trim().to_string(),
+    ))
+}
+
+/// Classifies a non-zero tmutil exit by its stderr. A missing path or a
+/// privilege error will fail the same way again; anything else is reported
+/// as `ResourceBusy` so `with_retry` treats it as transient.
+fn tmutil_failure(stderr: String) -> io::Error {
+    let kind = if stderr.contains("root") || stderr.contains("privilege") {
+        io::ErrorKind::PermissionDenied
+    } else if stderr.contains("No such file") || stderr.contains("does not exist") {
+        io::ErrorKind::NotFound
     } else {
-        Err(errors.join("; "))
+        io::ErrorKind::ResourceBusy
+    };
+    io::Error::new(kind, stderr)
+}
+
+/// `run_tmutil` with transient failures retried.
+fn run_tmutil_with_retry(args: &[OsString]) -> io::Result<String> {
+    with_retry(|| run_tmutil(args), thread::sleep)
+}
+
+fn remove_xattr(path: &Path) -> io::Result<()> {
+    match with_retry(|| xattr::remove(path, XATTR_KEY), thread::sleep) {
+        Err(e) if e.raw_os_error() == Some(93) => Ok(()),
+        result => result,
     }
 }
 
-pub fn are_excluded(paths: &[PathBuf]) -> Vec<bool> {
-    paths.iter().map(|p| is_excluded(p)).collect()
+pub fn add_exclusion(path: &Path, mode: ExclusionMode) -> Result<(), Error> {
+    let result = match mode {
+        ExclusionMode::Sticky => {
+            with_retry(|| xattr::set(path, XATTR_KEY, &XATTR_VALUE), thread::sleep)
+        }
+        ExclusionMode::Fixed => {
+            run_tmutil_with_retry(&tmutil_args("addexclusion", path, mode)).map(drop)
+        }
+    };
+    result.map_err(|e| {
+        exclusion_error(
+            &e,
+            format!("failed to set exclusion on {}: {e}", path.display()),
+        )
+    })
+}
+
+pub fn add_exclusions(paths: &[PathBuf], mode: ExclusionMode) -> Result<(), Error> {
+    apply_each(paths, |path| add_exclusion(path, mode))
+}
+
+/// Removes the exclusion recorded for `mode`. In fixed mode any sticky
+/// xattr left over from before the mode was switched is cleared as well.
+pub fn remove_exclusion(path: &Path, mode: ExclusionMode) -> Result<(), Error> {
+    let result = match mode {
+        ExclusionMode::Sticky => remove_xattr(path),
+        ExclusionMode::Fixed => run_tmutil_with_retry(&tmutil_args("removeexclusion", path, mode))
+            .and_then(|_| remove_xattr(path)),
+    };
+    result.map_err(|e| {
+        exclusion_error(
+            &e,
+            format!("failed to remove exclusion from {}: {e}", path.display()),
+        )
+    })
+}
+
+pub fn remove_exclusions(paths: &[PathBuf], mode: ExclusionMode) -> Result<(), Error> {
+    apply_each(paths, |path| remove_exclusion(path, mode))
+}
+
+/// Whether each path is excluded. Fixed-path exclusions leave no xattr, so
+/// in fixed mode paths without one are checked with `tmutil isexcluded`.
+pub fn are_excluded(paths: &[PathBuf], mode: ExclusionMode) -> Vec<bool> {
+    paths
+        .iter()
+        .map(|p| is_excluded(p) || (mode == ExclusionMode::Fixed && is_fixed_excluded(p)))
+        .collect()
 }
 
-fn is_excluded(path: &Path) -> bool {
+pub fn is_excluded(path: &Path) -> bool {
     xattr::get(path, XATTR_KEY)
         .ok()
         .flatten()
         .is_some_and(|val| val == XATTR_VALUE)
 }
 
+fn is_fixed_excluded(path: &Path) -> bool {
+    run_tmutil(&tmutil_args("isexcluded", path, ExclusionMode::Sticky))
+        .is_ok_and(|out| parse_isexcluded(&out, path))
+}
+
+/// Reads `tmutil isexcluded` output for a single `path`. The verdict only
+/// counts when the rest of the output is exactly that path, so a name
+/// containing newlines cannot be mistaken for a different result.
+fn parse_isexcluded(output: &str, path: &Path) -> bool {
+    let Some(rest) = output.trim_start().strip_prefix("[Excluded]") else {
+        return false;
+    };
+    let reported = rest.trim_start_matches([' ', '\t']);
+    let reported = reported.strip_suffix('\n').unwrap_or(reported);
+    Path::new(reported) == path
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -93,6 +242,203 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn are_excluded_returns_one_result_per_path() {
+        let paths: Vec<PathBuf> = (0..500)
+            .map(|i| PathBuf::from(format!("/nonexistent/project-{i}/node_modules")))
+            .collect();
+
+        let results = are_excluded(&paths, ExclusionMode::Sticky);
+
+        assert_eq!(results.len(), 500);
+        assert!(results.iter().all(|excluded| !excluded));
+    }
+
+    #[test]
+    fn are_excluded_keeps_order_for_unusual_names() {
+        let paths: Vec<PathBuf> = [
+            "/nonexistent/line\nbreak/node_modules",
+            "/nonexistent/[Excluded]    /target",
+            "/nonexistent/tab\tand space/.venv",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+
+        assert_eq!(
+            are_excluded(&paths, ExclusionMode::Sticky),
+            vec![false, false, false]
+        );
+    }
+
+    #[test]
+    fn parse_isexcluded_requires_the_queried_path() {
+        let path = Path::new("/Users/dev/odd\nname");
+
+        assert!(parse_isexcluded(
+            "[Excluded]    /Users/dev/odd\nname\n",
+            path
+        ));
+        assert!(!parse_isexcluded("[Excluded]    /Users/dev/odd\n", path));
+        assert!(!parse_isexcluded(
+            "[Included]    /Users/dev/odd\nname\n",
+            path
+        ));
+        assert!(!parse_isexcluded(
+            "[Excluded]    /Users/dev/other\n[Excluded]    /Users/dev/odd\nname\n",
+            path
+        ));
+    }
+
+    #[test]
+    fn remove_exclusions_reports_every_failure() {
+        let paths: Vec<PathBuf> = (0..500)
+            .map(|i| PathBuf::from(format!("/nonexistent/project-{i}/target")))
+            .collect();
+
+        let err = remove_exclusions(&paths, ExclusionMode::Sticky).unwrap_err();
+
+        assert_eq!(err.to_string().split("; ").count(), 500);
+        assert_eq!(err.exit_code(), crate::error::exit_code::TMUTIL);
+    }
+
+    #[test]
+    fn tmutil_args_pass_p_only_in_fixed_mode() {
+        let path = Path::new("/Users/dev/app/target");
+
+        assert_eq!(
+            tmutil_args("addexclusion", path, ExclusionMode::Sticky),
+            ["addexclusion", "/Users/dev/app/target"]
+        );
+        assert_eq!(
+            tmutil_args("addexclusion", path, ExclusionMode::Fixed),
+            ["addexclusion", "-p", "/Users/dev/app/target"]
+        );
+        assert_eq!(
+            tmutil_args("removeexclusion", path, ExclusionMode::Fixed),
+            ["removeexclusion", "-p", "/Users/dev/app/target"]
+        );
+    }
+
+    #[test]
+    fn exclusion_mode_defaults_to_sticky() {
+        assert_eq!(ExclusionMode::default(), ExclusionMode::Sticky);
+    }
+
+    #[test]
+    fn permission_denied_is_an_access_error() {
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        let other = io::Error::from(io::ErrorKind::Unsupported);
+
+        assert!(matches!(
+            exclusion_error(&denied, String::new()),
+            Error::Access(_)
+        ));
+        assert!(matches!(
+            exclusion_error(&other, String::new()),
+            Error::Tmutil(_)
+        ));
+    }
+
+    #[test]
+    fn with_retry_recovers_from_transient_errors() {
+        let mut calls = 0;
+        let delays = std::cell::RefCell::new(Vec::new());
+
+        let result = with_retry(
+            || {
+                calls += 1;
+                if calls < 3 {
+                    Err(io::Error::from(io::ErrorKind::Interrupted))
+                } else {
+                    Ok(calls)
+                }
+            },
+            |d| delays.borrow_mut().push(d),
+        );
+
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(
+            delays.into_inner(),
+            vec![Duration::from_millis(100), Duration::from_millis(200)]
+        );
+    }
+
+    #[test]
+    fn with_retry_gives_up_after_max_retries() {
+        let mut calls = 0;
+
+        let result: io::Result<()> = with_retry(
+            || {
+                calls += 1;
+                Err(io::Error::from(io::ErrorKind::ResourceBusy))
+            },
+            |_| {},
+        );
+
+        assert!(result.is_err());
+        assert_eq!(calls, MAX_RETRIES + 1);
+    }
+
+    #[test]
+    fn with_retry_does_not_retry_missing_file() {
+        let mut calls = 0;
+
+        let result: io::Result<()> = with_retry(
+            || {
+                calls += 1;
+                Err(io::Error::from(io::ErrorKind::NotFound))
+            },
+            |_| {},
+        );
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn with_retry_retries_transient_tmutil_exits() {
+        let mut calls = 0;
+
+        let result = with_retry(
+            || {
+                calls += 1;
+                if calls < 2 {
+                    Err(tmutil_failure(
+                        "Error (100002) while adding exclusion".into(),
+                    ))
+                } else {
+                    Ok(())
+                }
+            },
+            |_| {},
+        );
+
+        assert!(result.is_ok());
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn with_retry_does_not_retry_missing_path_or_privilege_tmutil_exits() {
+        for stderr in [
+            "/Users/dev/gone: No such file or directory",
+            "This operation requires root privileges.",
+        ] {
+            let mut calls = 0;
+
+            let result: io::Result<()> = with_retry(
+                || {
+                    calls += 1;
+                    Err(tmutil_failure(stderr.into()))
+                },
+                |_| {},
+            );
+
+            assert!(result.is_err());
+            assert_eq!(calls, 1, "{stderr}");
+        }
+    }
+
     #[test]
     #[cfg(target_os = "macos")]
     fn add_and_check_exclusion() {
@@ -100,7 +446,7 @@ mod tests {
         let path = dir.path();
 
         assert!(!is_excluded(path));
-        add_exclusion(path).unwrap();
+        add_exclusion(path, ExclusionMode::Sticky).unwrap();
         assert!(is_excluded(path));
     }
 
@@ -110,10 +456,10 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path();
 
-        add_exclusion(path).unwrap();
+        add_exclusion(path, ExclusionMode::Sticky).unwrap();
         assert!(is_excluded(path));
 
-        remove_exclusion(path).unwrap();
+        remove_exclusion(path, ExclusionMode::Sticky).unwrap();
         assert!(!is_excluded(path));
     }
 
@@ -121,7 +467,7 @@ mod tests {
     #[cfg(target_os = "macos")]
     fn remove_exclusion_on_non_excluded_is_ok() {
         let dir = tempfile::tempdir().unwrap();
-        assert!(remove_exclusion(dir.path()).is_ok());
+        assert!(remove_exclusion(dir.path(), ExclusionMode::Sticky).is_ok());
     }
 
     #[test]
@@ -130,19 +476,43 @@ mod tests {
         let dir1 = tempfile::tempdir().unwrap();
         let dir2 = tempfile::tempdir().unwrap();
 
-        add_exclusion(dir1.path()).unwrap();
+        add_exclusion(dir1.path(), ExclusionMode::Sticky).unwrap();
 
-        let results = are_excluded(&[dir1.path().to_path_buf(), dir2.path().to_path_buf()]);
+        let results = are_excluded(
+            &[dir1.path().to_path_buf(), dir2.path().to_path_buf()],
+            ExclusionMode::Sticky,
+        );
         assert_eq!(results, vec![true, false]);
     }
 
+    #[test]
+    #[cfg(target_os = "macos")]
+    fn are_excluded_matches_paths_with_newlines() {
+        let dir = tempfile::tempdir().unwrap();
+        let odd = dir.path().join("line\nbreak");
+        let plain = dir.path().join("plain");
+        std::fs::create_dir(&odd).unwrap();
+        std::fs::create_dir(&plain).unwrap();
+
+        add_exclusion(&odd, ExclusionMode::Sticky).unwrap();
+
+        assert_eq!(
+            are_excluded(&[plain, odd], ExclusionMode::Sticky),
+            vec![false, true]
+        );
+    }
+
     #[test]
     #[cfg(target_os = "macos")]
     fn add_exclusions_batch() {
         let dir1 = tempfile::tempdir().unwrap();
         let dir2 = tempfile::tempdir().unwrap();
 
-        add_exclusions(&[dir1.path().to_path_buf(), dir2.path().to_path_buf()]).unwrap();
+        add_exclusions(
+            &[dir1.path().to_path_buf(), dir2.path().to_path_buf()],
+            ExclusionMode::Sticky,
+        )
+        .unwrap();
 
         assert!(is_excluded(dir1.path()));
         assert!(is_excluded(dir2.path()));
@@ -154,8 +524,16 @@ mod tests {
         let dir1 = tempfile::tempdir().unwrap();
         let dir2 = tempfile::tempdir().unwrap();
 
-        add_exclusions(&[dir1.path().to_path_buf(), dir2.path().to_path_buf()]).unwrap();
-        remove_exclusions(&[dir1.path().to_path_buf(), dir2.path().to_path_buf()]).unwrap();
+        add_exclusions(
+            &[dir1.path().to_path_buf(), dir2.path().to_path_buf()],
+            ExclusionMode::Sticky,
+        )
+        .unwrap();
+        remove_exclusions(
+            &[dir1.path().to_path_buf(), dir2.path().to_path_buf()],
+            ExclusionMode::Sticky,
+        )
+        .unwrap();
 
         assert!(!is_excluded(dir1.path()));
         assert!(!is_excluded(dir2.path()));
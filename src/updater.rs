@@ -1,26 +1,99 @@
 use std::fs;
 use std::io::Write;
 use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::time::Duration;
 
-use serde::Deserialize;
+use ring::signature::{ED25519, UnparsedPublicKey};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use ureq::Agent;
+use ureq::{Agent, Proxy};
+
+use crate::config::Config;
+use crate::error::Error;
 
 const REPO: &str = "adeonir/veiled";
+const DEFAULT_API_BASE: &str = "https://api.github.com";
 const TIMEOUT: Duration = Duration::from_secs(30);
 const MAX_BINARY_SIZE: u64 = 10 * 1024 * 1024;
+const MAX_SIGNATURE_SIZE: u64 = 1024;
+const PROXY_ENV_VARS: &[&str] = &["HTTPS_PROXY", "https_proxy"];
+
+/// Ed25519 public key (hex) matching the `RELEASE_SIGNING_KEY` used by the
+/// release workflow to sign each binary. The release build embeds it from the
+/// `RELEASE_PUBLIC_KEY` repository variable; builds without it cannot update.
+const RELEASE_PUBLIC_KEY: Option<&str> = option_env!("VEILED_RELEASE_PUBLIC_KEY");
 
 #[derive(Debug)]
 pub struct UpdateResult {
     pub updated: bool,
+    pub blocked: bool,
     pub old_version: String,
     pub new_version: String,
 }
 
+/// Which GitHub releases the updater considers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Prerelease,
+}
+
+/// Where releases are looked up and which extra hosts downloads may come
+/// from, taken from the `update_*` config keys. Unset keys keep github.com.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UpdateSource {
+    pub channel: UpdateChannel,
+    pub api_base: Option<String>,
+    pub mirror_origins: Vec<String>,
+    pub max_update: Option<String>,
+}
+
+impl UpdateSource {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            channel: config.update_channel,
+            api_base: config.update_api_base.clone(),
+            mirror_origins: config.update_mirror_origins.clone(),
+            max_update: config.max_update.clone(),
+        }
+    }
+
+    /// Whether `latest` satisfies the `max_update` requirement. Always true
+    /// when the key is unset.
+    pub fn allows(&self, latest: &semver::Version) -> Result<bool, Box<dyn std::error::Error>> {
+        let Some(req) = self.max_update.as_deref() else {
+            return Ok(true);
+        };
+        let req = semver::VersionReq::parse(req)
+            .map_err(|e| Error::Config(format!("invalid max_update {req:?}: {e}")))?;
+        Ok(req.matches(latest))
+    }
+
+    fn releases_url(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let base = self
+            .api_base
+            .as_deref()
+            .unwrap_or(DEFAULT_API_BASE)
+            .trim_end_matches('/');
+        if !base.starts_with("https://") {
+            return Err(Error::Config(format!("update_api_base must use https: {base}")).into());
+        }
+        Ok(match self.channel {
+            UpdateChannel::Stable => format!("{base}/repos/{REPO}/releases/latest"),
+            UpdateChannel::Prerelease => format!("{base}/repos/{REPO}/releases"),
+        })
+    }
+}
+
 #[derive(Deserialize)]
 struct Release {
     tag_name: String,
+    #[serde(default)]
+    draft: bool,
     assets: Vec<Asset>,
 }
 
@@ -60,39 +133,162 @@ fn parse_checksum(content: &str) -> Result<String, Box<dyn std::error::Error>> {
     Ok(hex.to_lowercase())
 }
 
+fn decode_hex(hex: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return Err(format!("invalid hex string: {hex}").into());
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| format!("invalid hex string: {hex}").into())
+        })
+        .collect()
+}
+
+/// Decodes the embedded release key, failing before anything is downloaded
+/// when this binary was built without one.
+fn release_public_key(embedded: Option<&str>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let hex = embedded.filter(|hex| !hex.is_empty()).ok_or(
+        "this build has no release public key, so updates cannot be verified; \
+         install a published release instead",
+    )?;
+    let key = decode_hex(hex.trim())?;
+    if key.len() != 32 {
+        return Err(format!("invalid Ed25519 public key: {hex}").into());
+    }
+    Ok(key)
+}
+
+/// Verifies a detached Ed25519 signature over `data`. The signature file
+/// holds the 64-byte signature as hex, optionally followed by a file name.
+pub fn verify_signature(
+    data: &[u8],
+    signature_content: &str,
+    public_key: &[u8],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let hex = signature_content
+        .split_whitespace()
+        .next()
+        .ok_or("empty signature file")?;
+
+    if hex.len() != 128 {
+        return Err(format!("invalid Ed25519 signature: {hex}").into());
+    }
+    let signature = decode_hex(hex)?;
+
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(data, &signature)
+        .map_err(|_| "signature verification failed".into())
+}
+
 fn compute_sha256(data: &[u8]) -> String {
     format!("{:x}", Sha256::digest(data))
 }
 
-fn http_agent() -> Agent {
-    Agent::config_builder()
-        .timeout_global(Some(TIMEOUT))
-        .build()
-        .into()
+/// Resolves the proxy from the first non-empty `PROXY_ENV_VARS` entry
+/// returned by `lookup`.
+fn proxy_from_env(
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<Option<Proxy>, Box<dyn std::error::Error>> {
+    let Some((var, url)) = PROXY_ENV_VARS
+        .iter()
+        .find_map(|var| lookup(var).filter(|v| !v.is_empty()).map(|v| (var, v)))
+    else {
+        return Ok(None);
+    };
+
+    let proxy = Proxy::new(&url).map_err(|e| format!("invalid {var} ({url}): {e}"))?;
+    Ok(Some(proxy))
+}
+
+fn build_agent(proxy: Option<Proxy>) -> Agent {
+    let mut builder = Agent::config_builder().timeout_global(Some(TIMEOUT));
+    if proxy.is_some() {
+        builder = builder.proxy(proxy);
+    }
+    builder.build().into()
 }
 
-pub fn check() -> Result<UpdateResult, Box<dyn std::error::Error>> {
-    let agent = http_agent();
-    let url = format!("https://api.github.com/repos/{REPO}/releases/latest");
+fn http_agent() -> Result<Agent, Box<dyn std::error::Error>> {
+    Ok(build_agent(proxy_from_env(|var| std::env::var(var).ok())?))
+}
 
-    let response: Release = agent
+/// Picks the release with the highest semver tag, prereleases included.
+/// Drafts and tags that don't parse as versions are ignored.
+fn select_newest(releases: Vec<Release>) -> Option<Release> {
+    releases
+        .into_iter()
+        .filter(|r| !r.draft)
+        .filter_map(|r| parse_version(&r.tag_name).ok().map(|v| (v, r)))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, r)| r)
+}
+
+fn fetch_release(
+    agent: &Agent,
+    source: &UpdateSource,
+) -> Result<Release, Box<dyn std::error::Error>> {
+    let url = source.releases_url()?;
+
+    let mut response = agent
         .get(&url)
         .header("Accept", "application/vnd.github+json")
         .header("User-Agent", "veiled")
         .call()
-        .map_err(|e| format!("failed to fetch latest release: {e}"))?
-        .body_mut()
-        .read_json()?;
+        .map_err(|e| Error::Network(format!("failed to fetch latest release: {e}")))?;
+
+    match source.channel {
+        UpdateChannel::Stable => Ok(response
+            .body_mut()
+            .read_json()
+            .map_err(|e| Error::Network(format!("failed to read release: {e}")))?),
+        UpdateChannel::Prerelease => {
+            let releases: Vec<Release> = response
+                .body_mut()
+                .read_json()
+                .map_err(|e| Error::Network(format!("failed to read release: {e}")))?;
+            select_newest(releases).ok_or_else(|| "no releases found".into())
+        }
+    }
+}
+
+fn newer_than_current(tag: &str) -> Result<Option<semver::Version>, Box<dyn std::error::Error>> {
+    let current = parse_version(current_version())?;
+    let latest = parse_version(tag)?;
+    Ok((latest > current).then_some(latest))
+}
+
+/// Queries the release channel without downloading anything. Returns the
+/// newer version if one exists, or `None` when already up to date.
+pub fn latest_available(
+    source: &UpdateSource,
+) -> Result<Option<semver::Version>, Box<dyn std::error::Error>> {
+    let release = fetch_release(&http_agent()?, source)?;
+    newer_than_current(&release.tag_name)
+}
+
+pub fn check(source: &UpdateSource) -> Result<UpdateResult, Box<dyn std::error::Error>> {
+    let agent = http_agent()?;
+    let response = fetch_release(&agent, source)?;
 
     let old = current_version().to_string();
     let new = response.tag_name.clone();
 
-    let current = parse_version(&old)?;
-    let latest = parse_version(&new)?;
+    let Some(latest) = newer_than_current(&new)? else {
+        return Ok(UpdateResult {
+            updated: false,
+            blocked: false,
+            old_version: old,
+            new_version: new,
+        });
+    };
 
-    if latest <= current {
+    if !source.allows(&latest)? {
         return Ok(UpdateResult {
             updated: false,
+            blocked: true,
             old_version: old,
             new_version: new,
         });
@@ -100,6 +296,7 @@ pub fn check() -> Result<UpdateResult, Box<dyn std::error::Error>> {
 
     let asset_name = platform_asset_name();
     let checksum_name = format!("{asset_name}.sha256");
+    let signature_name = format!("{asset_name}.sig");
 
     let binary_asset = response
         .assets
@@ -113,14 +310,24 @@ pub fn check() -> Result<UpdateResult, Box<dyn std::error::Error>> {
         .find(|a| a.name == checksum_name)
         .ok_or_else(|| format!("no checksum available for this platform ({checksum_name})"))?;
 
+    let signature_asset = response
+        .assets
+        .iter()
+        .find(|a| a.name == signature_name)
+        .ok_or_else(|| format!("no signature available for this platform ({signature_name})"))?;
+
     download_and_replace(
         &agent,
         &binary_asset.browser_download_url,
         &checksum_asset.browser_download_url,
+        &signature_asset.browser_download_url,
+        &latest,
+        &source.mirror_origins,
     )?;
 
     Ok(UpdateResult {
         updated: true,
+        blocked: false,
         old_version: old,
         new_version: new,
     })
@@ -131,8 +338,25 @@ const TRUSTED_ORIGINS: &[&str] = &[
     "https://objects.githubusercontent.com/",
 ];
 
-fn validate_download_url(url: &str) -> Result<(), Box<dyn std::error::Error>> {
-    if !TRUSTED_ORIGINS.iter().any(|origin| url.starts_with(origin)) {
+/// Accepts `url` only from the github.com origins or a configured mirror.
+/// Every origin must be https, and must match up to a path boundary so a
+/// mirror `https://ghe.corp` does not also trust `https://ghe.corp.evil`.
+fn validate_download_url(
+    url: &str,
+    mirror_origins: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let trusted = TRUSTED_ORIGINS
+        .iter()
+        .copied()
+        .chain(mirror_origins.iter().map(String::as_str))
+        .any(|origin| {
+            let origin = origin.trim_end_matches('/');
+            origin.starts_with("https://")
+                && url
+                    .strip_prefix(origin)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        });
+    if !trusted {
         return Err(format!("untrusted download origin: {url}").into());
     }
     Ok(())
@@ -142,7 +366,11 @@ fn download_and_replace(
     agent: &Agent,
     binary_url: &str,
     checksum_url: &str,
+    signature_url: &str,
+    expected_version: &semver::Version,
+    mirror_origins: &[String],
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let public_key = release_public_key(RELEASE_PUBLIC_KEY)?;
     let binary_path =
         std::env::current_exe().map_err(|e| format!("failed to resolve binary path: {e}"))?;
 
@@ -150,32 +378,44 @@ fn download_and_replace(
         .parent()
         .ok_or("failed to resolve binary directory")?;
 
-    validate_download_url(binary_url)?;
-    validate_download_url(checksum_url)?;
+    validate_download_url(binary_url, mirror_origins)?;
+    validate_download_url(checksum_url, mirror_origins)?;
+    validate_download_url(signature_url, mirror_origins)?;
 
     let checksum_content = agent
         .get(checksum_url)
         .header("User-Agent", "veiled")
         .call()
-        .map_err(|e| format!("failed to download checksum: {e}"))?
+        .map_err(|e| Error::Network(format!("failed to download checksum: {e}")))?
         .into_body()
         .with_config()
         .limit(1024)
         .read_to_string()
-        .map_err(|e| format!("failed to read checksum: {e}"))?;
+        .map_err(|e| Error::Network(format!("failed to read checksum: {e}")))?;
 
     let expected = parse_checksum(&checksum_content)?;
 
+    let signature_content = agent
+        .get(signature_url)
+        .header("User-Agent", "veiled")
+        .call()
+        .map_err(|e| Error::Network(format!("failed to download signature: {e}")))?
+        .into_body()
+        .with_config()
+        .limit(MAX_SIGNATURE_SIZE)
+        .read_to_string()
+        .map_err(|e| Error::Network(format!("failed to read signature: {e}")))?;
+
     let bytes = agent
         .get(binary_url)
         .header("User-Agent", "veiled")
         .call()
-        .map_err(|e| format!("failed to download update: {e}"))?
+        .map_err(|e| Error::Network(format!("failed to download update: {e}")))?
         .into_body()
         .with_config()
         .limit(MAX_BINARY_SIZE)
         .read_to_vec()
-        .map_err(|e| format!("failed to read download: {e}"))?;
+        .map_err(|e| Error::Network(format!("failed to read download: {e}")))?;
 
     let actual = compute_sha256(&bytes);
 
@@ -183,6 +423,8 @@ fn download_and_replace(
         return Err(format!("checksum mismatch: expected {expected}, got {actual}").into());
     }
 
+    verify_signature(&bytes, &signature_content, &public_key)?;
+
     let mut temp = tempfile::NamedTempFile::new_in(parent)
         .map_err(|e| format!("failed to create temp file: {e}"))?;
 
@@ -191,9 +433,65 @@ fn download_and_replace(
 
     fs::set_permissions(temp.path(), fs::Permissions::from_mode(0o755))?;
 
-    temp.persist(&binary_path)
-        .map_err(|e| format!("failed to install update: {e}"))?;
+    install_with_rollback(temp, &binary_path, |path| {
+        verify_installed(path, expected_version)
+    })
+}
+
+fn backup_path(binary_path: &Path) -> PathBuf {
+    let mut name = binary_path.as_os_str().to_owned();
+    name.push(".bak");
+    PathBuf::from(name)
+}
+
+/// Runs `<binary> --version` and checks it reports `expected`.
+fn verify_installed(
+    binary_path: &Path,
+    expected: &semver::Version,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let output = Command::new(binary_path)
+        .arg("--version")
+        .output()
+        .map_err(|e| format!("failed to run updated binary: {e}"))?;
+
+    if !output.status.success() {
+        return Err(format!("updated binary exited with {}", output.status).into());
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let reported = stdout.split_whitespace().last().unwrap_or_default();
+    if reported != expected.to_string() {
+        return Err(
+            format!("updated binary reports version {reported}, expected {expected}").into(),
+        );
+    }
+
+    Ok(())
+}
+
+/// Backs up the current binary, installs `temp` over it and runs `verify`
+/// on the result. The backup is restored if verification fails and
+/// removed once it succeeds.
+fn install_with_rollback(
+    temp: tempfile::NamedTempFile,
+    binary_path: &Path,
+    verify: impl FnOnce(&Path) -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let backup = backup_path(binary_path);
+    fs::copy(binary_path, &backup).map_err(|e| format!("failed to back up binary: {e}"))?;
+
+    if let Err(e) = temp.persist(binary_path) {
+        let _ = fs::remove_file(&backup);
+        return Err(format!("failed to install update: {e}").into());
+    }
+
+    if let Err(e) = verify(binary_path) {
+        fs::rename(&backup, binary_path)
+            .map_err(|re| format!("{e}; failed to restore backup: {re}"))?;
+        return Err(format!("{e}; previous version restored").into());
+    }
 
+    let _ = fs::remove_file(&backup);
     Ok(())
 }
 
@@ -257,6 +555,64 @@ mod tests {
         assert!(latest <= current);
     }
 
+    #[test]
+    fn newer_than_current_detects_newer_tag() {
+        let current = parse_version(current_version()).unwrap();
+        let next = format!("v{}.0.0", current.major + 1);
+
+        assert_eq!(
+            newer_than_current(&next).unwrap(),
+            Some(semver::Version::new(current.major + 1, 0, 0))
+        );
+    }
+
+    #[test]
+    fn newer_than_current_is_none_for_same_or_older() {
+        assert!(newer_than_current(current_version()).unwrap().is_none());
+        assert!(newer_than_current("v0.0.0").unwrap().is_none());
+    }
+
+    #[test]
+    fn max_update_allows_minor_bump_within_requirement() {
+        let source = UpdateSource {
+            max_update: Some("^0".to_string()),
+            ..UpdateSource::default()
+        };
+        let current = parse_version("0.9.0").unwrap();
+        let latest = parse_version("v0.10.0").unwrap();
+
+        assert!(latest > current);
+        assert!(source.allows(&latest).unwrap());
+    }
+
+    #[test]
+    fn max_update_blocks_major_bump_outside_requirement() {
+        let source = UpdateSource {
+            max_update: Some("^0".to_string()),
+            ..UpdateSource::default()
+        };
+        let current = parse_version("0.9.0").unwrap();
+        let latest = parse_version("v1.0.0").unwrap();
+
+        assert!(latest > current);
+        assert!(!source.allows(&latest).unwrap());
+    }
+
+    #[test]
+    fn max_update_unset_allows_everything() {
+        let latest = parse_version("v9.0.0").unwrap();
+        assert!(UpdateSource::default().allows(&latest).unwrap());
+    }
+
+    #[test]
+    fn max_update_rejects_invalid_requirement() {
+        let source = UpdateSource {
+            max_update: Some("not a version".to_string()),
+            ..UpdateSource::default()
+        };
+        assert!(source.allows(&semver::Version::new(1, 0, 0)).is_err());
+    }
+
     #[test]
     fn deserialize_release_response() {
         let json = r#"{
@@ -290,6 +646,51 @@ mod tests {
         assert!(release.assets.is_empty());
     }
 
+    #[test]
+    fn select_newest_picks_highest_version_including_prereleases() {
+        let json = r#"[
+            {"tag_name": "v0.2.0", "assets": []},
+            {"tag_name": "v0.3.0-beta.2", "assets": []},
+            {"tag_name": "v0.3.0-beta.10", "assets": []},
+            {"tag_name": "v0.1.0", "assets": []}
+        ]"#;
+
+        let releases: Vec<Release> = serde_json::from_str(json).unwrap();
+        let newest = select_newest(releases).unwrap();
+
+        assert_eq!(newest.tag_name, "v0.3.0-beta.10");
+    }
+
+    #[test]
+    fn select_newest_prefers_final_release_over_its_prerelease() {
+        let json = r#"[
+            {"tag_name": "v0.3.0-rc.1", "assets": []},
+            {"tag_name": "v0.3.0", "assets": []}
+        ]"#;
+
+        let releases: Vec<Release> = serde_json::from_str(json).unwrap();
+
+        assert_eq!(select_newest(releases).unwrap().tag_name, "v0.3.0");
+    }
+
+    #[test]
+    fn select_newest_skips_drafts_and_invalid_tags() {
+        let json = r#"[
+            {"tag_name": "v9.0.0", "draft": true, "assets": []},
+            {"tag_name": "nightly", "assets": []},
+            {"tag_name": "v0.2.0", "assets": []}
+        ]"#;
+
+        let releases: Vec<Release> = serde_json::from_str(json).unwrap();
+
+        assert_eq!(select_newest(releases).unwrap().tag_name, "v0.2.0");
+    }
+
+    #[test]
+    fn select_newest_returns_none_for_empty_list() {
+        assert!(select_newest(vec![]).is_none());
+    }
+
     #[test]
     fn compute_sha256_produces_valid_hex() {
         let hash = compute_sha256(b"hello world");
@@ -354,17 +755,218 @@ mod tests {
         assert!(parse_checksum(content).is_err());
     }
 
+    fn test_keypair() -> ring::signature::Ed25519KeyPair {
+        let seed: Vec<u8> = (0u8..32).collect();
+        ring::signature::Ed25519KeyPair::from_seed_unchecked(&seed).unwrap()
+    }
+
+    fn to_hex(bytes: &[u8]) -> String {
+        use std::fmt::Write as _;
+
+        bytes.iter().fold(String::new(), |mut out, b| {
+            let _ = write!(out, "{b:02x}");
+            out
+        })
+    }
+
+    #[test]
+    fn verify_signature_accepts_valid_signature() {
+        use ring::signature::KeyPair;
+
+        let keypair = test_keypair();
+        let payload = b"veiled binary payload";
+        let content = format!(
+            "{}  veiled-macos-arm64\n",
+            to_hex(keypair.sign(payload).as_ref())
+        );
+
+        assert!(verify_signature(payload, &content, keypair.public_key().as_ref()).is_ok());
+    }
+
+    #[test]
+    fn verify_signature_rejects_tampered_payload() {
+        use ring::signature::KeyPair;
+
+        let keypair = test_keypair();
+        let content = to_hex(keypair.sign(b"original").as_ref());
+
+        assert!(verify_signature(b"tampered", &content, keypair.public_key().as_ref()).is_err());
+    }
+
+    #[test]
+    fn verify_signature_rejects_wrong_key() {
+        use ring::signature::KeyPair;
+
+        let keypair = test_keypair();
+        let content = to_hex(keypair.sign(b"payload").as_ref());
+        let seed = [7u8; 32];
+        let other = ring::signature::Ed25519KeyPair::from_seed_unchecked(&seed).unwrap();
+
+        assert!(verify_signature(b"payload", &content, other.public_key().as_ref()).is_err());
+    }
+
+    #[test]
+    fn verify_signature_rejects_malformed_content() {
+        use ring::signature::KeyPair;
+
+        let keypair = test_keypair();
+        let key = keypair.public_key().as_ref();
+
+        assert!(verify_signature(b"payload", "", key).is_err());
+        assert!(verify_signature(b"payload", "abc123", key).is_err());
+        assert!(verify_signature(b"payload", &"zz".repeat(64), key).is_err());
+    }
+
+    #[test]
+    fn release_public_key_decodes_embedded_hex() {
+        use ring::signature::KeyPair;
+
+        let keypair = test_keypair();
+        let hex = to_hex(keypair.public_key().as_ref());
+
+        assert_eq!(
+            release_public_key(Some(&hex)).unwrap(),
+            keypair.public_key().as_ref()
+        );
+    }
+
+    #[test]
+    fn release_public_key_is_required() {
+        let err = release_public_key(None).unwrap_err();
+        assert!(err.to_string().contains("no release public key"));
+        assert!(release_public_key(Some("")).is_err());
+    }
+
+    #[test]
+    fn release_public_key_rejects_wrong_length() {
+        assert!(release_public_key(Some("abcd")).is_err());
+    }
+
+    fn write_script(path: &Path, body: &str) {
+        fs::write(path, format!("#!/bin/sh\n{body}\n")).unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    fn staged_update(dir: &Path, body: &str) -> tempfile::NamedTempFile {
+        let temp = tempfile::NamedTempFile::new_in(dir).unwrap();
+        write_script(temp.path(), body);
+        temp
+    }
+
+    #[test]
+    fn backup_path_appends_bak_suffix() {
+        assert_eq!(
+            backup_path(Path::new("/usr/local/bin/veiled")),
+            PathBuf::from("/usr/local/bin/veiled.bak")
+        );
+    }
+
+    #[test]
+    fn install_with_rollback_keeps_working_binary() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let binary = dir.path().join("veiled");
+        write_script(&binary, "echo 'veiled 0.1.0'");
+        let temp = staged_update(dir.path(), "echo 'veiled 0.2.0'");
+        let expected = semver::Version::new(0, 2, 0);
+
+        install_with_rollback(temp, &binary, |p| verify_installed(p, &expected)).unwrap();
+
+        assert!(fs::read_to_string(&binary).unwrap().contains("0.2.0"));
+        assert!(!backup_path(&binary).exists());
+    }
+
+    #[test]
+    fn install_with_rollback_restores_failing_binary() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let binary = dir.path().join("veiled");
+        write_script(&binary, "echo 'veiled 0.1.0'");
+        let temp = staged_update(dir.path(), "exit 1");
+        let expected = semver::Version::new(0, 2, 0);
+
+        let err = install_with_rollback(temp, &binary, |p| verify_installed(p, &expected))
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("previous version restored"));
+        assert!(fs::read_to_string(&binary).unwrap().contains("0.1.0"));
+        assert!(!backup_path(&binary).exists());
+    }
+
+    #[test]
+    fn install_with_rollback_restores_on_version_mismatch() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let binary = dir.path().join("veiled");
+        write_script(&binary, "echo 'veiled 0.1.0'");
+        let temp = staged_update(dir.path(), "echo 'veiled 0.1.5'");
+        let expected = semver::Version::new(0, 2, 0);
+
+        let err = install_with_rollback(temp, &binary, |p| verify_installed(p, &expected))
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("reports version 0.1.5"));
+        assert!(fs::read_to_string(&binary).unwrap().contains("0.1.0"));
+    }
+
+    #[test]
+    fn proxy_from_env_is_none_when_unset() {
+        assert!(proxy_from_env(|_| None).unwrap().is_none());
+        assert!(proxy_from_env(|_| Some(String::new())).unwrap().is_none());
+    }
+
+    #[test]
+    fn proxy_from_env_prefers_uppercase_variable() {
+        let proxy = proxy_from_env(|var| match var {
+            "HTTPS_PROXY" => Some("http://proxy.corp:3128".to_string()),
+            _ => Some("http://other:8080".to_string()),
+        })
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(proxy.host(), "proxy.corp");
+        assert_eq!(proxy.port(), 3128);
+    }
+
+    #[test]
+    fn proxy_from_env_falls_back_to_lowercase_variable() {
+        let proxy = proxy_from_env(|var| {
+            (var == "https_proxy").then(|| "http://proxy.corp:8080".to_string())
+        })
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(proxy.port(), 8080);
+    }
+
+    #[test]
+    fn proxy_from_env_rejects_invalid_url() {
+        let err = proxy_from_env(|_| Some("ftp://bad proxy".to_string())).unwrap_err();
+        assert!(err.to_string().contains("HTTPS_PROXY"));
+    }
+
+    #[test]
+    fn build_agent_applies_proxy() {
+        let proxy = Proxy::new("http://proxy.corp:3128").unwrap();
+        let agent = build_agent(Some(proxy));
+
+        let applied = agent.config().proxy().unwrap();
+        assert_eq!(applied.host(), "proxy.corp");
+        assert_eq!(agent.config().timeouts().global, Some(TIMEOUT));
+    }
+
     #[test]
     fn validate_download_url_accepts_github() {
         assert!(
             validate_download_url(
-                "https://github.com/adeonir/veiled/releases/download/v0.1.0/veiled-macos-arm64"
+                "https://github.com/adeonir/veiled/releases/download/v0.1.0/veiled-macos-arm64",
+                &[]
             )
             .is_ok()
         );
         assert!(
             validate_download_url(
-                "https://objects.githubusercontent.com/github-production-release-asset/123/456"
+                "https://objects.githubusercontent.com/github-production-release-asset/123/456",
+                &[]
             )
             .is_ok()
         );
@@ -372,8 +974,62 @@ mod tests {
 
     #[test]
     fn validate_download_url_rejects_unknown_origin() {
-        assert!(validate_download_url("https://evil.com/veiled-macos-arm64").is_err());
-        assert!(validate_download_url("http://github.com/insecure").is_err());
+        assert!(validate_download_url("https://evil.com/veiled-macos-arm64", &[]).is_err());
+        assert!(validate_download_url("http://github.com/insecure", &[]).is_err());
+    }
+
+    #[test]
+    fn validate_download_url_accepts_configured_mirror_only() {
+        let mirrors = vec!["https://ghe.corp.example".to_string()];
+
+        assert!(
+            validate_download_url("https://ghe.corp.example/veiled/releases/v1/bin", &mirrors)
+                .is_ok()
+        );
+        assert!(validate_download_url("https://other.example/veiled/bin", &mirrors).is_err());
+        assert!(validate_download_url("https://ghe.corp.example.evil/bin", &mirrors).is_err());
+    }
+
+    #[test]
+    fn validate_download_url_rejects_http_mirror() {
+        let mirrors = vec!["http://ghe.corp.example/".to_string()];
+
+        assert!(validate_download_url("http://ghe.corp.example/veiled/bin", &mirrors).is_err());
+    }
+
+    #[test]
+    fn releases_url_defaults_to_github_api() {
+        let source = UpdateSource::default();
+
+        assert_eq!(
+            source.releases_url().unwrap(),
+            "https://api.github.com/repos/adeonir/veiled/releases/latest"
+        );
+    }
+
+    #[test]
+    fn releases_url_uses_configured_api_base() {
+        let source = UpdateSource {
+            channel: UpdateChannel::Prerelease,
+            api_base: Some("https://ghe.corp.example/api/v3/".to_string()),
+            mirror_origins: vec![],
+            max_update: None,
+        };
+
+        assert_eq!(
+            source.releases_url().unwrap(),
+            "https://ghe.corp.example/api/v3/repos/adeonir/veiled/releases"
+        );
+    }
+
+    #[test]
+    fn releases_url_rejects_http_api_base() {
+        let source = UpdateSource {
+            api_base: Some("http://ghe.corp.example/api/v3".to_string()),
+            ..UpdateSource::default()
+        };
+
+        assert!(source.releases_url().is_err());
     }
 }
 
@@ -388,6 +1044,12 @@ mod fuzz_tests {
             let _ = parse_checksum(&data);
         }
 
+        #[test]
+        fn verify_signature_never_panics(data in "\\PC{0,256}") {
+            let key = [0u8; 32];
+            let _ = verify_signature(b"payload", &data, &key);
+        }
+
         #[test]
         fn parse_version_never_panics(data in "\\PC{0,64}") {
             let _ = parse_version(&data);
@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+
+use crate::builtins::BuiltinNames;
+use crate::config::Config;
+use crate::scanner::MatchDepth;
+
+/// Decides which newly created directories `watch` should exclude, using
+/// the same search paths, ignore paths and builtin names as a scan.
+pub struct Matcher {
+    search_paths: Vec<PathBuf>,
+    ignore_paths: Vec<PathBuf>,
+    builtins: BuiltinNames,
+    match_depth: MatchDepth,
+}
+
+impl Matcher {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            search_paths: config.search_paths.iter().map(PathBuf::from).collect(),
+            ignore_paths: config.ignore_paths.iter().map(PathBuf::from).collect(),
+            builtins: BuiltinNames::new(&config.custom_builtins, &config.ignore_names)
+                .with_globs(&config.builtin_globs),
+            match_depth: config.match_depth,
+        }
+    }
+
+    fn is_builtin(&self, name: &std::ffi::OsStr) -> bool {
+        self.builtins.contains(&name.to_string_lossy())
+    }
+
+    /// Whether `path` is a builtin directory inside a search path (directly
+    /// inside it with `match_depth` root-only), outside every ignore path,
+    /// and not nested in another builtin directory (which is already
+    /// excluded as a whole).
+    pub fn matches(&self, path: &Path) -> bool {
+        if !self.builtins.matches_dir(path) {
+            return false;
+        }
+        if self
+            .ignore_paths
+            .iter()
+            .any(|ignored| path.starts_with(ignored))
+        {
+            return false;
+        }
+        let Some(root) = self
+            .search_paths
+            .iter()
+            .find(|root| path.starts_with(root) && path != root.as_path())
+        else {
+            return false;
+        };
+        if self.match_depth == MatchDepth::RootOnly && path.parent() != Some(root.as_path()) {
+            return false;
+        }
+
+        !path
+            .parent()
+            .into_iter()
+            .flat_map(Path::ancestors)
+            .take_while(|ancestor| *ancestor != root.as_path())
+            .filter_map(Path::file_name)
+            .any(|name| self.is_builtin(name))
+    }
+}
+
+/// Coalesces bursts of events: a path is released only once no new event
+/// for it has arrived within `window`, so a package manager creating and
+/// renaming directories is handled once it settles.
+pub struct Debouncer {
+    window: Duration,
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl Debouncer {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn push(&mut self, path: PathBuf, at: Instant) {
+        self.pending.insert(path, at);
+    }
+
+    /// Removes and returns, sorted, every path that has been quiet for at
+    /// least the debounce window as of `now`.
+    pub fn drain_ready(&mut self, now: Instant) -> Vec<PathBuf> {
+        let mut ready: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .filter(|(_, last)| now.saturating_duration_since(**last) >= self.window)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in &ready {
+            self.pending.remove(path);
+        }
+        ready.sort();
+        ready
+    }
+}
+
+/// Streams directories created (or renamed into place) under `paths`.
+#[cfg(target_os = "macos")]
+pub fn watch(paths: &[String]) -> Result<Receiver<PathBuf>, Box<dyn std::error::Error>> {
+    fsevents::watch(paths)
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn watch(_paths: &[String]) -> Result<Receiver<PathBuf>, Box<dyn std::error::Error>> {
+    Err("watch requires macOS FSEvents".into())
+}
+
+#[cfg(target_os = "macos")]
+mod fsevents {
+    use std::ffi::{CStr, CString, c_char, c_void};
+    use std::path::PathBuf;
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::thread;
+
+    type CFIndex = isize;
+    type CFRef = *const c_void;
+    type FSEventStreamRef = *mut c_void;
+
+    type Callback = extern "C" fn(
+        stream: FSEventStreamRef,
+        info: *mut c_void,
+        num_events: usize,
+        event_paths: *mut c_void,
+        event_flags: *const u32,
+        event_ids: *const u64,
+    );
+
+    #[repr(C)]
+    struct FSEventStreamContext {
+        version: CFIndex,
+        info: *mut c_void,
+        retain: Option<extern "C" fn(*const c_void) -> *const c_void>,
+        release: Option<extern "C" fn(*const c_void)>,
+        copy_description: Option<extern "C" fn(*const c_void) -> CFRef>,
+    }
+
+    #[repr(C)]
+    struct CFArrayCallBacks {
+        _opaque: [u8; 0],
+    }
+
+    const UTF8_ENCODING: u32 = 0x0800_0100;
+    const SINCE_NOW: u64 = u64::MAX;
+    const LATENCY_SECS: f64 = 0.5;
+    const CREATE_FLAG_FILE_EVENTS: u32 = 0x10;
+    const EVENT_FLAG_ITEM_CREATED: u32 = 0x100;
+    const EVENT_FLAG_ITEM_RENAMED: u32 = 0x800;
+    const EVENT_FLAG_ITEM_IS_DIR: u32 = 0x0002_0000;
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    unsafe extern "C" {
+        #[link_name = "kCFRunLoopDefaultMode"]
+        static RUN_LOOP_DEFAULT_MODE: CFRef;
+        #[link_name = "kCFTypeArrayCallBacks"]
+        static TYPE_ARRAY_CALLBACKS: CFArrayCallBacks;
+
+        fn CFStringCreateWithCString(alloc: CFRef, c_str: *const c_char, encoding: u32) -> CFRef;
+        fn CFArrayCreate(
+            alloc: CFRef,
+            values: *const CFRef,
+            num_values: CFIndex,
+            callbacks: *const CFArrayCallBacks,
+        ) -> CFRef;
+        fn CFRelease(cf: CFRef);
+        fn CFRunLoopGetCurrent() -> *mut c_void;
+        fn CFRunLoopRun();
+    }
+
+    #[link(name = "CoreServices", kind = "framework")]
+    unsafe extern "C" {
+        fn FSEventStreamCreate(
+            alloc: CFRef,
+            callback: Callback,
+            context: *const FSEventStreamContext,
+            paths_to_watch: CFRef,
+            since_when: u64,
+            latency: f64,
+            flags: u32,
+        ) -> FSEventStreamRef;
+        fn FSEventStreamScheduleWithRunLoop(
+            stream: FSEventStreamRef,
+            run_loop: *mut c_void,
+            run_loop_mode: CFRef,
+        );
+        fn FSEventStreamStart(stream: FSEventStreamRef) -> u8;
+    }
+
+    extern "C" fn on_events(
+        _stream: FSEventStreamRef,
+        info: *mut c_void,
+        num_events: usize,
+        event_paths: *mut c_void,
+        event_flags: *const u32,
+        _event_ids: *const u64,
+    ) {
+        // SAFETY: `info` is the leaked `Sender` from `watch`, and without
+        // kFSEventStreamCreateFlagUseCFTypes FSEvents passes `num_events`
+        // C strings and flags.
+        let (tx, paths, flags) = unsafe {
+            (
+                &*info.cast::<Sender<PathBuf>>(),
+                std::slice::from_raw_parts(event_paths.cast::<*const c_char>(), num_events),
+                std::slice::from_raw_parts(event_flags, num_events),
+            )
+        };
+
+        for (path, flag) in paths.iter().zip(flags) {
+            let is_new_dir = flag & EVENT_FLAG_ITEM_IS_DIR != 0
+                && flag & (EVENT_FLAG_ITEM_CREATED | EVENT_FLAG_ITEM_RENAMED) != 0;
+            if is_new_dir {
+                // SAFETY: FSEvents hands out NUL-terminated paths.
+                let path = unsafe { CStr::from_ptr(*path) };
+                let _ = tx.send(PathBuf::from(path.to_string_lossy().into_owned()));
+            }
+        }
+    }
+
+    pub fn watch(paths: &[String]) -> Result<Receiver<PathBuf>, Box<dyn std::error::Error>> {
+        let native_paths = paths
+            .iter()
+            .map(|p| CString::new(p.as_str()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (tx, rx) = mpsc::channel();
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            // SAFETY: plain CoreFoundation/FSEvents calls; the context's
+            // `info` is a leaked Sender that lives as long as the stream,
+            // which runs until the process exits.
+            unsafe {
+                let cf_strings: Vec<CFRef> = native_paths
+                    .iter()
+                    .map(|p| CFStringCreateWithCString(std::ptr::null(), p.as_ptr(), UTF8_ENCODING))
+                    .collect();
+                let array = CFArrayCreate(
+                    std::ptr::null(),
+                    cf_strings.as_ptr(),
+                    cf_strings.len().cast_signed(),
+                    &raw const TYPE_ARRAY_CALLBACKS,
+                );
+                for s in cf_strings {
+                    CFRelease(s);
+                }
+
+                let context = FSEventStreamContext {
+                    version: 0,
+                    info: Box::into_raw(Box::new(tx)).cast(),
+                    retain: None,
+                    release: None,
+                    copy_description: None,
+                };
+                let stream = FSEventStreamCreate(
+                    std::ptr::null(),
+                    on_events,
+                    &raw const context,
+                    array,
+                    SINCE_NOW,
+                    LATENCY_SECS,
+                    CREATE_FLAG_FILE_EVENTS,
+                );
+                CFRelease(array);
+
+                if stream.is_null() {
+                    let _ = ready_tx.send(Err("failed to create FSEvents stream"));
+                    return;
+                }
+                FSEventStreamScheduleWithRunLoop(
+                    stream,
+                    CFRunLoopGetCurrent(),
+                    RUN_LOOP_DEFAULT_MODE,
+                );
+                if FSEventStreamStart(stream) == 0 {
+                    let _ = ready_tx.send(Err("failed to start FSEvents stream"));
+                    return;
+                }
+                let _ = ready_tx.send(Ok(()));
+                CFRunLoopRun();
+            }
+        });
+
+        ready_rx
+            .recv()
+            .map_err(|_| "FSEvents thread exited unexpectedly")??;
+        Ok(rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher(search: &[&str], ignore: &[&str]) -> Matcher {
+        Matcher::new(&Config {
+            search_paths: search.iter().map(ToString::to_string).collect(),
+            ignore_paths: ignore.iter().map(ToString::to_string).collect(),
+            ..Config::default()
+        })
+    }
+
+    #[test]
+    fn matches_builtin_inside_search_path() {
+        let m = matcher(&["/Users/dev/Projects"], &[]);
+        assert!(m.matches(Path::new("/Users/dev/Projects/app/node_modules")));
+        assert!(!m.matches(Path::new("/Users/dev/Projects/app/src")));
+    }
+
+    #[test]
+    fn ignores_paths_outside_search_paths_or_under_ignore_paths() {
+        let m = matcher(&["/Users/dev/Projects"], &["/Users/dev/Projects/vendor"]);
+        assert!(!m.matches(Path::new("/Users/dev/Other/app/node_modules")));
+        assert!(!m.matches(Path::new("/Users/dev/Projects/vendor/lib/node_modules")));
+    }
+
+    #[test]
+    fn skips_builtins_nested_in_another_builtin() {
+        let m = matcher(&["/Users/dev/Projects"], &[]);
+        assert!(!m.matches(Path::new(
+            "/Users/dev/Projects/app/node_modules/pkg/node_modules"
+        )));
+    }
+
+    #[test]
+    fn root_only_matches_direct_children_of_search_paths() {
+        let m = Matcher::new(&Config {
+            search_paths: vec!["/Users/dev/app".to_string()],
+            match_depth: MatchDepth::RootOnly,
+            ..Config::default()
+        });
+        assert!(m.matches(Path::new("/Users/dev/app/node_modules")));
+        assert!(!m.matches(Path::new("/Users/dev/app/web/node_modules")));
+    }
+
+    #[test]
+    fn search_root_itself_never_matches() {
+        let m = matcher(&["/Users/dev/node_modules"], &[]);
+        assert!(!m.matches(Path::new("/Users/dev/node_modules")));
+    }
+
+    #[test]
+    fn debouncer_waits_for_quiet_window() {
+        let start = Instant::now();
+        let window = Duration::from_millis(500);
+        let mut debouncer = Debouncer::new(window);
+        let path = PathBuf::from("/p/node_modules");
+
+        debouncer.push(path.clone(), start);
+        debouncer.push(path.clone(), start + Duration::from_millis(300));
+
+        assert!(
+            debouncer
+                .drain_ready(start + Duration::from_millis(600))
+                .is_empty()
+        );
+        assert_eq!(
+            debouncer.drain_ready(start + Duration::from_millis(800)),
+            vec![path]
+        );
+        assert!(
+            debouncer
+                .drain_ready(start + Duration::from_secs(5))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn debouncer_releases_each_path_once_sorted() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(Duration::from_millis(100));
+        debouncer.push(PathBuf::from("/b/target"), start);
+        debouncer.push(PathBuf::from("/a/target"), start);
+        debouncer.push(PathBuf::from("/b/target"), start);
+
+        assert_eq!(
+            debouncer.drain_ready(start + Duration::from_millis(100)),
+            vec![PathBuf::from("/a/target"), PathBuf::from("/b/target")]
+        );
+    }
+}
@@ -10,6 +10,17 @@ fn veiled() -> (Command, TempDir) {
     (cmd, dir)
 }
 
+fn write_config(dir: &TempDir, search_path: &std::path::Path) {
+    std::fs::write(
+        dir.path().join("config.toml"),
+        format!(
+            "search_paths = [\"{}\"]\nignore_paths = []\nauto_update = false\n",
+            search_path.display()
+        ),
+    )
+    .unwrap();
+}
+
 // -- help and version --
 
 #[test]
@@ -25,8 +36,11 @@ fn help_displays_all_commands() {
         .stdout(predicate::str::contains("reset"))
         .stdout(predicate::str::contains("add"))
         .stdout(predicate::str::contains("remove"))
+        .stdout(predicate::str::contains("prune"))
         .stdout(predicate::str::contains("status"))
-        .stdout(predicate::str::contains("update"));
+        .stdout(predicate::str::contains("update"))
+        .stdout(predicate::str::contains("verify"))
+        .stdout(predicate::str::contains("reapply"));
 }
 
 #[test]
@@ -80,7 +94,77 @@ fn add_help_shows_path_argument() {
     cmd.args(["add", "--help"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("<PATH>").or(predicate::str::contains("path")));
+        .stdout(predicate::str::contains("<PATHS>").or(predicate::str::contains("path")));
+}
+
+#[test]
+fn add_fails_when_every_path_fails() {
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["add", "/nonexistent/one", "/nonexistent/two"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "/nonexistent/one: no such directory",
+        ))
+        .stderr(predicate::str::contains(
+            "/nonexistent/two: no such directory",
+        ))
+        .stderr(predicate::str::contains("failed to add 2 paths"));
+}
+
+#[test]
+fn add_force_records_nonexistent_path_as_pending() {
+    let (mut cmd, dir) = veiled();
+    cmd.args(["add", "--force", "/nonexistent/build/../dist"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added: /nonexistent/dist"))
+        .stderr(predicate::str::contains("does not exist yet"));
+
+    let config = std::fs::read_to_string(dir.path().join("config.toml")).unwrap();
+    assert!(config.contains("\"/nonexistent/dist\""));
+    let registry = std::fs::read_to_string(dir.path().join("registry.json")).unwrap_or_default();
+    assert!(!registry.contains("/nonexistent/dist"));
+}
+
+#[test]
+fn add_force_pending_path_can_be_removed() {
+    let (mut cmd, dir) = veiled();
+    cmd.args(["add", "--force", "/nonexistent/out"])
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("veiled")
+        .env("VEILED_CONFIG_DIR", dir.path())
+        .args(["remove", "/nonexistent/out"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed: /nonexistent/out"));
+
+    let config = std::fs::read_to_string(dir.path().join("config.toml")).unwrap();
+    assert!(!config.contains("/nonexistent/out"));
+}
+
+#[test]
+fn add_force_still_rejects_files() {
+    let file = NamedTempFile::new().unwrap();
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["add", "--force", file.path().to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not a directory"));
+}
+
+#[test]
+#[cfg(target_os = "macos")]
+fn add_continues_past_invalid_paths() {
+    let valid = TempDir::new().unwrap();
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["add", "/nonexistent/path", valid.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added:"))
+        .stderr(predicate::str::contains("no such directory"));
 }
 
 #[test]
@@ -125,182 +209,2454 @@ fn remove_help_shows_path_argument() {
     cmd.args(["remove", "--help"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("<PATH>").or(predicate::str::contains("path")));
+        .stdout(predicate::str::contains("[PATHS]"))
+        .stdout(predicate::str::contains("--all-extra"));
 }
 
-// -- list command --
+#[test]
+fn remove_accepts_multiple_paths() {
+    let (mut cmd, dir) = veiled();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        r#"{"paths": ["/nonexistent/one", "/nonexistent/two", "/nonexistent/three"]}"#,
+    )
+    .unwrap();
+
+    cmd.args([
+        "remove",
+        "/nonexistent/one",
+        "/nonexistent/two",
+        "/nonexistent/other",
+    ])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("Removed: /nonexistent/one"))
+    .stdout(predicate::str::contains("Removed: /nonexistent/two"))
+    .stderr(predicate::str::contains(
+        "/nonexistent/other: not managed by veiled",
+    ));
+
+    let registry = std::fs::read_to_string(dir.path().join("registry.json")).unwrap();
+    assert!(registry.contains("/nonexistent/three"));
+    assert!(!registry.contains("/nonexistent/one"));
+}
 
 #[test]
-fn list_exits_successfully() {
+fn remove_keep_registry_keeps_entry() {
+    let managed = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        serde_json::json!({ "paths": [managed.path()] }).to_string(),
+    )
+    .unwrap();
+
+    let assert = cmd
+        .args(["remove", "--keep-registry"])
+        .arg(managed.path())
+        .assert();
+    if cfg!(target_os = "macos") {
+        assert
+            .success()
+            .stdout(predicate::str::contains("Suspended:"));
+    } else {
+        assert
+            .failure()
+            .stderr(predicate::str::contains("failed to remove exclusion"));
+    }
+
+    let registry = std::fs::read_to_string(dir.path().join("registry.json")).unwrap();
+    assert!(registry.contains(&*managed.path().to_string_lossy()));
+    assert_eq!(
+        registry.contains("suspended_until"),
+        cfg!(target_os = "macos")
+    );
+}
+
+#[test]
+fn remove_keep_registry_rejects_unmanaged_path() {
     let (mut cmd, _dir) = veiled();
-    cmd.arg("list").assert().success();
+    cmd.args(["remove", "--keep-registry", "/nonexistent/other"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not managed by veiled"));
 }
 
-// -- status command --
+// -- suspend and resume --
 
 #[test]
-fn status_shows_daemon_state() {
-    let (mut cmd, _dir) = veiled();
-    cmd.arg("status")
+fn suspend_then_run_then_resume_cycle() {
+    let projects = TempDir::new().unwrap();
+    let managed = projects.path().join("app/node_modules");
+    std::fs::create_dir_all(&managed).unwrap();
+    let (_, dir) = veiled();
+    write_config(&dir, projects.path());
+    std::fs::write(
+        dir.path().join("registry.json"),
+        serde_json::json!({ "paths": [managed] }).to_string(),
+    )
+    .unwrap();
+    let veiled_in = |args: &[&str]| {
+        let mut cmd = cargo_bin_cmd!("veiled");
+        cmd.env("VEILED_CONFIG_DIR", dir.path())
+            .env_remove("VEILED_DAEMON")
+            .args(args)
+            .arg(&managed);
+        cmd
+    };
+    let registry = || std::fs::read_to_string(dir.path().join("registry.json")).unwrap();
+    let suspended = || {
+        serde_json::from_str::<serde_json::Value>(&registry()).unwrap()["paths"][0]["suspended"]
+            == true
+    };
+
+    let suspend = veiled_in(&["suspend"]).assert();
+    if cfg!(target_os = "macos") {
+        suspend
+            .success()
+            .stdout(predicate::str::contains("Suspended:"));
+    } else {
+        // Time Machine xattrs cannot be changed here; suspend by hand.
+        suspend.failure();
+        std::fs::write(
+            dir.path().join("registry.json"),
+            serde_json::json!({ "paths": [{ "path": managed, "suspended": true }] }).to_string(),
+        )
+        .unwrap();
+    }
+    assert!(suspended());
+
+    // run neither re-applies nor re-adds the suspended path.
+    cargo_bin_cmd!("veiled")
+        .env("VEILED_CONFIG_DIR", dir.path())
+        .env_remove("VEILED_DAEMON")
+        .args(["run", "--force-verify", "--format", "json"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Daemon:"));
+        .stdout(predicate::str::contains("\"re_applied\":0"))
+        .stdout(predicate::str::contains("\"added\":0"))
+        .stderr(predicate::str::contains("re-apply failed").not());
+    assert!(suspended());
+
+    let resume = veiled_in(&["resume"]).assert();
+    if cfg!(target_os = "macos") {
+        resume
+            .success()
+            .stdout(predicate::str::contains("Resumed:"));
+        assert!(!registry().contains("suspended"));
+    } else {
+        resume
+            .failure()
+            .stderr(predicate::str::contains("failed to set exclusion"));
+    }
 }
 
 #[test]
-fn status_shows_exclusion_info() {
-    let (mut cmd, _dir) = veiled();
-    cmd.arg("status").assert().success().stdout(
-        predicate::str::contains("excluded by veiled")
-            .or(predicate::str::contains("No exclusions")),
-    );
+fn resume_rejects_path_that_is_not_suspended() {
+    let (mut cmd, dir) = veiled();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        r#"{"paths": ["/nonexistent/active"]}"#,
+    )
+    .unwrap();
+
+    cmd.args(["resume", "/nonexistent/active"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "/nonexistent/active: not suspended",
+        ));
 }
 
 #[test]
-fn status_refresh_flag_accepted() {
-    let (mut cmd, _dir) = veiled();
-    cmd.args(["status", "--refresh"]).assert().success();
+fn remove_warns_about_pre_existing_exclusion() {
+    let (mut cmd, dir) = veiled();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        r#"{"paths": [{"path": "/nonexistent/sticky", "pre_existing": true}, "/nonexistent/own"]}"#,
+    )
+    .unwrap();
+
+    cmd.args(["remove", "/nonexistent/sticky", "/nonexistent/own"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "/nonexistent/sticky was excluded before veiled managed it",
+        ))
+        .stderr(predicate::str::contains("/nonexistent/own was excluded").not());
 }
 
 #[test]
-fn status_help_shows_refresh_flag() {
-    let (mut cmd, _dir) = veiled();
-    cmd.args(["status", "--help"])
+fn remove_all_extra_keeps_discovered_paths() {
+    let (mut cmd, dir) = veiled();
+    std::fs::write(
+        dir.path().join("config.toml"),
+        "extra_exclusions = [\"/nonexistent/custom\"]\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        r#"{"paths": ["/nonexistent/custom", "/nonexistent/app/node_modules"]}"#,
+    )
+    .unwrap();
+
+    cmd.args(["remove", "--all-extra"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("--refresh"));
-}
+        .stdout(predicate::str::contains("Removed: /nonexistent/custom"));
 
-// -- reset command --
+    let registry = std::fs::read_to_string(dir.path().join("registry.json")).unwrap();
+    assert!(registry.contains("/nonexistent/app/node_modules"));
+    assert!(!registry.contains("/nonexistent/custom"));
+
+    let config = std::fs::read_to_string(dir.path().join("config.toml")).unwrap();
+    assert!(!config.contains("/nonexistent/custom"));
+}
 
 #[test]
-fn reset_aborts_on_decline() {
+fn remove_all_extra_conflicts_with_paths() {
     let (mut cmd, _dir) = veiled();
-    cmd.arg("reset").write_stdin("n\n").assert().success();
+    cmd.args(["remove", "--all-extra", "/some/path"])
+        .assert()
+        .failure();
 }
 
 #[test]
-fn reset_help_shows_yes_flag() {
-    let (mut cmd, _dir) = veiled();
-    cmd.args(["reset", "--help"])
+fn remove_name_removes_only_that_artifact_type() {
+    let (mut cmd, dir) = veiled();
+    // Paths that no longer exist skip tmutil, so this runs on any platform.
+    std::fs::write(
+        dir.path().join("registry.json"),
+        serde_json::json!({ "paths": [
+            "/nonexistent/app/target",
+            "/nonexistent/api/target",
+            "/nonexistent/app/node_modules",
+            "/nonexistent/web/node_modules",
+        ] })
+        .to_string(),
+    )
+    .unwrap();
+
+    cmd.args(["remove", "--name", "target", "--yes"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("--yes"));
+        .stdout(predicate::str::contains("Removed: 2 target exclusions"));
+
+    let registry = std::fs::read_to_string(dir.path().join("registry.json")).unwrap();
+    assert!(!registry.contains("/target"));
+    assert!(registry.contains("/nonexistent/app/node_modules"));
+    assert!(registry.contains("/nonexistent/web/node_modules"));
 }
 
-// -- start command --
+#[test]
+fn remove_name_needs_confirmation_without_yes() {
+    let (mut cmd, dir) = veiled();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        serde_json::json!({ "paths": ["/nonexistent/app/target"] }).to_string(),
+    )
+    .unwrap();
+
+    cmd.args(["remove", "--name", "target"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("stdin is not a terminal"));
+
+    let registry = std::fs::read_to_string(dir.path().join("registry.json")).unwrap();
+    assert!(registry.contains("/nonexistent/app/target"));
+}
 
 #[test]
-fn start_help_shows_description() {
+fn remove_name_conflicts_with_paths() {
     let (mut cmd, _dir) = veiled();
-    cmd.args(["start", "--help"])
+    cmd.args(["remove", "--name", "target", "/some/path"])
         .assert()
-        .success()
-        .stdout(predicate::str::is_empty().not());
+        .failure();
 }
 
-// -- stop command --
+// -- run command --
 
 #[test]
-fn stop_help_shows_description() {
-    let (mut cmd, _dir) = veiled();
-    cmd.args(["stop", "--help"])
+fn run_in_daemon_context_prints_single_json_line() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, projects.path());
+
+    let output = cmd.arg("run").env("VEILED_DAEMON", "1").output().unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 1, "unexpected stdout: {stdout}");
+    assert!(!stdout.contains('\r'));
+    assert!(!String::from_utf8_lossy(&output.stderr).contains('\r'));
+
+    let summary: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+    assert_eq!(summary["added"], 0);
+    assert_eq!(summary["total_managed"], 0);
+    assert!(summary["timestamp"].as_str().unwrap().ends_with('Z'));
+}
+
+#[test]
+fn run_in_daemon_context_rotates_oversized_logs() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, projects.path());
+    std::fs::write(dir.path().join("stdout.log"), vec![b'x'; 1024 * 1024]).unwrap();
+    std::fs::write(dir.path().join("stderr.log"), "small").unwrap();
+
+    cmd.arg("run").env("VEILED_DAEMON", "1").assert().success();
+
+    assert!(!dir.path().join("stdout.log").exists());
+    assert!(dir.path().join("stdout.log.1").exists());
+    assert!(dir.path().join("stderr.log").exists());
+    assert!(!dir.path().join("stderr.log.1").exists());
+}
+
+#[test]
+fn run_quiet_prints_nothing() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, projects.path());
+
+    cmd.args(["run", "--quiet"])
+        .env_remove("VEILED_DAEMON")
         .assert()
         .success()
-        .stdout(predicate::str::contains("Deactivate daemon"));
+        .stdout(predicate::str::is_empty());
 }
 
-// -- update command --
+#[test]
+fn run_format_json_with_empty_search_path() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, projects.path());
+
+    let output = cmd
+        .args(["run", "--format", "json"])
+        .env_remove("VEILED_DAEMON")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        summary,
+        serde_json::json!({
+            "timestamp": summary["timestamp"],
+            "re_applied": 0,
+            "added": 0,
+            "total_managed": 0,
+            "saved_bytes": null,
+            "added_paths": [],
+        })
+    );
+}
+
+fn write_oversized_batch(dir: &TempDir, projects: &TempDir, threshold: &str) {
+    for project in ["a", "b"] {
+        let modules = projects.path().join(project).join("node_modules");
+        std::fs::create_dir_all(&modules).unwrap();
+        std::fs::write(modules.join("index.js"), vec![b'x'; 4096]).unwrap();
+    }
+    std::fs::write(
+        dir.path().join("config.toml"),
+        format!(
+            "search_paths = [\"{}\"]\nignore_paths = []\nauto_update = false\n{threshold}\n",
+            projects.path().display()
+        ),
+    )
+    .unwrap();
+}
 
 #[test]
-fn update_help_shows_description() {
-    let (mut cmd, _dir) = veiled();
-    cmd.args(["update", "--help"])
+fn run_skips_batch_above_warn_threshold_count() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    write_oversized_batch(&dir, &projects, "warn_threshold_count = 1");
+
+    let output = cmd
+        .args(["run", "--format", "json"])
+        .env_remove("VEILED_DAEMON")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("would newly exclude 2 directories"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("--force"), "{stderr}");
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(summary["added"], 0);
+    let registry = std::fs::read_to_string(dir.path().join("registry.json")).unwrap_or_default();
+    assert!(!registry.contains("node_modules"));
+}
+
+#[test]
+fn run_skips_batch_above_warn_threshold_bytes() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    write_oversized_batch(&dir, &projects, "warn_threshold_bytes = \"4KB\"");
+
+    cmd.args(["run", "--format", "json"])
+        .env_remove("VEILED_DAEMON")
         .assert()
         .success()
-        .stdout(predicate::str::contains(
-            "Update binary to the latest version",
-        ));
+        .stderr(predicate::str::contains("warn_threshold_bytes"));
 }
 
 #[test]
-fn update_displays_current_version() {
-    // update will fail (no releases / network) but should print the current version first
-    let (mut cmd, _dir) = veiled();
-    cmd.arg("update")
+fn run_force_bypasses_warn_threshold() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    write_oversized_batch(&dir, &projects, "warn_threshold_count = 1");
+
+    cmd.args(["run", "--force", "--format", "json"])
+        .env_remove("VEILED_DAEMON")
         .assert()
-        .stdout(predicate::str::contains(env!("CARGO_PKG_VERSION")));
+        .stderr(predicate::str::contains("guardrail").not());
 }
 
-// -- verbose flag --
+#[test]
+fn run_reports_paths_that_could_not_be_excluded() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    write_oversized_batch(&dir, &projects, "");
+
+    let output = cmd
+        .args(["run", "--format", "json"])
+        .env_remove("VEILED_DAEMON")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    // Without Time Machine xattr support every exclusion fails, so both
+    // directories are reported and neither is registered.
+    if !cfg!(target_os = "macos") {
+        assert_eq!(summary["added"], 0);
+        assert_eq!(summary["failed_paths"].as_array().unwrap().len(), 2);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("retrying individually"));
+    }
+}
 
 #[test]
-fn verbose_flag_accepted_before_subcommand() {
-    let (mut cmd, _dir) = veiled();
-    cmd.args(["--verbose", "list"]).assert().success();
+fn run_summary_only_collapses_failure_warnings() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    write_oversized_batch(&dir, &projects, "");
+
+    let output = cmd
+        .args(["run", "--summary-only"])
+        .env_remove("VEILED_DAEMON")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    if !cfg!(target_os = "macos") {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("2 exclusions failed; run with --verbose for details"));
+        assert!(!stderr.contains("retrying individually"));
+        assert!(!stderr.contains("node_modules:"));
+    }
 }
 
 #[test]
-fn verbose_flag_accepted_with_status() {
-    let (mut cmd, _dir) = veiled();
-    cmd.args(["--verbose", "status"]).assert().success();
+fn run_offers_files_matching_exclude_file_globs() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    let image = projects.path().join("vm").join("ubuntu.iso");
+    std::fs::create_dir_all(image.parent().unwrap()).unwrap();
+    std::fs::write(&image, vec![0u8; 4096]).unwrap();
+    std::fs::write(projects.path().join("vm").join("notes.txt"), "notes").unwrap();
+    std::fs::write(
+        dir.path().join("config.toml"),
+        format!(
+            "search_paths = [\"{}\"]\nignore_paths = []\nauto_update = false\nexclude_file_globs = [\"*.iso\"]\n",
+            projects.path().display()
+        ),
+    )
+    .unwrap();
+
+    let output = cmd
+        .args(["run", "--format", "json"])
+        .env_remove("VEILED_DAEMON")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    let image = image.to_string_lossy();
+    if cfg!(target_os = "macos") {
+        assert_eq!(summary["added"], 1);
+    } else {
+        assert_eq!(summary["failed_paths"], serde_json::json!([image]));
+    }
 }
 
 #[test]
-fn verbose_flag_shown_in_help() {
-    let (mut cmd, _dir) = veiled();
-    cmd.arg("--help")
+fn run_drops_registry_entries_nested_in_another() {
+    let projects = TempDir::new().unwrap();
+    let app = projects.path().join("app");
+    let modules = app.join("node_modules");
+    std::fs::create_dir_all(&modules).unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, projects.path());
+    std::fs::write(
+        dir.path().join("registry.json"),
+        serde_json::json!({ "paths": [app, modules] }).to_string(),
+    )
+    .unwrap();
+
+    cmd.args(["run", "--format", "json"])
+        .env_remove("VEILED_DAEMON")
+        .assert()
+        .success();
+
+    // The child's own exclusion is cleared before it is dropped. Without
+    // xattr support that fails, so the entry stays tracked for a later run.
+    let registry = std::fs::read_to_string(dir.path().join("registry.json")).unwrap();
+    assert!(registry.contains(&*app.to_string_lossy()));
+    assert_eq!(
+        registry.contains("node_modules"),
+        !cfg!(target_os = "macos")
+    );
+}
+
+#[test]
+fn run_skip_if_recent_skips_after_a_recent_full_run() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, projects.path());
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        serde_json::json!({ "paths": [], "last_full_run": now }).to_string(),
+    )
+    .unwrap();
+
+    cmd.args(["run", "--skip-if-recent", "1h"])
+        .env_remove("VEILED_DAEMON")
         .assert()
         .success()
-        .stdout(predicate::str::contains("--verbose"));
+        .stdout(predicate::str::contains("Scanned recently, skipping"));
+
+    cargo_bin_cmd!("veiled")
+        .env("VEILED_CONFIG_DIR", dir.path())
+        .env_remove("VEILED_DAEMON")
+        .args(["run", "--skip-if-recent", "1h", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Scanned recently").not());
 }
 
-// -- FDA warning --
+#[test]
+fn run_skip_if_recent_skips_before_the_update_check() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, projects.path());
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        serde_json::json!({ "paths": [], "last_full_run": now }).to_string(),
+    )
+    .unwrap();
+
+    cmd.args([
+        "run",
+        "--skip-if-recent",
+        "1h",
+        "--auto-update",
+        "--verbose",
+    ])
+    .env("VEILED_DAEMON", "1")
+    .assert()
+    .success()
+    .stdout(predicate::str::diff("{\"skipped\":\"recent\"}\n"))
+    .stderr(predicate::str::contains("update").not());
+
+    let registry = std::fs::read_to_string(dir.path().join("registry.json")).unwrap();
+    assert!(!registry.contains("last_update_check"));
+}
 
 #[test]
-fn status_fda_warning_on_stderr_if_present() {
-    let (mut cmd, _dir) = veiled();
-    let output = cmd.args(["status"]).output().unwrap();
-    let stderr = String::from_utf8_lossy(&output.stderr);
+fn run_records_last_full_run() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, projects.path());
 
-    // FDA check may or may not fail depending on environment;
-    // if there is stderr output, it must contain the expected warning text
-    if !stderr.is_empty() {
-        assert!(
-            stderr.contains("Full Disk Access may be required"),
-            "unexpected stderr: {stderr}"
-        );
-    }
+    cmd.args(["run", "--quiet"])
+        .env_remove("VEILED_DAEMON")
+        .assert()
+        .success();
+
+    let registry = std::fs::read_to_string(dir.path().join("registry.json")).unwrap();
+    assert!(registry.contains("last_full_run"));
 }
 
 #[test]
-fn verbose_status_shows_fda_detail_if_warning() {
-    let (mut cmd, _dir) = veiled();
-    let output = cmd.args(["--verbose", "status"]).output().unwrap();
-    let stderr = String::from_utf8_lossy(&output.stderr);
+fn run_interactive_requires_a_terminal() {
+    let projects = TempDir::new().unwrap();
+    std::fs::create_dir_all(projects.path().join("app/node_modules")).unwrap();
+    std::fs::write(projects.path().join("app/package.json"), "{}").unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, projects.path());
 
-    // When verbose + FDA warning, both "warning:" and "detail:" lines should appear
-    if stderr.contains("Full Disk Access may be required") {
-        assert!(
-            stderr.contains("detail:"),
-            "verbose mode should include detail line: {stderr}"
-        );
+    cmd.args(["run", "--interactive"])
+        .env_remove("VEILED_DAEMON")
+        .write_stdin("y\nn\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--interactive needs a terminal"));
+
+    assert!(!dir.path().join("registry.json").exists());
+}
+
+#[test]
+fn run_interactive_with_assume_yes_accepts_every_candidate() {
+    let projects = TempDir::new().unwrap();
+    let modules = projects.path().join("app/node_modules");
+    std::fs::create_dir_all(&modules).unwrap();
+    std::fs::write(projects.path().join("app/package.json"), "{}").unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, projects.path());
+
+    let assert = cmd
+        .args(["run", "--interactive", "--assume-yes", "--format", "json"])
+        .env_remove("VEILED_DAEMON")
+        .assert()
+        .success();
+
+    if cfg!(target_os = "macos") {
+        assert.stdout(predicate::str::contains("\"added\":1"));
+    } else {
+        // The exclusion itself fails here, but the candidate was accepted.
+        assert.stdout(predicate::str::contains(&*modules.to_string_lossy()));
     }
 }
 
 #[test]
-fn start_help_shows_install_description() {
-    let (mut cmd, _dir) = veiled();
-    cmd.args(["start", "--help"])
+fn run_stats_reports_scan_metrics() {
+    let projects = TempDir::new().unwrap();
+    std::fs::create_dir_all(projects.path().join("app/src")).unwrap();
+    std::fs::create_dir(projects.path().join(".local")).unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, projects.path());
+
+    let output = cmd
+        .args(["run", "--stats", "--format", "json"])
+        .env_remove("VEILED_DAEMON")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(summary["stats"]["dirs_visited"], 3);
+    assert_eq!(summary["stats"]["skipped_hidden"], 1);
+
+    cargo_bin_cmd!("veiled")
+        .env("VEILED_CONFIG_DIR", dir.path())
+        .env_remove("VEILED_DAEMON")
+        .args(["run", "--stats"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Install binary"));
+        .stdout(predicate::str::contains("Directories visited: 3"));
 }
 
-// -- unknown command --
+#[test]
+fn run_only_scopes_discovery_to_subtree() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    // A one-byte guardrail reports how many directories discovery found
+    // without touching Time Machine.
+    write_oversized_batch(&dir, &projects, "warn_threshold_bytes = 1");
+
+    cmd.args(["run", "--format", "json", "--only"])
+        .arg(projects.path().join("a"))
+        .env_remove("VEILED_DAEMON")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("would newly exclude 1 directory "));
+}
 
 #[test]
-fn unknown_command_exits_with_error() {
-    let (mut cmd, _dir) = veiled();
-    cmd.arg("foobar").assert().failure();
+fn run_include_path_scans_directory_outside_search_paths() {
+    let projects = TempDir::new().unwrap();
+    std::fs::create_dir_all(projects.path().join("app/node_modules")).unwrap();
+    std::fs::write(
+        projects.path().join("app/node_modules/index.js"),
+        vec![b'x'; 4096],
+    )
+    .unwrap();
+    let (mut cmd, dir) = veiled();
+    let config =
+        "search_paths = []\nignore_paths = []\nauto_update = false\nwarn_threshold_bytes = 1\n";
+    std::fs::write(dir.path().join("config.toml"), config).unwrap();
+
+    // The one-byte guardrail reports what discovery found without touching
+    // Time Machine.
+    cmd.args(["run", "--include-path"])
+        .arg(projects.path())
+        .env_remove("VEILED_DAEMON")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("would newly exclude 1 directory "));
+
+    let saved = std::fs::read_to_string(dir.path().join("config.toml")).unwrap();
+    assert_eq!(saved, config);
+}
+
+#[test]
+fn run_include_path_rejects_missing_directory() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, projects.path());
+
+    cmd.args(["run", "--include-path", "/nonexistent/veiled"])
+        .env_remove("VEILED_DAEMON")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no such directory"));
+}
+
+#[test]
+fn run_only_rejects_path_outside_search_paths() {
+    let projects = TempDir::new().unwrap();
+    let elsewhere = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, projects.path());
+
+    cmd.args(["run", "--only"])
+        .arg(elsewhere.path())
+        .env_remove("VEILED_DAEMON")
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains(
+            "not inside any configured search path",
+        ));
+}
+
+#[test]
+fn run_verbose_reports_phase_timings() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, projects.path());
+
+    let output = cmd
+        .args(["--verbose", "run"])
+        .env_remove("VEILED_DAEMON")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    for phase in ["prune", "reapply", "scan", "reconcile", "total"] {
+        assert!(
+            stderr.contains(&format!("{phase} took ")),
+            "missing {phase} timing in: {stderr}"
+        );
+    }
+}
+
+#[test]
+fn run_post_run_command_receives_summary_env() {
+    let projects = TempDir::new().unwrap();
+    let out = TempDir::new().unwrap();
+    let hook_output = out.path().join("hook.txt");
+    let (mut cmd, dir) = veiled();
+    std::fs::write(
+        dir.path().join("config.toml"),
+        format!(
+            "search_paths = [\"{}\"]\nauto_update = false\npost_run_command = \"echo added=$VEILED_ADDED saved=$VEILED_SAVED_BYTES total=$VEILED_TOTAL_MANAGED > {}\"\n",
+            projects.path().display(),
+            hook_output.display()
+        ),
+    )
+    .unwrap();
+
+    let output = cmd
+        .args(["run", "--format", "json"])
+        .env_remove("VEILED_DAEMON")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(&hook_output).unwrap(),
+        "added=0 saved=0 total=0\n"
+    );
+}
+
+#[test]
+fn run_post_run_command_failure_only_warns() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    std::fs::write(
+        dir.path().join("config.toml"),
+        format!(
+            "search_paths = [\"{}\"]\nauto_update = false\npost_run_command = \"exit 3\"\n",
+            projects.path().display()
+        ),
+    )
+    .unwrap();
+
+    cmd.arg("run")
+        .env_remove("VEILED_DAEMON")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("post_run_command failed"));
+}
+
+#[test]
+fn run_auto_update_flags_override_config() {
+    let projects = TempDir::new().unwrap();
+    let run = |auto_update: bool, flag: &str| {
+        let (mut cmd, dir) = veiled();
+        std::fs::write(
+            dir.path().join("config.toml"),
+            format!(
+                "search_paths = [\"{}\"]\nauto_update = {auto_update}\nupdate_api_base = \"https://127.0.0.1:1\"\n",
+                projects.path().display()
+            ),
+        )
+        .unwrap();
+        let output = cmd
+            .args(["--verbose", "run", flag])
+            .env_remove("VEILED_DAEMON")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+
+    assert!(!run(true, "--no-auto-update").contains("auto-update failed"));
+    assert!(run(false, "--auto-update").contains("auto-update failed"));
+}
+
+#[test]
+fn run_auto_update_flags_conflict() {
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["run", "--auto-update", "--no-auto-update"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn run_since_accepts_duration_and_rejects_garbage() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, projects.path());
+
+    cmd.args(["run", "--since", "24h", "--json"])
+        .env_remove("VEILED_DAEMON")
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("veiled")
+        .env("VEILED_CONFIG_DIR", dir.path())
+        .args(["run", "--since", "yesterday"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid duration"));
+}
+
+// -- scan command --
+
+#[test]
+fn scan_lists_found_directories_without_excluding_them() {
+    let projects = TempDir::new().unwrap();
+    let modules = projects.path().join("app/node_modules");
+    std::fs::create_dir_all(&modules).unwrap();
+    std::fs::write(modules.join("pkg.js"), vec![b'x'; 2048]).unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, projects.path());
+
+    cmd.arg("scan")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("app/node_modules"))
+        .stdout(predicate::str::contains("2.0 KiB"))
+        .stdout(predicate::str::contains("Total: 1 directory (2.0 KiB)"));
+
+    assert!(!dir.path().join("registry.json").exists());
+}
+
+#[test]
+fn scan_json_reports_paths_and_total() {
+    let projects = TempDir::new().unwrap();
+    let modules = projects.path().join("app/node_modules");
+    std::fs::create_dir_all(&modules).unwrap();
+    std::fs::write(modules.join("pkg.js"), vec![b'x'; 100]).unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, projects.path());
+
+    let output = cmd.args(["scan", "--json"]).output().unwrap();
+    assert!(output.status.success());
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        report,
+        serde_json::json!({
+            "paths": [{ "path": modules.to_string_lossy(), "size_bytes": 100 }],
+            "total_bytes": 100,
+        })
+    );
+}
+
+#[test]
+fn scan_include_path_lists_directories_outside_search_paths() {
+    let projects = TempDir::new().unwrap();
+    let elsewhere = TempDir::new().unwrap();
+    std::fs::create_dir_all(elsewhere.path().join("app/node_modules")).unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, projects.path());
+
+    let output = cmd
+        .args(["scan", "--json", "--include-path"])
+        .arg(elsewhere.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let paths = report["paths"].as_array().unwrap();
+    assert_eq!(paths.len(), 1);
+    assert!(
+        paths[0]["path"]
+            .as_str()
+            .unwrap()
+            .ends_with("app/node_modules")
+    );
+}
+
+// -- prune command --
+
+#[test]
+fn prune_removes_nonexistent_entries() {
+    let existing = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        serde_json::json!({
+            "paths": [existing.path(), "/nonexistent/project/node_modules"]
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    cmd.arg("prune")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pruned: 1 stale exclusion"));
+
+    let registry: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("registry.json")).unwrap())
+            .unwrap();
+    let paths = registry["paths"].as_array().unwrap();
+    assert_eq!(paths.len(), 1);
+    assert_eq!(paths[0]["path"], serde_json::json!(existing.path()));
+    assert_eq!(paths[0]["size_bytes"], 0);
+}
+
+#[test]
+fn prune_with_empty_registry_reports_nothing_stale() {
+    let (mut cmd, _dir) = veiled();
+    cmd.arg("prune")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No stale exclusions"));
+}
+
+// -- list command --
+
+#[test]
+fn list_exits_successfully() {
+    let (mut cmd, _dir) = veiled();
+    cmd.arg("list").assert().success();
+}
+
+#[test]
+fn list_json_emits_paths_with_raw_sizes() {
+    let managed = TempDir::new().unwrap();
+    std::fs::write(managed.path().join("pkg.js"), "hello").unwrap();
+    let (mut cmd, dir) = veiled();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        serde_json::json!({ "paths": [managed.path()] }).to_string(),
+    )
+    .unwrap();
+
+    let output = cmd.args(["list", "--json"]).output().unwrap();
+    assert!(output.status.success());
+
+    let entries: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(entries[0]["path"], managed.path().to_str().unwrap());
+    assert_eq!(entries[0]["size_bytes"], 5);
+}
+
+#[test]
+fn list_json_with_empty_registry_is_empty_array() {
+    let (mut cmd, _dir) = veiled();
+    let output = cmd.args(["--json", "list"]).output().unwrap();
+    assert!(output.status.success());
+
+    let entries: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(entries, serde_json::json!([]));
+}
+
+#[test]
+fn list_verify_annotates_drift_missing_and_suspended_paths() {
+    let managed = TempDir::new().unwrap();
+    let held = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        serde_json::json!({ "paths": [
+            managed.path(),
+            "/nonexistent/gone/target",
+            { "path": held.path(), "suspended": true },
+        ] })
+        .to_string(),
+    )
+    .unwrap();
+
+    let output = cmd.args(["list", "--verify"]).output().unwrap();
+    assert!(output.status.success());
+    let text = String::from_utf8_lossy(&output.stdout);
+    let line = |needle: &str| {
+        text.lines()
+            .find(|l| l.contains(needle))
+            .unwrap_or_else(|| panic!("{needle} missing from {text}"))
+            .to_string()
+    };
+
+    // The temp dir was never excluded, so tmutil reports it as drifted.
+    assert!(line(managed.path().to_str().unwrap()).ends_with("drift"));
+    assert!(line("/nonexistent/gone/target").ends_with("missing"));
+    // Suspended paths are meant to be backed up, so they are not drift.
+    assert!(line(held.path().to_str().unwrap()).ends_with("suspended"));
+
+    let registry = std::fs::read_to_string(dir.path().join("registry.json")).unwrap();
+    assert!(registry.contains("/nonexistent/gone/target"));
+}
+
+#[test]
+fn list_verify_json_includes_state() {
+    let (mut cmd, dir) = veiled();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        r#"{"paths":["/nonexistent/gone/target"]}"#,
+    )
+    .unwrap();
+
+    let output = cmd.args(["list", "--verify", "--json"]).output().unwrap();
+    let entries: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(entries[0]["state"], "missing");
+}
+
+#[test]
+fn reapply_with_empty_registry_reports_nothing_managed() {
+    let (mut cmd, _dir) = veiled();
+    cmd.arg("reapply")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No exclusions managed"));
+}
+
+#[test]
+fn reapply_restores_lost_exclusion() {
+    let managed = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        serde_json::json!({ "paths": [managed.path(), "/nonexistent/gone/target"] }).to_string(),
+    )
+    .unwrap();
+
+    // The temp dir was never excluded, so reapply has to restore it; the
+    // missing path is skipped rather than treated as lost.
+    let assert = cmd.arg("reapply").assert();
+    if cfg!(target_os = "macos") {
+        assert
+            .success()
+            .stdout(predicate::str::contains("Restored: 1 lost exclusion"));
+    } else {
+        assert.failure().stderr(predicate::str::contains(
+            "could not restore 1 lost exclusion",
+        ));
+    }
+}
+
+fn write_sized_registry(dir: &TempDir) {
+    std::fs::write(
+        dir.path().join("registry.json"),
+        serde_json::json!({
+            "paths": [
+                { "path": "/nonexistent/b/node_modules", "size_bytes": 10 },
+                { "path": "/nonexistent/c/target", "size_bytes": 5_000_000 },
+                { "path": "/nonexistent/a/.venv", "size_bytes": 2048 },
+            ]
+        })
+        .to_string(),
+    )
+    .unwrap();
+}
+
+fn line_order(stdout: &[u8], needles: &[&str]) -> Vec<usize> {
+    let text = String::from_utf8_lossy(stdout);
+    needles
+        .iter()
+        .map(|n| {
+            text.find(n)
+                .unwrap_or_else(|| panic!("{n} missing from {text}"))
+        })
+        .collect()
+}
+
+#[test]
+fn list_path_style_controls_path_rendering() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("config.toml"),
+        "search_paths = [\"/nonexistent/projects\"]\nauto_update = false\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        r#"{"paths":["/nonexistent/projects/app/node_modules","/nonexistent/other/target"]}"#,
+    )
+    .unwrap();
+    let list = |style: &str| {
+        let output = cargo_bin_cmd!("veiled")
+            .env("VEILED_CONFIG_DIR", dir.path())
+            .args(["list", "--path-style", style])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    assert_eq!(
+        list("plain"),
+        "/nonexistent/other/target\n/nonexistent/projects/app/node_modules\n"
+    );
+    assert_eq!(
+        list("relative"),
+        "/nonexistent/other/target\napp/node_modules\n"
+    );
+    assert!(list("pretty").contains("node_modules"));
+}
+
+#[test]
+fn list_sort_size_orders_largest_first_with_sizes() {
+    let (mut cmd, dir) = veiled();
+    write_sized_registry(&dir);
+
+    let output = cmd.args(["list", "--sort", "size"]).output().unwrap();
+    assert!(output.status.success());
+
+    let positions = line_order(&output.stdout, &["target", ".venv", "node_modules"]);
+    assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    let text = String::from_utf8_lossy(&output.stdout);
+    assert!(text.contains("4.8 MiB"));
+    assert!(text.contains("2.0 KiB"));
+}
+
+#[test]
+fn list_sorts_by_path_by_default() {
+    let (mut cmd, dir) = veiled();
+    write_sized_registry(&dir);
+
+    let output = cmd.arg("list").output().unwrap();
+    assert!(output.status.success());
+
+    let positions = line_order(&output.stdout, &["/a/", "/b/", "/c/"]);
+    assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    assert!(!String::from_utf8_lossy(&output.stdout).contains(" MiB"));
+}
+
+// -- status command --
+
+#[test]
+fn status_shows_daemon_state() {
+    let (mut cmd, _dir) = veiled();
+    cmd.arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Daemon:"));
+}
+
+#[test]
+fn status_shows_exclusion_info() {
+    let (mut cmd, _dir) = veiled();
+    cmd.arg("status").assert().success().stdout(
+        predicate::str::contains("excluded by veiled")
+            .or(predicate::str::contains("No exclusions")),
+    );
+}
+
+#[test]
+fn status_uses_decimal_size_units_from_config() {
+    let (mut cmd, dir) = veiled();
+    std::fs::write(dir.path().join("config.toml"), "size_units = \"decimal\"\n").unwrap();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        r#"{"paths": ["/nonexistent/target"], "saved_bytes": 1500000}"#,
+    )
+    .unwrap();
+
+    cmd.arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(1.5 MB saved)"));
+}
+
+#[test]
+fn status_refresh_stale_only_recomputes_changed_dirs() {
+    let (mut cmd, dir) = veiled();
+    let cached = TempDir::new().unwrap();
+    let changed = TempDir::new().unwrap();
+    std::fs::write(changed.path().join("a.bin"), vec![0u8; 2048]).unwrap();
+    let mtime = std::fs::metadata(cached.path())
+        .unwrap()
+        .modified()
+        .unwrap()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        serde_json::json!({
+            "paths": [
+                { "path": cached.path(), "size_bytes": 1_048_576, "size_mtime": mtime },
+                { "path": changed.path(), "size_bytes": 1, "size_mtime": 0 },
+            ]
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    cmd.args(["status", "--refresh", "--stale-only"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Sizes: 1 recomputed, 1 cached"))
+        .stdout(predicate::str::contains("(1.0 MiB saved)"));
+}
+
+#[test]
+fn status_stale_only_requires_refresh() {
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["status", "--stale-only"]).assert().failure();
+}
+
+#[test]
+fn status_json_emits_counts() {
+    let (mut cmd, dir) = veiled();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        r#"{"paths": ["/Users/dev/app/node_modules"], "saved_bytes": 2048}"#,
+    )
+    .unwrap();
+
+    let output = cmd.args(["status", "--json"]).output().unwrap();
+    assert!(output.status.success());
+
+    let status: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(status["daemon_active"].is_boolean());
+    assert_eq!(status["managed_count"], 1);
+    assert_eq!(status["saved_bytes"], 2048);
+    assert!(status["last_full_run"].is_null());
+}
+
+#[test]
+fn status_shows_last_run_and_update_check() {
+    let (mut cmd, dir) = veiled();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        serde_json::json!({ "paths": [], "last_full_run": now - 6 * 3_600 - 60 }).to_string(),
+    )
+    .unwrap();
+
+    cmd.arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Last run: 6 hours ago"))
+        .stdout(predicate::str::contains("Last update check: never"));
+}
+
+#[test]
+fn status_top_lists_largest_exclusions() {
+    let (mut cmd, dir) = veiled();
+    write_sized_registry(&dir);
+
+    let output = cmd.args(["status", "--top", "2"]).output().unwrap();
+    assert!(output.status.success());
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    assert!(text.contains("3 paths are excluded by veiled"));
+    assert!(text.contains("Largest:"));
+    let positions = line_order(&output.stdout, &["4.8 MiB", "2.0 KiB"]);
+    assert!(positions[0] < positions[1]);
+    assert!(!text.contains("node_modules"));
+}
+
+#[test]
+fn status_by_type_reports_grouped_totals() {
+    let (mut cmd, dir) = veiled();
+    write_sized_registry(&dir);
+
+    let output = cmd
+        .args(["status", "--by-type", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let status: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        status["by_type"],
+        serde_json::json!([
+            { "name": "target", "size_bytes": 5_000_000, "count": 1 },
+            { "name": ".venv", "size_bytes": 2048, "count": 1 },
+            { "name": "node_modules", "size_bytes": 10, "count": 1 },
+        ])
+    );
+}
+
+#[test]
+fn status_verify_reports_drifted_exclusions() {
+    let managed = TempDir::new().unwrap();
+    let suspended = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        serde_json::json!({ "paths": [
+            managed.path(),
+            "/nonexistent/gone/target",
+            { "path": suspended.path(), "suspended": true },
+        ] })
+        .to_string(),
+    )
+    .unwrap();
+
+    // The temp dir was never excluded, so tmutil reports it as drifted;
+    // missing and suspended paths don't count.
+    cmd.args(["status", "--verify"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "1 exclusion drifted (run `veiled reapply`)",
+        ));
+}
+
+#[test]
+fn status_verify_json_includes_drift_count() {
+    let (mut cmd, dir) = veiled();
+    write_sized_registry(&dir);
+
+    let output = cmd.args(["status", "--json"]).output().unwrap();
+    let status: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(status.get("drifted").is_none());
+
+    let output = cargo_bin_cmd!("veiled")
+        .env("VEILED_CONFIG_DIR", dir.path())
+        .args(["status", "--verify", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let status: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(status["drifted"].is_u64());
+}
+
+#[test]
+fn status_top_zero_and_empty_registry_are_graceful() {
+    let (mut cmd, dir) = veiled();
+    write_sized_registry(&dir);
+    cmd.args(["status", "--top", "0"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Largest:").not());
+
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["status", "--top", "5"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No exclusions"));
+}
+
+#[test]
+fn status_refresh_flag_accepted() {
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["status", "--refresh"]).assert().success();
+}
+
+#[test]
+fn status_help_shows_refresh_flag() {
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["status", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--refresh"));
+}
+
+// -- reset command --
+
+#[test]
+fn reset_aborts_on_decline() {
+    let (mut cmd, _dir) = veiled();
+    cmd.arg("reset").write_stdin("n\n").assert().success();
+}
+
+#[test]
+fn reset_previews_size_and_paths_before_prompt() {
+    let (mut cmd, dir) = veiled();
+    let paths: Vec<serde_json::Value> = (1..=7)
+        .map(|i| serde_json::json!({ "path": format!("/nonexistent/p{i}/target"), "size_bytes": i * 1_048_576 }))
+        .collect();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        serde_json::json!({ "paths": paths }).to_string(),
+    )
+    .unwrap();
+
+    cmd.arg("reset")
+        .write_stdin("n\n")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "Resetting: 7 exclusions (28.0 MiB)",
+        ))
+        .stdout(predicate::str::contains("/nonexistent/p7/target"))
+        .stdout(predicate::str::contains("/nonexistent/p3/target"))
+        .stdout(predicate::str::contains("/nonexistent/p2/target").not())
+        .stdout(predicate::str::contains("... and 2 more"));
+
+    let registry = std::fs::read_to_string(dir.path().join("registry.json")).unwrap();
+    assert!(registry.contains("/nonexistent/p1/target"));
+}
+
+#[test]
+fn reset_dry_run_lists_everything_and_keeps_registry() {
+    let managed = TempDir::new().unwrap();
+    std::fs::write(managed.path().join("pkg.js"), vec![0u8; 2048]).unwrap();
+    let (mut cmd, dir) = veiled();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        serde_json::json!({
+            "paths": [
+                { "path": managed.path(), "size_bytes": 2048 },
+                { "path": "/nonexistent/gone/target", "size_bytes": 10 },
+            ]
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let before = std::fs::read_to_string(dir.path().join("registry.json")).unwrap();
+
+    cmd.args(["reset", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Would remove: 2 exclusions (2.0 KiB reclaimed)",
+        ))
+        .stdout(predicate::str::contains(managed.path().to_str().unwrap()))
+        .stdout(predicate::str::contains(
+            "missing  /nonexistent/gone/target",
+        ))
+        .stdout(predicate::str::contains("[y/N]").not());
+
+    let after = std::fs::read_to_string(dir.path().join("registry.json")).unwrap();
+    assert_eq!(before, after);
+}
+
+#[test]
+fn reset_yes_prints_preview_without_prompt() {
+    let (mut cmd, dir) = veiled();
+    write_sized_registry(&dir);
+
+    cmd.args(["reset", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Resetting: 3 exclusions"))
+        .stdout(predicate::str::contains("[y/N]").not())
+        .stdout(predicate::str::contains("Removed: 3 exclusions"));
+}
+
+#[test]
+fn reset_without_terminal_fails_instead_of_waiting() {
+    let (mut cmd, dir) = veiled();
+    write_sized_registry(&dir);
+
+    cmd.arg("reset")
+        .timeout(std::time::Duration::from_secs(10))
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("[y/N]").not())
+        .stderr(predicate::str::contains("--assume-yes"));
+
+    let registry = std::fs::read_to_string(dir.path().join("registry.json")).unwrap();
+    assert!(registry.contains("/nonexistent"));
+}
+
+#[test]
+fn uninstall_deletes_config_registry_and_logs() {
+    let home = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    for name in ["config.toml", "registry.json", "stdout.log", "stderr.log.1"] {
+        std::fs::write(dir.path().join(name), "").unwrap();
+    }
+    std::fs::write(dir.path().join("registry.json"), r#"{"paths":[]}"#).unwrap();
+
+    cmd.args(["uninstall", "--yes"])
+        .env("HOME", home.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Daemon is not running."))
+        .stdout(predicate::str::contains("uninstalled"));
+
+    assert!(!dir.path().exists());
+}
+
+#[test]
+fn uninstall_without_terminal_keeps_files() {
+    let home = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    std::fs::write(dir.path().join("config.toml"), "").unwrap();
+
+    cmd.arg("uninstall")
+        .env("HOME", home.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--assume-yes"));
+
+    assert!(dir.path().join("config.toml").exists());
+}
+
+#[test]
+fn reset_assume_yes_skips_prompt() {
+    let (mut cmd, dir) = veiled();
+    write_sized_registry(&dir);
+
+    cmd.args(["--assume-yes", "reset"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed: 3 exclusions"));
+}
+
+#[test]
+fn reset_help_shows_yes_flag() {
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["reset", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--yes"));
+}
+
+// -- verify command --
+
+#[test]
+fn verify_with_empty_registry_succeeds() {
+    let (mut cmd, _dir) = veiled();
+    cmd.arg("verify").assert().success();
+}
+
+#[test]
+fn verify_reports_drifted_path() {
+    // a registered directory without the exclusion attribute counts as drifted
+    let drifted = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        serde_json::json!({ "paths": [drifted.path()] }).to_string(),
+    )
+    .unwrap();
+
+    cmd.arg("verify")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is no longer excluded"))
+        .stderr(predicate::str::contains("1 of 1 exclusion drifted"));
+}
+
+// -- start command --
+
+#[test]
+fn start_help_shows_description() {
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["start", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty().not());
+}
+
+// -- stop command --
+
+#[test]
+fn stop_help_shows_description() {
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["stop", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deactivate daemon"));
+}
+
+// -- update command --
+
+#[test]
+fn update_help_shows_description() {
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["update", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Update binary to the latest version",
+        ));
+}
+
+#[test]
+fn update_displays_current_version() {
+    // update will fail (no releases / network) but should print the current version first
+    let (mut cmd, _dir) = veiled();
+    cmd.arg("update")
+        .assert()
+        .stdout(predicate::str::contains(env!("CARGO_PKG_VERSION")));
+}
+
+#[test]
+fn update_help_shows_check_flag() {
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["update", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--check"));
+}
+
+// -- verbose flag --
+
+#[test]
+fn verbose_flag_accepted_before_subcommand() {
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["--verbose", "list"]).assert().success();
+}
+
+#[test]
+fn verbose_flag_accepted_with_status() {
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["--verbose", "status"]).assert().success();
+}
+
+#[test]
+fn verbose_flag_shown_in_help() {
+    let (mut cmd, _dir) = veiled();
+    cmd.arg("--help")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--verbose"));
+}
+
+// -- quiet flag --
+
+#[test]
+fn quiet_list_with_empty_registry_prints_nothing() {
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["--quiet", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+}
+
+#[test]
+fn quiet_suppresses_status_lines_but_keeps_errors() {
+    let (mut cmd, dir) = veiled();
+    let projects = TempDir::new().unwrap();
+    write_config(&dir, std::path::Path::new("/nonexistent/root"));
+
+    cmd.args(["--quiet", "search-paths", "add"])
+        .arg(projects.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+
+    cargo_bin_cmd!("veiled")
+        .env("VEILED_CONFIG_DIR", dir.path())
+        .args(["--quiet", "search-paths", "remove", "/nonexistent/other"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("is not a search path"));
+}
+
+#[test]
+fn quiet_conflicts_with_verbose() {
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["--quiet", "--verbose", "list"])
+        .assert()
+        .failure();
+}
+
+// -- FDA warning --
+
+#[test]
+fn status_fda_warning_on_stderr_if_present() {
+    let (mut cmd, _dir) = veiled();
+    let output = cmd.args(["status"]).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // FDA check may or may not fail depending on environment;
+    // if there is stderr output, it must contain the expected warning text
+    if !stderr.is_empty() {
+        assert!(
+            stderr.contains("Full Disk Access may be required"),
+            "unexpected stderr: {stderr}"
+        );
+    }
+}
+
+#[test]
+fn verbose_status_shows_fda_detail_if_warning() {
+    let (mut cmd, _dir) = veiled();
+    let output = cmd.args(["--verbose", "status"]).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // When verbose + FDA warning, both "warning:" and "detail:" lines should appear
+    if stderr.contains("Full Disk Access may be required") {
+        assert!(
+            stderr.contains("detail:"),
+            "verbose mode should include detail line: {stderr}"
+        );
+    }
+}
+
+#[test]
+fn start_print_shows_plist_without_installing() {
+    let home = TempDir::new().unwrap();
+    let (mut cmd, _dir) = veiled();
+
+    cmd.env("HOME", home.path())
+        .args(["start", "--print"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("<key>ProgramArguments</key>"))
+        .stdout(predicate::str::contains("<string>run</string>"));
+
+    assert!(!home.path().join("Library/LaunchAgents").exists());
+}
+
+#[test]
+fn start_print_points_the_daemon_at_the_config_dir() {
+    let home = TempDir::new().unwrap();
+    let profile = TempDir::new().unwrap();
+    let (mut cmd, _dir) = veiled();
+
+    cmd.env("HOME", home.path())
+        .arg("--config-dir")
+        .arg(profile.path())
+        .args(["start", "--print"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "<string>--config-dir</string>\n        <string>{}</string>",
+            profile.path().display()
+        )));
+}
+
+#[test]
+fn start_print_points_the_daemon_at_the_config_file() {
+    let home = TempDir::new().unwrap();
+    let alternate = TempDir::new().unwrap();
+    let file = alternate.path().join("work.toml");
+    let (mut cmd, _dir) = veiled();
+
+    cmd.env("HOME", home.path())
+        .current_dir(alternate.path())
+        .args(["--config", "work.toml", "start", "--print"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "<string>--config</string>\n        <string>{}</string>",
+            file.display()
+        )));
+}
+
+#[test]
+fn start_help_shows_install_description() {
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["start", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Install binary"));
+}
+
+// -- export command --
+
+#[test]
+fn export_prints_versioned_document() {
+    let (mut cmd, dir) = veiled();
+    std::fs::write(
+        dir.path().join("config.toml"),
+        "search_paths = [\"~/Code\"]\nextra_exclusions = [\"/opt/cache\"]\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("registry.json"),
+        r#"{"paths": ["/opt/cache", "/srv/app/node_modules"]}"#,
+    )
+    .unwrap();
+
+    let output = cmd.arg("export").output().unwrap();
+    assert!(output.status.success());
+
+    let document: toml::Value = toml::from_str(&String::from_utf8(output.stdout).unwrap()).unwrap();
+    assert_eq!(document["schema_version"].as_integer(), Some(1));
+    assert_eq!(document["search_paths"][0].as_str(), Some("~/Code"));
+    assert_eq!(document["extra_exclusions"][0].as_str(), Some("/opt/cache"));
+    assert_eq!(document["paths"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn export_writes_to_output_file() {
+    let (mut cmd, dir) = veiled();
+    let target = dir.path().join("veiled-export.toml");
+
+    cmd.args(["export", "--output", target.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+
+    let content = std::fs::read_to_string(&target).unwrap();
+    assert!(content.contains("schema_version = 1"));
+}
+
+// -- import command --
+
+#[test]
+fn import_roundtrips_export_into_new_config_dir() {
+    let (mut export, source) = veiled();
+    std::fs::write(
+        source.path().join("config.toml"),
+        "search_paths = [\"~/Code\", \"/srv/work\"]\nignore_paths = [\"~/Library\"]\nextra_exclusions = [\"/opt/cache\"]\n",
+    )
+    .unwrap();
+    let file = source.path().join("export.toml");
+    export
+        .args(["export", "--output", file.to_str().unwrap()])
+        .assert()
+        .success();
+
+    let (mut import, target) = veiled();
+    std::fs::write(
+        target.path().join("config.toml"),
+        "search_paths = [\"~/Code\"]\nignore_paths = []\n",
+    )
+    .unwrap();
+    import
+        .args(["import", file.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported: 3 new config entries"));
+
+    let source_config: toml::Value =
+        toml::from_str(&std::fs::read_to_string(source.path().join("config.toml")).unwrap())
+            .unwrap();
+    let target_config: toml::Value =
+        toml::from_str(&std::fs::read_to_string(target.path().join("config.toml")).unwrap())
+            .unwrap();
+    for key in ["search_paths", "ignore_paths", "extra_exclusions"] {
+        assert_eq!(source_config[key], target_config[key], "{key} differs");
+    }
+}
+
+#[test]
+fn import_apply_skips_missing_paths() {
+    let (mut cmd, dir) = veiled();
+    let file = dir.path().join("export.toml");
+    std::fs::write(
+        &file,
+        "schema_version = 1\npaths = [\"/nonexistent/app/node_modules\"]\n",
+    )
+    .unwrap();
+
+    cmd.args(["import", "--apply", file.to_str().unwrap()])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("does not exist, skipping"))
+        .stdout(predicate::str::contains("Applied: 0 exclusions"));
+}
+
+#[test]
+fn import_rejects_newer_schema_version() {
+    let (mut cmd, dir) = veiled();
+    let file = dir.path().join("export.toml");
+    std::fs::write(&file, "schema_version = 99\n").unwrap();
+
+    cmd.args(["import", file.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "unsupported export schema version",
+        ));
+}
+
+// -- completions command --
+
+#[test]
+fn completions_prints_script_for_supported_shells() {
+    for shell in ["bash", "zsh", "fish"] {
+        let (mut cmd, _dir) = veiled();
+        cmd.args(["completions", shell])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("veiled"))
+            .stderr(predicate::str::is_empty());
+    }
+}
+
+#[test]
+fn completions_rejects_unknown_shell() {
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["completions", "tcsh"]).assert().failure();
+}
+
+// -- search-paths command --
+
+#[test]
+fn search_paths_add_then_list() {
+    let (mut cmd, dir) = veiled();
+    let projects = TempDir::new().unwrap();
+    let canonical = std::fs::canonicalize(projects.path()).unwrap();
+    write_config(&dir, std::path::Path::new("/nonexistent/root"));
+
+    cmd.args(["search-paths", "add"])
+        .arg(projects.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added:"));
+
+    cargo_bin_cmd!("veiled")
+        .env("VEILED_CONFIG_DIR", dir.path())
+        .args(["search-paths", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("/nonexistent/root"))
+        .stdout(predicate::str::contains(
+            canonical.to_string_lossy().as_ref(),
+        ));
+}
+
+#[test]
+fn search_paths_add_is_idempotent() {
+    let (mut cmd, dir) = veiled();
+    let projects = TempDir::new().unwrap();
+    write_config(&dir, &std::fs::canonicalize(projects.path()).unwrap());
+
+    cmd.args(["search-paths", "add"])
+        .arg(projects.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("already a search path"));
+
+    let content = std::fs::read_to_string(dir.path().join("config.toml")).unwrap();
+    let config: toml::Value = toml::from_str(&content).unwrap();
+    assert_eq!(config["search_paths"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn search_paths_add_rejects_missing_directory() {
+    let (mut cmd, _dir) = veiled();
+    cmd.args(["search-paths", "add", "/nonexistent/projects"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no such directory"));
+}
+
+#[test]
+fn search_paths_remove_drops_entry() {
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, std::path::Path::new("/nonexistent/root"));
+
+    cmd.args(["search-paths", "remove", "/nonexistent/root"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed:"));
+
+    cargo_bin_cmd!("veiled")
+        .env("VEILED_CONFIG_DIR", dir.path())
+        .args(["search-paths", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No search paths configured."));
+}
+
+#[test]
+fn search_paths_remove_unknown_path_fails() {
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, std::path::Path::new("/nonexistent/root"));
+
+    cmd.args(["search-paths", "remove", "/nonexistent/other"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("is not a search path"));
+}
+
+// -- ignore command --
+
+#[test]
+fn ignore_add_inside_search_path_then_list() {
+    let (mut cmd, dir) = veiled();
+    let projects = TempDir::new().unwrap();
+    let vendored = projects.path().join("huge-vendored-repo");
+    write_config(&dir, projects.path());
+
+    cmd.args(["ignore", "add"])
+        .arg(&vendored)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Ignored:"))
+        .stderr(predicate::str::contains("warning:").not());
+
+    cargo_bin_cmd!("veiled")
+        .env("VEILED_CONFIG_DIR", dir.path())
+        .args(["ignore", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            vendored.to_string_lossy().as_ref(),
+        ));
+}
+
+#[test]
+fn ignored_candidate_is_stored_collapsed_and_absent_from_next_scan() {
+    let home = TempDir::new().unwrap();
+    let modules = home.path().join("Projects/app/node_modules");
+    std::fs::create_dir_all(&modules).unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, &home.path().join("Projects"));
+
+    cmd.env("HOME", home.path())
+        .args(["ignore", "add"])
+        .arg(&modules)
+        .assert()
+        .success();
+
+    let content = std::fs::read_to_string(dir.path().join("config.toml")).unwrap();
+    assert!(content.contains("\"~/Projects/app/node_modules\""));
+
+    cargo_bin_cmd!("veiled")
+        .env("VEILED_CONFIG_DIR", dir.path())
+        .env("HOME", home.path())
+        .arg("scan")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("node_modules").not());
+}
+
+#[test]
+fn ignore_add_outside_search_paths_warns_but_saves() {
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, std::path::Path::new("/nonexistent/root"));
+
+    cmd.args(["ignore", "add", "/nonexistent/elsewhere"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("outside every search path"));
+
+    let content = std::fs::read_to_string(dir.path().join("config.toml")).unwrap();
+    assert!(content.contains("/nonexistent/elsewhere"));
+}
+
+#[test]
+fn ignore_remove_unknown_path_fails() {
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, std::path::Path::new("/nonexistent/root"));
+
+    cmd.args(["ignore", "remove", "/nonexistent/other"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not an ignored path"));
+}
+
+// -- config dir --
+
+#[test]
+fn config_dir_flag_isolates_profiles() {
+    let (mut cmd, env_dir) = veiled();
+    let profile_a = TempDir::new().unwrap();
+    let profile_b = TempDir::new().unwrap();
+    write_config(&profile_a, std::path::Path::new("/nonexistent/a"));
+    write_config(&profile_b, std::path::Path::new("/nonexistent/b"));
+
+    cmd.arg("--config-dir")
+        .arg(profile_a.path())
+        .args(["search-paths", "add"])
+        .arg(env_dir.path())
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("veiled")
+        .env("VEILED_CONFIG_DIR", env_dir.path())
+        .args(["search-paths", "list", "--config-dir"])
+        .arg(profile_b.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("/nonexistent/b"))
+        .stdout(predicate::str::contains("/nonexistent/a").not());
+
+    let content = std::fs::read_to_string(profile_a.path().join("config.toml")).unwrap();
+    assert!(
+        content.contains(
+            &*std::fs::canonicalize(env_dir.path())
+                .unwrap()
+                .to_string_lossy()
+        )
+    );
+    assert!(!env_dir.path().join("config.toml").exists());
+}
+
+#[test]
+fn config_flag_creates_alternate_file_on_list() {
+    let (mut cmd, dir) = veiled();
+    let profiles = TempDir::new().unwrap();
+    let alt = profiles.path().join("alt.toml");
+    std::fs::write(
+        dir.path().join("registry.json"),
+        r#"{"paths":["/nonexistent/managed/target"]}"#,
+    )
+    .unwrap();
+
+    // Sorting by size reads size_mode, so the config gets loaded.
+    cmd.arg("--config")
+        .arg(&alt)
+        .args(["list", "--sort", "size"])
+        .assert()
+        .success();
+
+    assert!(alt.exists());
+    assert!(!dir.path().join("config.toml").exists());
+}
+
+#[test]
+fn config_flag_reads_file_but_keeps_registry_in_config_dir() {
+    let (mut cmd, dir) = veiled();
+    let profiles = TempDir::new().unwrap();
+    let alt = profiles.path().join("alt.toml");
+    std::fs::write(&alt, "search_paths = [\"/nonexistent/alt\"]\n").unwrap();
+    write_config(&dir, std::path::Path::new("/nonexistent/default"));
+    std::fs::write(
+        dir.path().join("registry.json"),
+        r#"{"paths":["/nonexistent/managed/target"]}"#,
+    )
+    .unwrap();
+
+    cmd.arg("--config")
+        .arg(&alt)
+        .args(["search-paths", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("/nonexistent/alt"))
+        .stdout(predicate::str::contains("/nonexistent/default").not());
+
+    cargo_bin_cmd!("veiled")
+        .env("VEILED_CONFIG_DIR", dir.path())
+        .args(["list", "--config"])
+        .arg(&alt)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("/nonexistent/managed/target"));
+}
+
+#[test]
+fn xdg_config_home_locates_config() {
+    let xdg = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    std::fs::create_dir(xdg.path().join("veiled")).unwrap();
+    std::fs::write(
+        xdg.path().join("veiled/config.toml"),
+        "search_paths = [\"/nonexistent/xdg-projects\"]\n",
+    )
+    .unwrap();
+    let xdg_veiled = || {
+        let mut cmd = cargo_bin_cmd!("veiled");
+        cmd.env_remove("VEILED_CONFIG_DIR")
+            .env("HOME", home.path())
+            .env("XDG_CONFIG_HOME", xdg.path());
+        cmd
+    };
+
+    xdg_veiled()
+        .args(["search-paths", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("/nonexistent/xdg-projects"));
+    xdg_veiled()
+        .args(["add", "--force", "/nonexistent/xdg-out"])
+        .assert()
+        .success();
+
+    let config = std::fs::read_to_string(xdg.path().join("veiled/config.toml")).unwrap();
+    assert!(config.contains("/nonexistent/xdg-out"));
+    assert!(!home.path().join(".config/veiled").exists());
+}
+
+// -- config command --
+
+#[test]
+fn config_validate_accepts_valid_config_with_warnings() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    std::fs::write(
+        dir.path().join("config.toml"),
+        format!(
+            "search_paths = [\"{}\", \"/nonexistent/missing\"]\nignore_paths = []\n",
+            projects.path().display()
+        ),
+    )
+    .unwrap();
+
+    cmd.args(["config", "validate"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Valid:"))
+        .stdout(predicate::str::contains("(1 warning)"))
+        .stderr(predicate::str::contains(
+            "search path /nonexistent/missing does not exist",
+        ));
+}
+
+#[test]
+fn config_validate_quiet_prints_nothing_for_a_valid_config() {
+    let projects = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    write_config(&dir, projects.path());
+
+    cmd.args(["--quiet", "config", "validate"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+}
+
+#[test]
+fn config_validate_rejects_malformed_toml() {
+    let (mut cmd, dir) = veiled();
+    std::fs::write(dir.path().join("config.toml"), "search_paths = [\n").unwrap();
+
+    cmd.args(["config", "validate"])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("failed to parse"))
+        .stderr(predicate::str::contains("is invalid: 1 error"));
+
+    let content = std::fs::read_to_string(dir.path().join("config.toml")).unwrap();
+    assert_eq!(content, "search_paths = [\n");
+}
+
+#[test]
+fn system_config_is_merged_under_user_config() {
+    let system = TempDir::new().unwrap();
+    let system_config = system.path().join("config.toml");
+    std::fs::write(
+        &system_config,
+        "search_paths = [\"/nonexistent/fleet\"]\nauto_update = false\n",
+    )
+    .unwrap();
+    let (mut cmd, dir) = veiled();
+
+    cmd.args(["search-paths", "list"])
+        .env("VEILED_SYSTEM_CONFIG", &system_config)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("/nonexistent/fleet"));
+
+    let user = std::fs::read_to_string(dir.path().join("config.toml")).unwrap();
+    assert!(!user.contains("auto_update"));
+}
+
+#[test]
+fn ignore_add_keeps_system_ignore_paths_and_search_paths() {
+    let system = TempDir::new().unwrap();
+    let fleet = system.path().join("fleet");
+    let src = system.path().join("src");
+    std::fs::create_dir_all(src.join("x")).unwrap();
+    let system_config = system.path().join("config.toml");
+    std::fs::write(
+        &system_config,
+        format!(
+            "search_paths = [{:?}]\nignore_paths = [{:?}]\n",
+            src.to_string_lossy(),
+            fleet.to_string_lossy()
+        ),
+    )
+    .unwrap();
+    let (mut cmd, dir) = veiled();
+
+    cmd.args(["ignore", "add", &src.join("x").to_string_lossy()])
+        .env("VEILED_SYSTEM_CONFIG", &system_config)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("outside every search path").not());
+
+    cargo_bin_cmd!("veiled")
+        .env("VEILED_CONFIG_DIR", dir.path())
+        .env("VEILED_SYSTEM_CONFIG", &system_config)
+        .args(["ignore", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(fleet.to_string_lossy().as_ref()))
+        .stdout(predicate::str::contains(
+            src.join("x").to_string_lossy().as_ref(),
+        ))
+        .stdout(predicate::str::contains("Downloads").not());
+
+    let user = std::fs::read_to_string(dir.path().join("config.toml")).unwrap();
+    assert!(!user.contains("search_paths"));
+}
+
+#[test]
+fn search_paths_remove_rejects_system_search_paths() {
+    let system = TempDir::new().unwrap();
+    let system_config = system.path().join("config.toml");
+    std::fs::write(&system_config, "search_paths = [\"/nonexistent/fleet\"]\n").unwrap();
+    let (mut cmd, _dir) = veiled();
+
+    cmd.args(["search-paths", "remove", "/nonexistent/fleet"])
+        .env("VEILED_SYSTEM_CONFIG", &system_config)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("system config"));
+}
+
+#[test]
+fn config_path_prints_file_in_config_dir() {
+    let (mut cmd, dir) = veiled();
+    let output = cmd.args(["config", "path"]).output().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim_end(),
+        dir.path().join("config.toml").to_string_lossy()
+    );
+}
+
+#[test]
+fn config_edit_creates_default_config_before_opening_editor() {
+    let (mut cmd, dir) = veiled();
+    cmd.args(["config", "edit"])
+        .env_remove("VISUAL")
+        .env("EDITOR", "true")
+        .assert()
+        .success();
+
+    let content = std::fs::read_to_string(dir.path().join("config.toml")).unwrap();
+    assert!(content.contains("search_paths"));
+}
+
+// -- logs command --
+
+#[test]
+fn logs_without_files_prints_friendly_message() {
+    let (mut cmd, _dir) = veiled();
+    cmd.arg("logs")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No daemon logs"))
+        .stderr(predicate::str::contains("Full Disk Access").not());
+}
+
+#[test]
+fn logs_prints_last_lines_of_each_file() {
+    let (mut cmd, dir) = veiled();
+    let stdout_log = (1..=30)
+        .map(|i| format!("run {i}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    std::fs::write(dir.path().join("stdout.log"), stdout_log).unwrap();
+    std::fs::write(dir.path().join("stderr.log"), "warning: oops\n").unwrap();
+
+    cmd.args(["logs", "-n", "3"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("stdout.log <=="))
+        .stdout(predicate::str::contains("run 28\nrun 29\nrun 30"))
+        .stdout(predicate::str::contains("run 27\n").not())
+        .stdout(predicate::str::contains("stderr.log <=="))
+        .stdout(predicate::str::contains("warning: oops"));
+}
+
+// -- watch command --
+
+#[test]
+fn watch_without_search_paths_fails_fast() {
+    let (mut cmd, dir) = veiled();
+    std::fs::write(dir.path().join("config.toml"), "search_paths = []\n").unwrap();
+
+    cmd.arg("watch")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no search paths configured"));
+}
+
+// -- exit codes --
+
+#[test]
+fn unwritable_config_dir_exits_with_config_code() {
+    let config_file = NamedTempFile::new().unwrap();
+    let projects = TempDir::new().unwrap();
+
+    cargo_bin_cmd!("veiled")
+        .env("VEILED_CONFIG_DIR", config_file.path())
+        .args(["search-paths", "add"])
+        .arg(projects.path())
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("failed to open config"));
+}
+
+// -- unknown command --
+
+#[test]
+fn unknown_command_exits_with_error() {
+    let (mut cmd, _dir) = veiled();
+    // clap's usage code, which no veiled error category shares.
+    cmd.arg("foobar").assert().code(2);
+}
+
+// -- doctor command --
+
+#[test]
+fn doctor_json_reports_every_check() {
+    let home = TempDir::new().unwrap();
+    let (mut cmd, dir) = veiled();
+    std::fs::write(dir.path().join("registry.json"), "{not json").unwrap();
+
+    let output = cmd
+        .args(["doctor", "--json"])
+        .env("HOME", home.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    for key in [
+        "fda",
+        "daemon_loaded",
+        "config_ok",
+        "registry_ok",
+        "binary_path_matches",
+        "healthy",
+    ] {
+        assert!(report[key].is_boolean(), "missing {key} in {report}");
+    }
+    assert_eq!(report["registry_ok"], false);
+    assert_eq!(report["config_ok"], true);
+    assert_eq!(report["healthy"], false);
 }